@@ -1,36 +1,101 @@
 use crate::{
+    clipboard::copy_to_clipboard,
+    config::Config,
     icons::Icons,
-    iw::{adapter::Adapter, agent::AgentManager, known_network::KnownNetwork, network::Network},
+    iw::{
+        access_point::generate_passphrase,
+        adapter::Adapter,
+        agent::{AgentManager, AuthRequest},
+        ap_profile::{ApProfile, ApProfileField},
+        dpp::DeviceProvisioning,
+        events::{IwdEvent, IwdEvents, DEVICE_PROVISIONING_INTERFACE, STATION_INTERFACE},
+        known_network::KnownNetwork,
+        network::{connect_error, is_auth_failure, is_valid_passphrase, Network},
+        objects::{DeviceSelection, IwdObjects},
+        profile::{read_passphrase, restore_profile, update_passphrase, EapProfile},
+        wps::{is_aborted, is_valid_pin, SimpleConfiguration, WALK_TIME_SECS},
+    },
+    launcher::Launcher,
+    live::LiveEvent,
     menu::{
-        AdapterMenuOptions, ApMenuOptions, KnownNetworkOptions, MainMenuOptions, Menu,
-        SettingsMenuOptions,
+        AdapterMenuOptions, ApClientsMenuOptions, ApMenuOptions, ApProfileFieldEdit,
+        ApProfileOptions, ApProfilesMenuOptions, ConnectionDetail, ConnectionDetailsOptions,
+        EasyConnectMenuOptions, KeyAction, KnownNetworkOptions, MainMenuOptions, Menu,
+        SavedNetworksMenuOptions, SettingsMenuOptions, WpsMenuOptions,
     },
     notification::NotificationManager,
+    qr::{decode_image, expand_home, image_path_from_command, WifiCredentials, WifiQr},
+    state::{easy_connect_qr_code_path, qr_code_path, AccessPointSettings},
 };
-use anyhow::{anyhow, Context, Error, Result};
-use iwdrs::{modes::Mode, session::Session};
+use anyhow::{anyhow, Context, Result};
+use iwdrs::{error::network::ConnectError, modes::Mode, network::NetworkType};
 use log::{debug, error, info, warn};
 use notify_rust::Timeout;
 use rust_i18n::t;
-use std::{sync::Arc, time::Duration};
-use tokio::time::sleep;
+use std::{
+    borrow::Cow, convert::TryFrom, future::Future, path::PathBuf, pin::Pin, sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    task::{JoinError, JoinHandle},
+    time::{sleep, Instant},
+};
+use zbus::{zvariant::OwnedObjectPath, Connection};
+
+#[derive(Debug, Clone)]
+enum WpsMethod {
+    PushButton,
+    GeneratedPin(String),
+    EnteredPin(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum EasyConnectOutcome {
+    Connected,
+    Finished,
+    /// Provisioning ended without a configuration reaching this device
+    NotConfigured,
+    Failed,
+    Stopped,
+}
+
+/// Passphrase prompt state carried across the attempts of a single connection.
+#[derive(Debug, Default)]
+struct PassphrasePrompt {
+    error_message: Option<String>,
+    requested: bool,
+}
 
 pub struct App {
     pub running: bool,
     pub reset_mode: bool,
     pub back_on_escape: bool,
-    pub session: Arc<Session>,
+    pub connection: Connection,
     pub current_mode: Mode,
+    pub iwd_state_dir: PathBuf,
+    pub ap_profile_dir: PathBuf,
+    pub qr_image_command: Option<String>,
+    pub auth_retries: usize,
+    pub live_menu: bool,
+    pub device_path: OwnedObjectPath,
     adapter: Adapter,
+    events: IwdEvents,
     agent_manager: AgentManager,
     notification_manager: Arc<NotificationManager>,
 }
 
 impl App {
-    pub async fn new(icons: Arc<Icons>, back_on_escape: bool) -> Result<Self> {
+    pub async fn new(
+        icons: Arc<Icons>,
+        config: &Config,
+        device_path: OwnedObjectPath,
+    ) -> Result<Self> {
         let agent_manager = AgentManager::new().await?;
-        let session = agent_manager.session();
-        let adapter = Adapter::new(session.clone()).await?;
+        // Subscribed first, so the changes caused by powering on below are not missed
+        let mut events = IwdEvents::new().await?;
+        let objects = IwdObjects::new().await?;
+        let adapter = Adapter::new(&objects, device_path.clone()).await?;
+        events.watch_device(adapter.path.clone(), device_path.clone());
         let current_mode = adapter.device.mode;
 
         let notification_manager = Arc::new(NotificationManager::new(icons.clone()));
@@ -46,18 +111,44 @@ impl App {
         Ok(Self {
             running: true,
             adapter,
+            events,
             agent_manager,
             notification_manager,
-            session,
+            connection: objects.connection().clone(),
             current_mode,
             reset_mode: false,
-            back_on_escape,
+            back_on_escape: config.back_on_escape(),
+            iwd_state_dir: config.iwd_state_dir(),
+            ap_profile_dir: config.ap_profile_dir(),
+            qr_image_command: config.qr_image_command.clone(),
+            auth_retries: config.auth_retries(),
+            live_menu: config.live_menu(),
+            device_path,
         })
     }
 
+    pub async fn select_device(
+        menu: &Menu,
+        menu_command: &Option<String>,
+        icon_type: &str,
+        spaces: usize,
+        selection: &DeviceSelection,
+    ) -> Result<Option<OwnedObjectPath>> {
+        let objects = IwdObjects::new().await?;
+        let mut devices = objects.matching_devices(selection);
+
+        match devices.len() {
+            0 => Err(anyhow!("No matching Wi-Fi device found")),
+            1 => Ok(devices.pop().map(|device| device.path)),
+            _ => Ok(menu
+                .select_device(menu_command, &devices, icon_type, spaces)?
+                .map(|device| device.path)),
+        }
+    }
+
     pub async fn reset(&mut self, mode: Mode) -> Result<()> {
-        let session = Arc::new(Session::new().await?);
-        let adapter = Adapter::new(session.clone())
+        let objects = IwdObjects::new().await?;
+        let adapter = Adapter::new(&objects, self.device_path.clone())
             .await
             .with_context(|| "Failed to create a new adapter during reset")?;
 
@@ -68,7 +159,7 @@ impl App {
             .with_context(|| format!("Failed to set mode to {mode:?} during reset"))?;
 
         self.adapter = adapter;
-        self.session = session;
+        self.connection = objects.connection().clone();
         self.current_mode = mode;
 
         info!("App state reset with mode: {:?}", self.current_mode);
@@ -91,17 +182,16 @@ impl App {
             self.handle_adapter_options(menu, menu_command, icon_type, spaces)
                 .await?;
             if self.running {
-                self.adapter
-                    .refresh()
+                self.sync_state()
                     .await
-                    .with_context(|| "Failed to refresh adapter state after power-on")?;
+                    .with_context(|| "Failed to update adapter state after power-on")?;
             } else {
                 return Ok(None);
             }
         }
 
         while self.running {
-            self.adapter.refresh().await?;
+            self.sync_state().await?;
 
             match self.adapter.device.mode {
                 Mode::Station => {
@@ -151,23 +241,47 @@ impl App {
         Ok(())
     }
 
-    async fn wait_for_scan_completion(station: &mut crate::iw::station::Station) -> Result<()> {
+    /// Brings the adapter up to date with the changes iwd announced since the last call.
+    async fn sync_state(&mut self) -> Result<()> {
+        for event in self.events.pending().await? {
+            self.apply_event(&event).await;
+        }
+
+        Ok(())
+    }
+
+    async fn apply_event(&mut self, event: &IwdEvent) {
+        if let Err(e) = self.adapter.apply_event(event).await {
+            debug!("Failed to apply iwd event: {e:?}");
+        }
+    }
+
+    /// Applies iwd events until the running scan completes, which reloads the networks.
+    async fn wait_for_scan_completion(&mut self) -> Result<()> {
         const SCAN_TIMEOUT_SECS: u64 = 30;
-        const SCAN_POLL_INTERVAL_MS: u64 = 250;
 
-        let result = tokio::time::timeout(Duration::from_secs(SCAN_TIMEOUT_SECS), async {
-            while station.is_scanning {
-                sleep(Duration::from_millis(SCAN_POLL_INTERVAL_MS)).await;
-                station.refresh().await?;
+        tokio::time::timeout(Duration::from_secs(SCAN_TIMEOUT_SECS), async {
+            self.sync_state().await?;
+
+            while self
+                .adapter
+                .device
+                .station
+                .as_ref()
+                .is_some_and(|station| station.is_scanning)
+            {
+                let event = self
+                    .events
+                    .next()
+                    .await
+                    .ok_or_else(|| anyhow!("iwd events ended during the scan"))?;
+                self.apply_event(&event).await;
             }
-            Ok::<(), Error>(())
-        })
-        .await;
 
-        match result {
-            Ok(inner_result) => inner_result,
-            Err(_) => Err(anyhow!("Station scan timeout exceeded during run loop")),
-        }
+            Ok(())
+        })
+        .await
+        .map_err(|_| anyhow!("Station scan timeout exceeded during run loop"))?
     }
 
     async fn handle_main_options(
@@ -182,18 +296,51 @@ impl App {
             MainMenuOptions::Scan => {
                 self.perform_network_scan().await?;
             }
+            MainMenuOptions::HiddenNetwork => {
+                if let Some(ssid) = self
+                    .perform_hidden_network_connection(menu, menu_command, icon_type)
+                    .await?
+                {
+                    return Ok(Some(ssid));
+                }
+            }
+            MainMenuOptions::QrCode => {
+                if let Some(ssid) = self
+                    .perform_qr_code_connection(menu, menu_command, icon_type)
+                    .await?
+                {
+                    return Ok(Some(ssid));
+                }
+            }
+            MainMenuOptions::Wps => {
+                if let Some(ssid) = self
+                    .handle_wps_menu(menu, menu_command, icon_type, spaces)
+                    .await?
+                {
+                    return Ok(Some(ssid));
+                }
+            }
+            MainMenuOptions::EasyConnect => {
+                self.handle_easy_connect_menu(menu, menu_command, icon_type, spaces)
+                    .await?;
+            }
             MainMenuOptions::Settings => {
                 self.handle_settings_menu(menu, menu_command, icon_type, spaces)
                     .await?;
             }
-            MainMenuOptions::Network(output) => {
+            MainMenuOptions::Network(network) => {
                 if let Some(ssid) = self
-                    .handle_network_selection(menu, menu_command, &output, icon_type, spaces)
+                    .handle_network_selection(menu, menu_command, &network, icon_type, spaces)
                     .await?
                 {
                     return Ok(Some(ssid));
                 }
             }
+            MainMenuOptions::NetworkAction(network, action) => {
+                return self
+                    .perform_network_action(menu, menu_command, &network, action, icon_type)
+                    .await;
+            }
         }
         Ok(None)
     }
@@ -209,28 +356,24 @@ impl App {
         if let Some(ap) = self.adapter.device.access_point.as_mut() {
             match ap_menu_option {
                 ApMenuOptions::StartAp => {
-                    if ap.ssid.is_empty() || ap.psk.is_empty() {
-                        debug!("SSID or Password not set");
-                        if ap.ssid.is_empty() {
-                            if let Some(ssid) = menu.prompt_ap_ssid(menu_command, icon_type) {
-                                ap.set_ssid(ssid);
-                            }
-                        }
-                        if ap.psk.is_empty() {
-                            if let Some(password) =
-                                menu.prompt_ap_passphrase(menu_command, icon_type)
-                            {
-                                ap.set_psk(password);
-                            }
-                        }
-                    }
-                    if !ap.ssid.is_empty() && !ap.psk.is_empty() {
-                        self.perform_ap_start(menu, menu_command, icon_type).await?;
-                    }
+                    self.perform_ap_start(menu, menu_command, icon_type).await?;
                 }
                 ApMenuOptions::StopAp => {
                     self.perform_ap_stop().await?;
                 }
+                ApMenuOptions::ConnectedClients => {
+                    self.handle_ap_clients_menu(menu, menu_command, icon_type, spaces)
+                        .await?;
+                }
+                ApMenuOptions::Share => {
+                    let ssid = ap.ssid.clone();
+                    let payload = ap.share_payload();
+                    self.perform_share(&ssid, payload);
+                }
+                ApMenuOptions::Profiles => {
+                    self.handle_ap_profiles_menu(menu, menu_command, icon_type, spaces)
+                        .await?;
+                }
                 ApMenuOptions::SetSsid => {
                     if let Some(ssid) = menu.prompt_ap_ssid(menu_command, icon_type) {
                         ap.set_ssid(ssid.clone());
@@ -276,12 +419,28 @@ impl App {
     ) -> Result<bool> {
         let mut available_options = vec![];
 
+        let is_in_range = self.adapter.device.station.as_ref().is_some_and(|station| {
+            station
+                .known_networks
+                .iter()
+                .any(|(net, _)| net.name == known_network.name)
+        });
+
         if is_connected {
             available_options.push(KnownNetworkOptions::Disconnect);
-        } else {
+            available_options.push(KnownNetworkOptions::ConnectionDetails);
+        } else if is_in_range {
             available_options.push(KnownNetworkOptions::Connect);
         }
 
+        if !matches!(known_network.network_type, NetworkType::Eap) {
+            available_options.push(KnownNetworkOptions::Share);
+        }
+
+        if matches!(known_network.network_type, NetworkType::Psk) {
+            available_options.push(KnownNetworkOptions::UpdatePassphrase);
+        }
+
         available_options.push(KnownNetworkOptions::ForgetNetwork);
         available_options.push(if known_network.is_autoconnect {
             KnownNetworkOptions::DisableAutoconnect
@@ -315,12 +474,40 @@ impl App {
                     self.perform_forget_network(known_network).await?;
                     Ok(false)
                 }
+                KnownNetworkOptions::Share => {
+                    self.perform_share(
+                        &known_network.name,
+                        known_network.share_payload(&self.iwd_state_dir),
+                    );
+                    Ok(true)
+                }
+                KnownNetworkOptions::UpdatePassphrase => {
+                    self.perform_passphrase_update(
+                        menu,
+                        menu_command,
+                        known_network,
+                        is_connected,
+                        icon_type,
+                    )
+                    .await
+                }
                 KnownNetworkOptions::Disconnect => {
                     if is_connected {
                         self.perform_network_disconnection().await?;
                     }
                     Ok(true)
                 }
+                KnownNetworkOptions::ConnectionDetails => {
+                    self.handle_connection_details(
+                        menu,
+                        menu_command,
+                        &known_network.name,
+                        icon_type,
+                        spaces,
+                    )
+                    .await?;
+                    Ok(self.running)
+                }
                 KnownNetworkOptions::Connect => {
                     if let Some(station) = self.adapter.device.station.as_mut() {
                         if let Some(network) = station
@@ -329,7 +516,13 @@ impl App {
                             .find(|(net, _)| net.name == known_network.name)
                             .map(|(net, _)| net.clone())
                         {
-                            self.perform_known_network_connection(&network).await?;
+                            self.perform_known_network_connection(
+                                menu,
+                                menu_command,
+                                &network,
+                                icon_type,
+                            )
+                            .await?;
                         }
                     }
                     Ok(true)
@@ -346,182 +539,749 @@ impl App {
         }
     }
 
-    async fn handle_settings_menu(
+    async fn handle_connection_details(
         &mut self,
         menu: &Menu,
         menu_command: &Option<String>,
+        network_name: &str,
         icon_type: &str,
         spaces: usize,
     ) -> Result<()> {
-        let mut stay_in_settings_menu = true;
+        loop {
+            let Some(station) = self.adapter.device.station.as_mut() else {
+                return Ok(());
+            };
 
-        while stay_in_settings_menu {
-            self.adapter.refresh().await?;
+            station.refresh_diagnostic().await?;
 
-            if let Some(option) = menu
-                .show_settings_menu(
-                    menu_command,
-                    &self.current_mode,
-                    icon_type,
-                    spaces,
-                    self.back_on_escape,
-                )
+            let Some(diagnostic) = station.diagnostic.as_ref() else {
+                let msg = t!(
+                    "notifications.network.details_unavailable",
+                    network_name = network_name
+                );
+                warn!("{msg}");
+                try_send_notification!(
+                    self.notification_manager,
+                    None,
+                    Some(msg.to_string()),
+                    Some("error"),
+                    None
+                );
+                return Ok(());
+            };
+
+            let details = ConnectionDetail::from_diagnostic(diagnostic);
+
+            match menu
+                .show_connection_details(menu_command, icon_type, spaces, &details, network_name)
                 .await?
             {
-                let should_stay = self
-                    .handle_settings_options(option, menu, menu_command, icon_type, spaces)
-                    .await?;
-
-                if !should_stay {
-                    stay_in_settings_menu = false;
-                }
-            } else {
-                debug!("Exited settings menu");
-                if !self.back_on_escape {
-                    self.running = false;
+                Some(ConnectionDetailsOptions::Copy(detail)) => self.perform_copy_detail(&detail),
+                Some(ConnectionDetailsOptions::Back) => return Ok(()),
+                None => {
+                    debug!("Exited connection details for {network_name}");
+                    if !self.back_on_escape {
+                        self.running = false;
+                    }
+                    return Ok(());
                 }
-                stay_in_settings_menu = false;
             }
         }
-
-        Ok(())
     }
 
-    async fn handle_settings_options(
+    async fn handle_ap_clients_menu(
         &mut self,
-        option: SettingsMenuOptions,
         menu: &Menu,
         menu_command: &Option<String>,
         icon_type: &str,
         spaces: usize,
-    ) -> Result<bool> {
-        match option {
-            SettingsMenuOptions::Back => Ok(false),
-            SettingsMenuOptions::DisableAdapter => {
-                self.perform_adapter_disable(menu, menu_command, icon_type, spaces)
-                    .await?;
-                Ok(false)
-            }
-            SettingsMenuOptions::SwitchMode => {
-                self.perform_mode_switch(menu).await?;
-                self.reset_mode = true;
-                self.running = false;
-                Ok(false)
+    ) -> Result<()> {
+        loop {
+            let Some(ap) = self.adapter.device.access_point.as_mut() else {
+                return Ok(());
+            };
+
+            ap.refresh_clients().await?;
+
+            match menu
+                .show_ap_clients(menu_command, &ap.clients, icon_type, spaces)
+                .await?
+            {
+                Some(ApClientsMenuOptions::Client(client)) => {
+                    let details = ConnectionDetail::from_client(&client);
+
+                    match menu
+                        .show_connection_details(
+                            menu_command,
+                            icon_type,
+                            spaces,
+                            &details,
+                            &client.address,
+                        )
+                        .await?
+                    {
+                        Some(ConnectionDetailsOptions::Copy(detail)) => {
+                            self.perform_copy_detail(&detail)
+                        }
+                        Some(ConnectionDetailsOptions::Back) => {}
+                        None => {
+                            if !self.back_on_escape {
+                                self.running = false;
+                                return Ok(());
+                            }
+                        }
+                    }
+                }
+                Some(ApClientsMenuOptions::Back) => return Ok(()),
+                None => {
+                    debug!("Exited connected clients menu");
+                    if !self.back_on_escape {
+                        self.running = false;
+                    }
+                    return Ok(());
+                }
             }
         }
     }
 
-    async fn handle_adapter_options(
+    async fn handle_ap_profiles_menu(
         &mut self,
         menu: &Menu,
         menu_command: &Option<String>,
         icon_type: &str,
         spaces: usize,
     ) -> Result<()> {
-        if let Some(option) = menu.prompt_enable_adapter(menu_command, icon_type, spaces) {
-            match option {
-                AdapterMenuOptions::PowerOnDevice => {
-                    self.adapter.device.power_on().await?;
-                    self.reset(self.current_mode).await?;
-                    info!("{}", t!("notifications.app.adapter_enabled"));
-                    try_send_notification!(
-                        self.notification_manager,
-                        None,
-                        Some(t!("notifications.app.adapter_enabled").to_string()),
-                        Some("network_wireless"),
-                        None
-                    );
+        loop {
+            let profiles = ApProfile::list(&self.ap_profile_dir)?;
+
+            let ssid = match menu
+                .show_ap_profiles(menu_command, &profiles, icon_type, spaces)
+                .await?
+            {
+                Some(ApProfilesMenuOptions::Create) => {
+                    match self.perform_ap_profile_creation(menu, menu_command, icon_type)? {
+                        Some(ssid) => ssid,
+                        None => continue,
+                    }
+                }
+                Some(ApProfilesMenuOptions::Profile(ssid)) => ssid,
+                Some(ApProfilesMenuOptions::Back) => return Ok(()),
+                None => {
+                    debug!("Exited access point profiles menu");
+                    if !self.back_on_escape {
+                        self.running = false;
+                    }
+                    return Ok(());
                 }
+            };
+
+            self.handle_ap_profile_options(menu, menu_command, &ssid, icon_type, spaces)
+                .await?;
+
+            if !self.running {
+                return Ok(());
             }
-        } else {
-            debug!("{}", t!("notifications.app.adapter_menu_exited"));
-            self.running = false;
         }
-
-        Ok(())
     }
 
-    async fn handle_network_selection(
-        &mut self,
+    fn perform_ap_profile_creation(
+        &self,
         menu: &Menu,
         menu_command: &Option<String>,
-        output: &str,
         icon_type: &str,
-        spaces: usize,
     ) -> Result<Option<String>> {
-        let station = self
-            .adapter
-            .device
-            .station
-            .as_mut()
-            .ok_or_else(|| anyhow!("No station available for network selection"))?;
+        let Some(ssid) = menu.prompt_ap_ssid(menu_command, icon_type) else {
+            return Ok(None);
+        };
 
-        let networks = station
-            .new_networks
-            .iter()
-            .chain(station.known_networks.iter());
+        if ApProfile::path(&self.ap_profile_dir, &ssid).exists() {
+            return Ok(Some(ssid));
+        }
 
-        if let Some((network, _)) =
-            menu.select_network(networks, output.to_string(), icon_type, spaces)
-        {
-            if let Some(ref known_network) = network.known_network {
-                let is_connected = station
-                    .connected_network
-                    .as_ref()
-                    .is_some_and(|cn| cn.name == network.name);
+        let passphrase = match menu.prompt_ap_passphrase(menu_command, icon_type) {
+            Some(passphrase) => passphrase,
+            None => generate_passphrase()?,
+        };
 
-                self.handle_network_menu(
-                    menu,
-                    menu_command,
-                    known_network,
-                    icon_type,
-                    spaces,
-                    is_connected,
-                )
-                .await?;
-                return Ok(None);
-            } else {
-                return self
-                    .perform_new_network_connection(menu, menu_command, &network, icon_type)
-                    .await;
-            }
+        let mut profile = ApProfile::new(&ssid);
+
+        if let Err(e) = ApProfileField::Passphrase.validate(&passphrase) {
+            self.notify_ap_profile_invalid_value(menu, ApProfileField::Passphrase, &e);
+            return Ok(None);
         }
+        profile.set(ApProfileField::Passphrase, Some(passphrase));
 
-        Ok(None)
+        Ok(self.perform_ap_profile_save(&profile).then_some(ssid))
     }
 
-    async fn handle_network_menu(
+    async fn handle_ap_profile_options(
         &mut self,
         menu: &Menu,
         menu_command: &Option<String>,
-        known_network: &KnownNetwork,
+        ssid: &str,
         icon_type: &str,
         spaces: usize,
-        is_connected: bool,
     ) -> Result<()> {
-        let mut stay_in_network_menu = true;
-        let mut network_clone = known_network.clone();
-        let mut current_is_connected = is_connected;
-
-        while stay_in_network_menu {
-            if let Some(station) = self.adapter.device.station.as_mut() {
-                station.refresh().await?;
+        loop {
+            let mut profile = match ApProfile::load(&self.ap_profile_dir, ssid) {
+                Ok(profile) => profile,
+                Err(e) => {
+                    self.notify_ap_profile_error(&e);
+                    return Ok(());
+                }
+            };
 
-                current_is_connected = station
-                    .connected_network
-                    .as_ref()
-                    .is_some_and(|cn| cn.name == network_clone.name);
+            match menu
+                .show_ap_profile_options(menu_command, &profile, icon_type, spaces)
+                .await?
+            {
+                Some(ApProfileOptions::Start) => {
+                    self.perform_ap_profile_start(ssid).await?;
+                    return Ok(());
+                }
+                Some(ApProfileOptions::Edit(field)) => {
+                    let edit = menu.prompt_ap_profile_field(
+                        menu_command,
+                        field,
+                        profile.get(field),
+                        icon_type,
+                    )?;
+
+                    match edit {
+                        Some(ApProfileFieldEdit::Set(value)) => {
+                            if let Err(e) = field.validate(&value) {
+                                self.notify_ap_profile_invalid_value(menu, field, &e);
+                                continue;
+                            }
+                            profile.set(field, Some(value));
+                        }
+                        Some(ApProfileFieldEdit::Clear) => profile.set(field, None),
+                        None => continue,
+                    }
 
-                if let Some((updated_network, _)) = station
-                    .known_networks
-                    .iter()
-                    .find(|(net, _)| net.name == network_clone.name)
-                {
-                    if let Some(ref updated_known_network) = updated_network.known_network {
-                        network_clone = updated_known_network.clone();
-                    } else {
-                        warn!("Network {} is no longer available", network_clone.name);
-                        break;
+                    self.perform_ap_profile_save(&profile);
+                }
+                Some(ApProfileOptions::Delete) => {
+                    match ApProfile::delete(&self.ap_profile_dir, ssid) {
+                        Ok(()) => {
+                            let msg = t!("notifications.device.ap_profile_deleted", ssid = ssid);
+                            info!("{msg}");
+                            try_send_notification!(
+                                self.notification_manager,
+                                None,
+                                Some(msg.to_string()),
+                                Some("forget_network"),
+                                None
+                            );
+                        }
+                        Err(e) => self.notify_ap_profile_error(&e),
+                    }
+                    return Ok(());
+                }
+                Some(ApProfileOptions::Back) => return Ok(()),
+                None => {
+                    debug!("Exited access point profile {ssid}");
+                    if !self.back_on_escape {
+                        self.running = false;
+                    }
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn perform_ap_profile_save(&self, profile: &ApProfile) -> bool {
+        match profile.save(&self.ap_profile_dir) {
+            Ok(path) => {
+                let msg = t!("notifications.device.ap_profile_saved", ssid = profile.ssid);
+                info!("{msg} ({})", path.display());
+                try_send_notification!(
+                    self.notification_manager,
+                    None,
+                    Some(msg.to_string()),
+                    Some("ok"),
+                    None
+                );
+                true
+            }
+            Err(e) => {
+                self.notify_ap_profile_error(&e);
+                false
+            }
+        }
+    }
+
+    async fn perform_ap_profile_start(&mut self, ssid: &str) -> Result<()> {
+        let Some(ap) = self.adapter.device.access_point.as_mut() else {
+            let msg = t!("notifications.device.no_access_point_available").to_string();
+            error!("{msg}");
+            try_send_notification!(
+                self.notification_manager,
+                None,
+                Some(msg),
+                Some("error"),
+                None
+            );
+            return Ok(());
+        };
+
+        if ap.has_started {
+            debug!("Access point is already started");
+            return Ok(());
+        }
+
+        if let Err(e) = ap.start_profile(ssid).await {
+            self.notify_ap_profile_error(&e);
+            return Ok(());
+        }
+
+        let msg = t!("notifications.device.access_point_started").to_string();
+        info!("{msg}");
+        try_send_notification!(
+            self.notification_manager,
+            None,
+            Some(msg),
+            Some("access_point"),
+            None
+        );
+
+        self.sync_state().await
+    }
+
+    fn notify_ap_profile_invalid_value(
+        &self,
+        menu: &Menu,
+        field: ApProfileField,
+        error: &anyhow::Error,
+    ) {
+        let msg = t!(
+            "notifications.device.ap_profile_invalid_value",
+            field = menu.get_ap_profile_field_text(field),
+            error_message = error.to_string()
+        );
+        warn!("{msg}");
+        try_send_notification!(
+            self.notification_manager,
+            None,
+            Some(msg.to_string()),
+            Some("error"),
+            None
+        );
+    }
+
+    fn notify_ap_profile_error(&self, error: &anyhow::Error) {
+        let msg = t!(
+            "notifications.device.ap_profile_error",
+            error_message = format!("{error:#}")
+        );
+        error!("{msg}");
+        try_send_notification!(
+            self.notification_manager,
+            None,
+            Some(msg.to_string()),
+            Some("error"),
+            None
+        );
+    }
+
+    fn perform_share(&self, ssid: &str, payload: Result<String>) {
+        let result = payload.and_then(|payload| {
+            let qr = WifiQr::new(&payload)?;
+
+            let path = qr_code_path(ssid)
+                .ok_or_else(|| anyhow!("Unable to determine the iwmenu state directory"))?;
+            qr.save_png(&path)?;
+
+            Ok(path)
+        });
+
+        match result {
+            Ok(path) => {
+                let msg = t!(
+                    "notifications.network.share_ready",
+                    network_name = ssid,
+                    path = path.display().to_string()
+                );
+                info!("{msg}");
+                let _ = self
+                    .notification_manager
+                    .send_image_notification(
+                        None,
+                        Some(msg.to_string()),
+                        &path,
+                        Some(Timeout::Milliseconds(15000)),
+                    )
+                    .map_err(|e| eprintln!("Notification failed: {e}"));
+            }
+            Err(e) => {
+                let msg = t!(
+                    "notifications.network.share_failed",
+                    network_name = ssid,
+                    error_message = format!("{e:#}")
+                );
+                error!("{msg}");
+                try_send_notification!(
+                    self.notification_manager,
+                    None,
+                    Some(msg.to_string()),
+                    Some("error"),
+                    None
+                );
+            }
+        }
+    }
+
+    fn perform_copy_detail(&self, detail: &ConnectionDetail) {
+        match copy_to_clipboard(&detail.value) {
+            Ok(()) => {
+                let msg = t!(
+                    "notifications.network.copied_to_clipboard",
+                    field = detail.label
+                );
+                info!("{msg}");
+                try_send_notification!(
+                    self.notification_manager,
+                    None,
+                    Some(msg.to_string()),
+                    Some("ok"),
+                    None
+                );
+            }
+            Err(e) => {
+                let msg = t!(
+                    "notifications.network.copy_failed",
+                    error_message = e.to_string()
+                );
+                error!("{msg}");
+                try_send_notification!(
+                    self.notification_manager,
+                    None,
+                    Some(msg.to_string()),
+                    Some("error"),
+                    None
+                );
+            }
+        }
+    }
+
+    async fn handle_settings_menu(
+        &mut self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        icon_type: &str,
+        spaces: usize,
+    ) -> Result<()> {
+        let mut stay_in_settings_menu = true;
+
+        while stay_in_settings_menu {
+            self.sync_state().await?;
+
+            if let Some(option) = menu
+                .show_settings_menu(
+                    menu_command,
+                    &self.current_mode,
+                    icon_type,
+                    spaces,
+                    self.back_on_escape,
+                )
+                .await?
+            {
+                let should_stay = self
+                    .handle_settings_options(option, menu, menu_command, icon_type, spaces)
+                    .await?;
+
+                if !should_stay {
+                    stay_in_settings_menu = false;
+                }
+            } else {
+                debug!("Exited settings menu");
+                if !self.back_on_escape {
+                    self.running = false;
+                }
+                stay_in_settings_menu = false;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_settings_options(
+        &mut self,
+        option: SettingsMenuOptions,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        icon_type: &str,
+        spaces: usize,
+    ) -> Result<bool> {
+        match option {
+            SettingsMenuOptions::Back => Ok(false),
+            SettingsMenuOptions::SavedNetworks => {
+                self.handle_saved_networks_menu(menu, menu_command, icon_type, spaces)
+                    .await?;
+                Ok(self.running)
+            }
+            SettingsMenuOptions::DisableAdapter => {
+                self.perform_adapter_disable(menu, menu_command, icon_type, spaces)
+                    .await?;
+                Ok(false)
+            }
+            SettingsMenuOptions::SwitchMode => {
+                self.perform_mode_switch(menu).await?;
+                self.reset_mode = true;
+                self.running = false;
+                Ok(false)
+            }
+        }
+    }
+
+    async fn handle_saved_networks_menu(
+        &mut self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        icon_type: &str,
+        spaces: usize,
+    ) -> Result<()> {
+        while self.running {
+            let saved_networks = KnownNetwork::all(&self.connection).await?;
+
+            match menu
+                .show_saved_networks_menu(
+                    menu_command,
+                    &saved_networks,
+                    icon_type,
+                    spaces,
+                    self.back_on_escape,
+                )
+                .await?
+            {
+                Some(SavedNetworksMenuOptions::Network(known_network)) => {
+                    self.handle_saved_network_menu(
+                        menu,
+                        menu_command,
+                        known_network,
+                        icon_type,
+                        spaces,
+                    )
+                    .await?;
+                }
+                Some(SavedNetworksMenuOptions::Back) => break,
+                None => {
+                    debug!("Exited saved networks menu");
+                    if !self.back_on_escape {
+                        self.running = false;
+                    }
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_saved_network_menu(
+        &mut self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        known_network: KnownNetwork,
+        icon_type: &str,
+        spaces: usize,
+    ) -> Result<()> {
+        let mut network_clone = known_network;
+
+        while self.running {
+            let is_connected = self
+                .adapter
+                .device
+                .station
+                .as_ref()
+                .and_then(|station| station.connected_network.as_ref())
+                .is_some_and(|cn| cn.name == network_clone.name);
+
+            let should_stay = self
+                .handle_known_network_options(
+                    menu,
+                    menu_command,
+                    &network_clone,
+                    icon_type,
+                    spaces,
+                    is_connected,
+                )
+                .await?;
+
+            if !should_stay {
+                break;
+            }
+
+            self.sync_state().await?;
+
+            match KnownNetwork::all(&self.connection)
+                .await?
+                .into_iter()
+                .find(|net| net.name == network_clone.name)
+            {
+                Some(updated_network) => network_clone = updated_network,
+                None => {
+                    warn!("Network {} is no longer saved", network_clone.name);
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_adapter_options(
+        &mut self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        icon_type: &str,
+        spaces: usize,
+    ) -> Result<()> {
+        if let Some(option) = menu.prompt_enable_adapter(menu_command, icon_type, spaces) {
+            match option {
+                AdapterMenuOptions::PowerOnDevice => {
+                    self.adapter.device.power_on().await?;
+                    self.reset(self.current_mode).await?;
+                    info!("{}", t!("notifications.app.adapter_enabled"));
+                    try_send_notification!(
+                        self.notification_manager,
+                        None,
+                        Some(t!("notifications.app.adapter_enabled").to_string()),
+                        Some("network_wireless"),
+                        None
+                    );
+                }
+            }
+        } else {
+            debug!("{}", t!("notifications.app.adapter_menu_exited"));
+            self.running = false;
+        }
+
+        Ok(())
+    }
+
+    async fn handle_network_selection(
+        &mut self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        network: &Network,
+        icon_type: &str,
+        spaces: usize,
+    ) -> Result<Option<String>> {
+        let station = self
+            .adapter
+            .device
+            .station
+            .as_mut()
+            .ok_or_else(|| anyhow!("No station available for network selection"))?;
+
+        if let Some(ref known_network) = network.known_network {
+            let is_connected = station
+                .connected_network
+                .as_ref()
+                .is_some_and(|cn| cn.name == network.name);
+
+            self.handle_network_menu(
+                menu,
+                menu_command,
+                known_network,
+                icon_type,
+                spaces,
+                is_connected,
+            )
+            .await?;
+            Ok(None)
+        } else {
+            self.perform_new_network_connection(menu, menu_command, network, icon_type)
+                .await
+        }
+    }
+
+    /// Runs an action bound to a custom launcher key on a network of the main menu. Actions
+    /// that do not apply to the network, such as forgetting an unknown one, are ignored.
+    async fn perform_network_action(
+        &mut self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        network: &Network,
+        action: KeyAction,
+        icon_type: &str,
+    ) -> Result<Option<String>> {
+        let is_connected = self
+            .adapter
+            .device
+            .station
+            .as_ref()
+            .and_then(|station| station.connected_network.as_ref())
+            .is_some_and(|cn| cn.name == network.name);
+
+        match (action, &network.known_network) {
+            (KeyAction::Scan, _) => self.perform_network_scan().await?,
+            (KeyAction::Connect, _) if is_connected => {}
+            (KeyAction::Connect, Some(_)) => {
+                return self
+                    .perform_known_network_connection(menu, menu_command, network, icon_type)
+                    .await;
+            }
+            (KeyAction::Connect, None) => {
+                return self
+                    .perform_new_network_connection(menu, menu_command, network, icon_type)
+                    .await;
+            }
+            (KeyAction::Disconnect, _) if is_connected => {
+                self.perform_network_disconnection().await?;
+            }
+            (KeyAction::Forget, Some(known_network)) => {
+                self.perform_forget_network(known_network).await?;
+            }
+            (KeyAction::Autoconnect, Some(known_network)) => {
+                self.perform_toggle_autoconnect(known_network, !known_network.is_autoconnect)
+                    .await?;
+            }
+            (KeyAction::Share, Some(known_network)) => {
+                self.perform_share(
+                    &known_network.name,
+                    known_network.share_payload(&self.iwd_state_dir),
+                );
+            }
+            _ => debug!("{} does not apply to {}", action.name(), network.name),
+        }
+
+        Ok(None)
+    }
+
+    async fn handle_network_menu(
+        &mut self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        known_network: &KnownNetwork,
+        icon_type: &str,
+        spaces: usize,
+        is_connected: bool,
+    ) -> Result<()> {
+        let mut stay_in_network_menu = true;
+        let mut network_clone = known_network.clone();
+        let mut current_is_connected = is_connected;
+
+        while stay_in_network_menu {
+            self.sync_state().await?;
+
+            if let Some(station) = self.adapter.device.station.as_mut() {
+                current_is_connected = station
+                    .connected_network
+                    .as_ref()
+                    .is_some_and(|cn| cn.name == network_clone.name);
+
+                if let Some((updated_network, _)) = station
+                    .known_networks
+                    .iter()
+                    .find(|(net, _)| net.name == network_clone.name)
+                {
+                    if let Some(ref updated_known_network) = updated_network.known_network {
+                        network_clone = updated_known_network.clone();
+                    } else {
+                        warn!("Network {} is no longer available", network_clone.name);
+                        break;
                     }
                 } else {
                     warn!("Network {} is no longer available", network_clone.name);
@@ -529,47 +1289,840 @@ impl App {
                 }
             }
 
-            let should_stay = self
-                .handle_known_network_options(
-                    menu,
-                    menu_command,
-                    &network_clone,
-                    icon_type,
-                    spaces,
-                    current_is_connected,
+            let should_stay = self
+                .handle_known_network_options(
+                    menu,
+                    menu_command,
+                    &network_clone,
+                    icon_type,
+                    spaces,
+                    current_is_connected,
+                )
+                .await?;
+
+            if !should_stay {
+                stay_in_network_menu = false;
+            }
+
+            self.sync_state().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn perform_known_network_connection(
+        &mut self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        network: &Network,
+        icon_type: &str,
+    ) -> Result<Option<String>> {
+        info!(target: "network", "Connecting to known network: {}", network.name);
+
+        self.perform_network_connection(menu, menu_command, network, icon_type)
+            .await
+    }
+
+    async fn perform_new_network_connection(
+        &mut self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        network: &Network,
+        icon_type: &str,
+    ) -> Result<Option<String>> {
+        info!(target: "network", "Connecting to new network: {}", network.name);
+
+        self.perform_network_connection(menu, menu_command, network, icon_type)
+            .await
+    }
+
+    async fn perform_network_connection(
+        &mut self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        network: &Network,
+        icon_type: &str,
+    ) -> Result<Option<String>> {
+        let mut result = self
+            .connect_with_retries(menu, menu_command, || network.connect(), icon_type)
+            .await;
+
+        let needs_provisioning = network.network_type == NetworkType::Eap
+            && result
+                .as_ref()
+                .err()
+                .and_then(connect_error)
+                .is_some_and(|e| matches!(e, ConnectError::NotConfigured));
+
+        if needs_provisioning
+            && self
+                .perform_eap_provisioning(menu, menu_command, network, icon_type)
+                .await?
+        {
+            result = self
+                .connect_with_retries(menu, menu_command, || network.connect(), icon_type)
+                .await;
+        }
+
+        self.handle_connection_result(&network.name, result).await
+    }
+
+    async fn perform_hidden_network_connection(
+        &mut self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        icon_type: &str,
+    ) -> Result<Option<String>> {
+        let Some(ssid) = menu.prompt_hidden_network_ssid(menu_command, icon_type) else {
+            return Ok(None);
+        };
+
+        let station = self
+            .adapter
+            .device
+            .station
+            .as_ref()
+            .ok_or_else(|| anyhow!("No station available for hidden network connection"))?;
+
+        info!(target: "network", "Connecting to hidden network: {ssid}");
+
+        let result = self
+            .connect_with_retries(
+                menu,
+                menu_command,
+                || station.connect_hidden_network(&ssid),
+                icon_type,
+            )
+            .await;
+
+        self.handle_connection_result(&ssid, result).await
+    }
+
+    async fn perform_qr_code_connection(
+        &mut self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        icon_type: &str,
+    ) -> Result<Option<String>> {
+        let path = match &self.qr_image_command {
+            Some(command) => image_path_from_command(command),
+            None => match menu.prompt_qr_image_path(menu_command, icon_type) {
+                Some(path) => Ok(expand_home(&path)),
+                None => return Ok(None),
+            },
+        };
+
+        let credentials = match path.and_then(|path| WifiCredentials::from_image(&path)) {
+            Ok(credentials) => credentials,
+            Err(e) => {
+                let msg = t!(
+                    "notifications.network.qr_code_failed",
+                    error_message = format!("{e:#}")
+                );
+                error!("{msg}");
+                try_send_notification!(
+                    self.notification_manager,
+                    None,
+                    Some(msg.to_string()),
+                    Some("error"),
+                    None
+                );
+                return Ok(None);
+            }
+        };
+
+        let ssid = credentials.ssid.as_str();
+
+        let station = self
+            .adapter
+            .device
+            .station
+            .as_ref()
+            .ok_or_else(|| anyhow!("No station available for network connection"))?;
+
+        let network = station
+            .known_networks
+            .iter()
+            .chain(station.new_networks.iter())
+            .find(|(network, _)| network.name == ssid)
+            .map(|(network, _)| network.clone());
+
+        let connection: Pin<Box<dyn Future<Output = Result<()>> + '_>> = match &network {
+            Some(network) => {
+                info!(target: "network", "Connecting to network from QR code: {ssid}");
+                Box::pin(network.connect())
+            }
+            None if credentials.hidden => {
+                info!(target: "network", "Connecting to hidden network from QR code: {ssid}");
+                Box::pin(station.connect_hidden_network(ssid))
+            }
+            None => {
+                let msg = t!("notifications.network.not_found", network_name = ssid);
+                warn!("{msg}");
+                try_send_notification!(
+                    self.notification_manager,
+                    None,
+                    Some(msg.to_string()),
+                    Some("error"),
+                    None
+                );
+                return Ok(None);
+            }
+        };
+
+        let result = Self::connect_with_passphrase(
+            &self.agent_manager,
+            connection,
+            credentials.passphrase.clone(),
+        )
+        .await;
+
+        self.handle_connection_result(&credentials.ssid, result)
+            .await
+    }
+
+    /// Answers the passphrase request with `passphrase` instead of prompting for it.
+    async fn connect_with_passphrase<F>(
+        agent_manager: &AgentManager,
+        connection: F,
+        mut passphrase: Option<String>,
+    ) -> Result<()>
+    where
+        F: Future<Output = Result<()>>,
+    {
+        tokio::pin!(connection);
+
+        loop {
+            tokio::select! {
+                result = &mut connection => return result,
+                Some(request) = agent_manager.next_request() => {
+                    debug!("Authentication requested: {request:?}");
+                    match (&request, passphrase.take()) {
+                        (AuthRequest::Passphrase { .. }, Some(passphrase)) => {
+                            agent_manager.send_passkey(passphrase)?
+                        }
+                        _ => agent_manager.cancel_auth()?,
+                    }
+                }
+            }
+        }
+    }
+
+    async fn handle_wps_menu(
+        &mut self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        icon_type: &str,
+        spaces: usize,
+    ) -> Result<Option<String>> {
+        let wps = match self.adapter.device.simple_configuration().await {
+            Ok(wps) => wps,
+            Err(e) => {
+                self.notify_wps_failed(&e);
+                return Ok(None);
+            }
+        };
+
+        let method = match menu.show_wps_menu(menu_command, icon_type, spaces).await? {
+            Some(WpsMenuOptions::Back) => return Ok(None),
+            None => {
+                debug!("Exited WPS menu");
+                if !self.back_on_escape {
+                    self.running = false;
+                }
+                return Ok(None);
+            }
+            Some(WpsMenuOptions::PushButton) => WpsMethod::PushButton,
+            Some(WpsMenuOptions::GeneratePin) => match wps.generate_pin().await {
+                Ok(pin) => WpsMethod::GeneratedPin(pin),
+                Err(e) => {
+                    self.notify_wps_failed(&e);
+                    return Ok(None);
+                }
+            },
+            Some(WpsMenuOptions::EnterPin) => {
+                let Some(pin) = menu.prompt_wps_pin(menu_command, icon_type) else {
+                    return Ok(None);
+                };
+
+                if !is_valid_pin(&pin) {
+                    let msg = t!("notifications.wps.invalid_pin");
+                    warn!("{msg}");
+                    try_send_notification!(
+                        self.notification_manager,
+                        None,
+                        Some(msg.to_string()),
+                        Some("error"),
+                        None
+                    );
+                    return Ok(None);
+                }
+
+                WpsMethod::EnteredPin(pin)
+            }
+        };
+
+        self.perform_wps_connection(menu, menu_command, icon_type, spaces, wps, method)
+            .await
+    }
+
+    /// Shows a stop entry while `task` runs. Returns the output of the task once it finishes,
+    /// closing the entry, or `None` when the entry is picked or dismissed first.
+    async fn run_with_stop_menu<T>(
+        menu: &Menu,
+        menu_command: &Option<String>,
+        hint: &str,
+        icon_type: &str,
+        spaces: usize,
+        task: &mut JoinHandle<T>,
+    ) -> Option<Result<T, JoinError>> {
+        const CLOSE_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+        const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+        let mut stop_menu = {
+            let menu = menu.clone();
+            let menu_command = menu_command.clone();
+            let hint = hint.to_string();
+            let icon_type = icon_type.to_string();
+
+            tokio::task::spawn_blocking(move || {
+                if let Err(e) = menu.show_stop_menu(&menu_command, &hint, &icon_type, spaces) {
+                    warn!("{e:#}");
+                }
+            })
+        };
+
+        let output = tokio::select! {
+            output = &mut *task => output,
+            _ = &mut stop_menu => return None,
+        };
+
+        // The task may finish before the launcher is spawned, so closing is retried until the
+        // menu returns, giving up on a launcher that does not exit
+        let deadline = Instant::now() + CLOSE_TIMEOUT;
+        while !stop_menu.is_finished() {
+            if Instant::now() >= deadline {
+                warn!("The stop menu did not close within {CLOSE_TIMEOUT:?}");
+                break;
+            }
+            Launcher::close();
+            sleep(CLOSE_RETRY_INTERVAL).await;
+        }
+
+        Some(output)
+    }
+
+    async fn perform_wps_connection(
+        &mut self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        icon_type: &str,
+        spaces: usize,
+        wps: SimpleConfiguration,
+        method: WpsMethod,
+    ) -> Result<Option<String>> {
+        let (hint, notification_id, countdown) = match &method {
+            WpsMethod::PushButton => {
+                let notification_id = try_send_notification_with_id!(
+                    self.notification_manager,
+                    None,
+                    Some(
+                        t!(
+                            "notifications.wps.push_button_countdown",
+                            seconds = WALK_TIME_SECS
+                        )
+                        .to_string()
+                    ),
+                    Some("wps"),
+                    Some(Timeout::Never)
+                );
+                let countdown = notification_id.map(|id| {
+                    tokio::spawn(Self::run_wps_countdown(
+                        self.notification_manager.clone(),
+                        id,
+                    ))
+                });
+
+                (
+                    t!("menus.wps.push_button_waiting_hint"),
+                    notification_id,
+                    countdown,
+                )
+            }
+            WpsMethod::GeneratedPin(pin) => {
+                let notification_id = try_send_notification_with_id!(
+                    self.notification_manager,
+                    None,
+                    Some(t!("notifications.wps.pin_started", pin = pin).to_string()),
+                    Some("wps"),
+                    Some(Timeout::Never)
+                );
+
+                (
+                    t!("menus.wps.pin_waiting_hint", pin = pin),
+                    notification_id,
+                    None,
+                )
+            }
+            WpsMethod::EnteredPin(_) => (t!("menus.wps.waiting_hint"), None, None),
+        };
+
+        let mut connection = {
+            let wps = wps.clone();
+            tokio::spawn(async move {
+                match method {
+                    WpsMethod::PushButton => wps.push_button().await,
+                    WpsMethod::GeneratedPin(pin) | WpsMethod::EnteredPin(pin) => {
+                        wps.start_pin(&pin).await
+                    }
+                }
+            })
+        };
+
+        let finished = Self::run_with_stop_menu(
+            menu,
+            menu_command,
+            &hint,
+            icon_type,
+            spaces,
+            &mut connection,
+        )
+        .await;
+
+        let result = match finished {
+            Some(result) => result,
+            None => {
+                if let Err(e) = wps.cancel().await {
+                    warn!("{e:#}");
+                }
+                connection.await
+            }
+        }
+        .context("WPS connection task failed")?;
+
+        if let Some(countdown) = countdown {
+            countdown.abort();
+        }
+        if let Some(id) = notification_id {
+            let _ = self.notification_manager.close_notification(id);
+        }
+
+        match result {
+            Ok(()) => {
+                self.sync_state().await?;
+
+                let network_name = self
+                    .adapter
+                    .device
+                    .station
+                    .as_ref()
+                    .and_then(|station| station.connected_network.as_ref())
+                    .map(|network| network.name.clone())
+                    .unwrap_or_default();
+
+                let msg = t!("notifications.wps.connected", network_name = network_name);
+                info!("{msg}");
+                try_send_notification!(
+                    self.notification_manager,
+                    None,
+                    Some(msg.to_string()),
+                    Some("connected"),
+                    None
+                );
+
+                Ok(Some(network_name))
+            }
+            Err(e) if is_aborted(&e) => {
+                let msg = t!("notifications.wps.canceled");
+                info!("{msg}");
+                try_send_notification!(
+                    self.notification_manager,
+                    None,
+                    Some(msg.to_string()),
+                    Some("wps"),
+                    None
+                );
+                Ok(None)
+            }
+            Err(e) => {
+                self.notify_wps_failed(&e);
+                Ok(None)
+            }
+        }
+    }
+
+    async fn run_wps_countdown(notification_manager: Arc<NotificationManager>, id: u32) {
+        for seconds in (0..WALK_TIME_SECS).rev() {
+            sleep(Duration::from_secs(1)).await;
+
+            let msg = t!("notifications.wps.push_button_countdown", seconds = seconds);
+            if notification_manager.update_notification(id, &msg).is_err() {
+                return;
+            }
+        }
+    }
+
+    fn notify_wps_failed(&self, error: &anyhow::Error) {
+        let msg = t!(
+            "notifications.wps.failed",
+            error_message = format!("{error:#}")
+        );
+        error!("{msg}");
+        try_send_notification!(
+            self.notification_manager,
+            None,
+            Some(msg.to_string()),
+            Some("error"),
+            None
+        );
+    }
+
+    async fn handle_easy_connect_menu(
+        &mut self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        icon_type: &str,
+        spaces: usize,
+    ) -> Result<()> {
+        // Configuring another device shares the network this one is connected to
+        let can_configure = self
+            .adapter
+            .device
+            .station
+            .as_ref()
+            .is_some_and(|station| station.connected_network.is_some());
+
+        match menu
+            .show_easy_connect_menu(menu_command, can_configure, icon_type, spaces)
+            .await?
+        {
+            Some(EasyConnectMenuOptions::Enrollee) => {
+                self.perform_easy_connect_enrollee(menu, menu_command, icon_type, spaces)
+                    .await
+            }
+            Some(EasyConnectMenuOptions::Configurator) => {
+                self.perform_easy_connect_configurator(menu, menu_command, icon_type, spaces)
+                    .await
+            }
+            Some(EasyConnectMenuOptions::Back) => Ok(()),
+            None => {
+                debug!("Exited Easy Connect menu");
+                if !self.back_on_escape {
+                    self.running = false;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    async fn start_easy_connect(&self) -> Result<(DeviceProvisioning, IwdEvents)> {
+        let provisioning = self.adapter.device.provisioning().await?;
+
+        // Subscribe before starting so no progress signal is missed
+        let mut events = IwdEvents::new().await?;
+        events.watch_device(self.adapter.path.clone(), self.adapter.device.path.clone());
+
+        Ok((provisioning, events))
+    }
+
+    async fn perform_easy_connect_enrollee(
+        &mut self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        icon_type: &str,
+        spaces: usize,
+    ) -> Result<()> {
+        let started = match self.start_easy_connect().await {
+            Ok((provisioning, events)) => provisioning
+                .start_enrollee()
+                .await
+                .map(|uri| (provisioning, events, uri)),
+            Err(e) => Err(e),
+        };
+
+        let (provisioning, events, uri) = match started {
+            Ok(started) => started,
+            Err(e) => {
+                self.notify_easy_connect_failed(&e);
+                return Ok(());
+            }
+        };
+
+        let saved = WifiQr::new(&uri).and_then(|qr| {
+            let path = easy_connect_qr_code_path()
+                .ok_or_else(|| anyhow!("Unable to determine the iwmenu state directory"))?;
+            qr.save_png(&path)?;
+
+            Ok(path)
+        });
+
+        let notification_id = match saved {
+            Ok(path) => self
+                .notification_manager
+                .send_image_notification(
+                    None,
+                    Some(t!("notifications.easy_connect.enrollee_started").to_string()),
+                    &path,
+                    Some(Timeout::Never),
                 )
-                .await?;
+                .map_err(|e| eprintln!("Notification failed: {e}"))
+                .ok(),
+            Err(e) => {
+                self.notify_easy_connect_failed(&e);
+                if let Err(e) = provisioning.stop().await {
+                    warn!("{e:#}");
+                }
+                return Ok(());
+            }
+        };
 
-            if !should_stay {
-                stay_in_network_menu = false;
+        let outcome = self
+            .wait_for_easy_connect(
+                menu,
+                menu_command,
+                icon_type,
+                spaces,
+                &provisioning,
+                events,
+                true,
+            )
+            .await;
+
+        if let Some(id) = notification_id {
+            let _ = self.notification_manager.close_notification(id);
+        }
+
+        self.report_easy_connect_outcome(outcome).await
+    }
+
+    async fn perform_easy_connect_configurator(
+        &mut self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        icon_type: &str,
+        spaces: usize,
+    ) -> Result<()> {
+        let Some(input) = menu.prompt_dpp_uri(menu_command, icon_type) else {
+            return Ok(());
+        };
+
+        let uri = if input.starts_with("DPP:") {
+            Ok(input)
+        } else {
+            decode_image(&expand_home(&input), "DPP:")
+        };
+
+        let started = match (uri, self.start_easy_connect().await) {
+            (Ok(uri), Ok((provisioning, events))) => provisioning
+                .configure_enrollee(&uri)
+                .await
+                .map(|()| (provisioning, events)),
+            (Err(e), _) | (_, Err(e)) => Err(e),
+        };
+
+        let (provisioning, events) = match started {
+            Ok(started) => started,
+            Err(e) => {
+                self.notify_easy_connect_failed(&e);
+                return Ok(());
             }
+        };
 
-            if let Some(station) = self.adapter.device.station.as_mut() {
-                station.refresh().await?;
+        let msg = t!("notifications.easy_connect.configurator_started");
+        info!("{msg}");
+        try_send_notification!(
+            self.notification_manager,
+            None,
+            Some(msg.to_string()),
+            Some("easy_connect"),
+            None
+        );
+
+        let outcome = self
+            .wait_for_easy_connect(
+                menu,
+                menu_command,
+                icon_type,
+                spaces,
+                &provisioning,
+                events,
+                false,
+            )
+            .await;
+
+        self.report_easy_connect_outcome(outcome).await
+    }
+
+    /// Keeps a stop entry open while Easy Connect runs and closes it once iwd reports the end.
+    #[allow(clippy::too_many_arguments)]
+    async fn wait_for_easy_connect(
+        &self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        icon_type: &str,
+        spaces: usize,
+        provisioning: &DeviceProvisioning,
+        events: IwdEvents,
+        enrollee: bool,
+    ) -> EasyConnectOutcome {
+        let mut watcher = tokio::spawn(Self::watch_easy_connect(
+            events,
+            self.notification_manager.clone(),
+            enrollee,
+        ));
+
+        let hint = t!("menus.easy_connect.waiting_hint");
+
+        let finished =
+            Self::run_with_stop_menu(menu, menu_command, &hint, icon_type, spaces, &mut watcher)
+                .await;
+
+        if let Some(outcome) = finished {
+            return outcome.unwrap_or(EasyConnectOutcome::Stopped);
+        }
+
+        watcher.abort();
+        if let Err(e) = provisioning.stop().await {
+            warn!("{e:#}");
+        }
+
+        EasyConnectOutcome::Stopped
+    }
+
+    async fn watch_easy_connect(
+        mut events: IwdEvents,
+        notification_manager: Arc<NotificationManager>,
+        enrollee: bool,
+    ) -> EasyConnectOutcome {
+        // iwd stops provisioning before connecting with the received configuration
+        const CONNECT_GRACE: Duration = Duration::from_secs(5);
+
+        let mut provisioning_stopped = false;
+        let mut connecting = false;
+
+        loop {
+            let event = if provisioning_stopped && !connecting {
+                match tokio::time::timeout(CONNECT_GRACE, events.next()).await {
+                    Ok(event) => event,
+                    Err(_) if enrollee => break EasyConnectOutcome::NotConfigured,
+                    Err(_) => break EasyConnectOutcome::Finished,
+                }
+            } else {
+                events.next().await
+            };
+
+            let Some(event) = event else {
+                break EasyConnectOutcome::Stopped;
+            };
+
+            if event
+                .changed_property(DEVICE_PROVISIONING_INTERFACE, "Started")
+                .and_then(|value| bool::try_from(value).ok())
+                == Some(false)
+            {
+                provisioning_stopped = true;
+            }
+
+            match event
+                .changed_property(STATION_INTERFACE, "State")
+                .and_then(|value| <&str>::try_from(value).ok())
+            {
+                Some("connecting") if !connecting => {
+                    connecting = true;
+                    let msg = t!("notifications.easy_connect.connecting");
+                    info!("{msg}");
+                    try_send_notification!(
+                        notification_manager,
+                        None,
+                        Some(msg.to_string()),
+                        Some("easy_connect"),
+                        None
+                    );
+                }
+                Some("connected") if connecting => break EasyConnectOutcome::Connected,
+                Some("disconnected") if connecting => break EasyConnectOutcome::Failed,
+                _ => {}
             }
         }
+    }
+
+    async fn report_easy_connect_outcome(&mut self, outcome: EasyConnectOutcome) -> Result<()> {
+        let (msg, icon) = match outcome {
+            EasyConnectOutcome::Connected => {
+                self.sync_state().await?;
+
+                let network_name = self
+                    .adapter
+                    .device
+                    .station
+                    .as_ref()
+                    .and_then(|station| station.connected_network.as_ref())
+                    .map(|network| network.name.clone())
+                    .unwrap_or_default();
+
+                (
+                    t!(
+                        "notifications.easy_connect.connected",
+                        network_name = network_name
+                    ),
+                    "connected",
+                )
+            }
+            EasyConnectOutcome::Finished => {
+                (t!("notifications.easy_connect.configurator_finished"), "ok")
+            }
+            EasyConnectOutcome::NotConfigured => {
+                (t!("notifications.easy_connect.not_configured"), "error")
+            }
+            EasyConnectOutcome::Failed => {
+                (t!("notifications.easy_connect.connection_failed"), "error")
+            }
+            EasyConnectOutcome::Stopped => (t!("notifications.easy_connect.stopped"), "ok"),
+        };
+
+        info!("{msg}");
+        try_send_notification!(
+            self.notification_manager,
+            None,
+            Some(msg.to_string()),
+            Some(icon),
+            None
+        );
 
         Ok(())
     }
 
-    async fn perform_known_network_connection(
+    fn notify_easy_connect_failed(&self, error: &anyhow::Error) {
+        let msg = t!(
+            "notifications.easy_connect.failed",
+            error_message = format!("{error:#}")
+        );
+        error!("{msg}");
+        try_send_notification!(
+            self.notification_manager,
+            None,
+            Some(msg.to_string()),
+            Some("error"),
+            None
+        );
+    }
+
+    async fn handle_connection_result(
         &mut self,
-        network: &Network,
+        network_name: &str,
+        result: Result<()>,
     ) -> Result<Option<String>> {
-        let station = self
-            .adapter
+        self.adapter
             .device
             .station
-            .as_mut()
-            .ok_or_else(|| anyhow!("No station available for known network connection"))?;
-
-        info!(target: "network", "Connecting to known network: {}", network.name);
+            .as_ref()
+            .ok_or_else(|| anyhow!("No station available for network connection"))?;
 
-        match network.connect().await {
+        match result {
             Ok(()) => {
                 let msg = t!(
                     "notifications.network.connected",
-                    network_name = network.name
+                    network_name = network_name
                 );
                 info!("{msg}");
                 try_send_notification!(
@@ -579,8 +2132,8 @@ impl App {
                     Some("connected"),
                     None
                 );
-                station.refresh().await?;
-                Ok(Some(network.name.clone()))
+                self.sync_state().await?;
+                Ok(Some(network_name.to_string()))
             }
             Err(e) => {
                 let msg = e.to_string();
@@ -596,38 +2149,40 @@ impl App {
             }
         }
     }
-
-    async fn perform_new_network_connection(
-        &mut self,
+
+    async fn perform_eap_provisioning(
+        &self,
         menu: &Menu,
         menu_command: &Option<String>,
         network: &Network,
         icon_type: &str,
-    ) -> Result<Option<String>> {
-        let station = self
-            .adapter
-            .device
-            .station
-            .as_mut()
-            .ok_or_else(|| anyhow!("No station available for new network connection"))?;
+    ) -> Result<bool> {
+        const PROVISIONING_TIMEOUT_SECS: u64 = 5;
 
-        info!(target: "network", "Connecting to new network: {}", network.name);
+        let Some(method) = menu.prompt_eap_method(menu_command, &network.name, icon_type) else {
+            return Ok(false);
+        };
+        let Some(phase2_method) = menu.prompt_phase2_method(menu_command, method, icon_type) else {
+            return Ok(false);
+        };
 
-        if network.is_secure() {
-            if let Some(passphrase) =
-                menu.prompt_station_passphrase(menu_command, &network.name, icon_type)
-            {
-                self.agent_manager.send_passkey(passphrase)?;
-            } else {
-                self.agent_manager.cancel_auth()?;
-                return Ok(None);
-            }
-        }
+        let profile = EapProfile {
+            ssid: network.name.clone(),
+            method,
+            phase2_method,
+            identity: menu.prompt_eap_identity(menu_command, icon_type),
+            ca_cert: menu.prompt_eap_ca_cert(menu_command, icon_type),
+            domain_mask: menu.prompt_eap_domain_mask(menu_command, icon_type),
+        };
 
-        match network.connect().await {
-            Ok(()) => {
+        // Subscribed before writing so the known network iwd creates from the file is not missed
+        let mut events = IwdEvents::new().await?;
+
+        match profile.write(&self.iwd_state_dir) {
+            Ok(path) => {
+                debug!("Wrote provisioning file {}", path.display());
                 let msg = t!(
-                    "notifications.network.connected",
+                    "notifications.network.provisioned",
                     network_name = network.name
                 );
                 info!("{msg}");
@@ -635,23 +2190,180 @@ impl App {
                     self.notification_manager,
                     None,
                     Some(msg.to_string()),
-                    Some("connected"),
+                    Some("ok"),
                     None
                 );
-                station.refresh().await?;
-                Ok(Some(network.name.clone()))
+
+                // Retry once iwd has loaded the new file
+                let known_network_added = async {
+                    while let Some(event) = events.next().await {
+                        if event.adds_known_network(&network.name) {
+                            break;
+                        }
+                    }
+                };
+
+                if tokio::time::timeout(
+                    Duration::from_secs(PROVISIONING_TIMEOUT_SECS),
+                    known_network_added,
+                )
+                .await
+                .is_err()
+                {
+                    warn!("iwd did not load {} in time", path.display());
+                }
+
+                Ok(true)
             }
             Err(e) => {
-                let msg = e.to_string();
-                info!("{msg}");
+                let msg = t!(
+                    "notifications.network.provisioning_failed",
+                    error = format!("{e:#}")
+                );
+                error!("{msg}");
                 try_send_notification!(
                     self.notification_manager,
                     None,
-                    Some(msg),
+                    Some(msg.to_string()),
                     Some("error"),
                     None
                 );
-                Ok(None)
+                Ok(false)
+            }
+        }
+    }
+
+    /// Runs `connect` until it succeeds, and re-opens the passphrase prompt with the error as
+    /// the hint when iwd rejects a passphrase typed by the user, up to `auth_retries` times.
+    async fn connect_with_retries<C, F>(
+        &self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        connect: C,
+        icon_type: &str,
+    ) -> Result<()>
+    where
+        C: Fn() -> F,
+        F: Future<Output = Result<()>>,
+    {
+        let mut prompt = PassphrasePrompt::default();
+        let mut attempt = 0;
+
+        loop {
+            prompt.requested = false;
+
+            let result = Self::connect_with_agent(
+                &self.agent_manager,
+                menu,
+                menu_command,
+                connect(),
+                icon_type,
+                &mut prompt,
+            )
+            .await;
+
+            match result {
+                Err(e) if attempt < self.auth_retries && is_auth_failure(&e, prompt.requested) => {
+                    attempt += 1;
+                    info!(
+                        "Authentication failed, retrying ({attempt}/{})",
+                        self.auth_retries
+                    );
+                    prompt.error_message = Some(e.to_string());
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn connect_with_agent<F>(
+        agent_manager: &AgentManager,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        connection: F,
+        icon_type: &str,
+        prompt: &mut PassphrasePrompt,
+    ) -> Result<()>
+    where
+        F: Future<Output = Result<()>>,
+    {
+        tokio::pin!(connection);
+
+        loop {
+            tokio::select! {
+                result = &mut connection => return result,
+                Some(request) = agent_manager.next_request() => {
+                    Self::handle_auth_request(
+                        agent_manager,
+                        menu,
+                        menu_command,
+                        request,
+                        icon_type,
+                        prompt,
+                    )?;
+                }
+            }
+        }
+    }
+
+    fn handle_auth_request(
+        agent_manager: &AgentManager,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        request: AuthRequest,
+        icon_type: &str,
+        prompt: &mut PassphrasePrompt,
+    ) -> Result<()> {
+        debug!("Authentication requested: {request:?}");
+
+        let ssid = request.ssid();
+
+        match &request {
+            AuthRequest::Passphrase { .. } => {
+                prompt.requested = true;
+                let mut error_message = prompt.error_message.take();
+
+                loop {
+                    let Some(passphrase) = menu.prompt_station_passphrase(
+                        menu_command,
+                        ssid,
+                        error_message.as_deref(),
+                        icon_type,
+                    ) else {
+                        return agent_manager.cancel_auth();
+                    };
+
+                    if is_valid_passphrase(&passphrase) {
+                        return agent_manager.send_passkey(passphrase);
+                    }
+
+                    debug!("Rejected malformed passphrase for {ssid}");
+                    error_message = Some(t!("menus.auth.passphrase.invalid").to_string());
+                }
+            }
+            AuthRequest::PrivateKeyPassphrase { .. } => {
+                match menu.prompt_private_key_passphrase(menu_command, ssid, icon_type) {
+                    Some(passphrase) => agent_manager.send_passkey(passphrase),
+                    None => agent_manager.cancel_auth(),
+                }
+            }
+            AuthRequest::UserNameAndPassword { .. } => {
+                let Some(user_name) = menu.prompt_eap_username(menu_command, ssid, icon_type)
+                else {
+                    return agent_manager.cancel_auth();
+                };
+
+                match menu.prompt_eap_password(menu_command, ssid, Some(&user_name), icon_type) {
+                    Some(password) => agent_manager.send_credentials(user_name, password),
+                    None => agent_manager.cancel_auth(),
+                }
+            }
+            AuthRequest::UserPassword { user_name, .. } => {
+                match menu.prompt_eap_password(menu_command, ssid, user_name.as_deref(), icon_type)
+                {
+                    Some(password) => agent_manager.send_passkey(password),
+                    None => agent_manager.cancel_auth(),
+                }
             }
         }
     }
@@ -689,7 +2401,7 @@ impl App {
             None
         );
 
-        station.refresh().await?;
+        self.sync_state().await?;
         Ok(())
     }
 
@@ -718,12 +2430,7 @@ impl App {
                 Some(Timeout::Never)
             );
 
-            while station.is_scanning {
-                sleep(Duration::from_millis(500)).await;
-                station.refresh().await?;
-            }
-
-            station.refresh().await?;
+            self.wait_for_scan_completion().await?;
 
             if let Some(id) = notification_id {
                 self.notification_manager.close_notification(id)?;
@@ -767,6 +2474,214 @@ impl App {
         Ok(())
     }
 
+    /// Replaces the secret of a known PSK network. The iwd profile is edited in place when the
+    /// state directory is writable, otherwise the network is forgotten and joined again with the
+    /// new passphrase, which is only done when the previous secret could be read to put it back
+    /// if the network rejects the new one.
+    async fn perform_passphrase_update(
+        &mut self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        known_network: &KnownNetwork,
+        is_connected: bool,
+        icon_type: &str,
+    ) -> Result<bool> {
+        let ssid = known_network.name.as_str();
+
+        let mut error_message = None;
+        let passphrase = loop {
+            let Some(passphrase) =
+                menu.prompt_new_passphrase(menu_command, ssid, error_message.as_deref(), icon_type)
+            else {
+                return Ok(true);
+            };
+
+            if is_valid_passphrase(&passphrase) {
+                break passphrase;
+            }
+
+            error_message = Some(t!("menus.auth.passphrase.invalid").to_string());
+        };
+
+        let network = self.adapter.device.station.as_ref().and_then(|station| {
+            station
+                .known_networks
+                .iter()
+                .find(|(net, _)| net.name == ssid)
+                .map(|(net, _)| net.clone())
+        });
+
+        match update_passphrase(&self.iwd_state_dir, ssid, &passphrase) {
+            Ok(previous) => {
+                info!("Updated the passphrase in the iwd profile of {ssid}");
+
+                // Reconnect so the new passphrase is checked right away
+                if let Some(network) = network.as_ref().filter(|_| is_connected) {
+                    if let Err(e) = self.perform_reconnection(network).await {
+                        warn!("Failed to reconnect to {ssid} with the new passphrase: {e}");
+
+                        restore_profile(&self.iwd_state_dir, ssid, &previous)?;
+                        if let Err(e) = self.perform_reconnection(network).await {
+                            warn!(
+                                "Failed to reconnect to {ssid} with the previous passphrase: {e}"
+                            );
+                        }
+
+                        self.notify_passphrase_update(
+                            t!(
+                                "notifications.known_networks.passphrase_restored",
+                                network_name = ssid
+                            ),
+                            "error",
+                        );
+                        return Ok(true);
+                    }
+                }
+
+                self.notify_passphrase_update(
+                    t!(
+                        "notifications.known_networks.passphrase_updated",
+                        network_name = ssid
+                    ),
+                    "set_passphrase",
+                );
+                Ok(true)
+            }
+            Err(e) => {
+                warn!("{e:#}, falling back to forgetting and joining {ssid} again");
+
+                let Some(network) = network else {
+                    self.notify_passphrase_update(
+                        t!(
+                            "notifications.known_networks.passphrase_out_of_range",
+                            network_name = ssid
+                        ),
+                        "error",
+                    );
+                    return Ok(true);
+                };
+
+                // Forgetting the network without a way back could lose it for good
+                let previous = match read_passphrase(&self.iwd_state_dir, ssid) {
+                    Ok(Some(previous)) => previous,
+                    result => {
+                        if let Err(e) = result {
+                            warn!("{e:#}");
+                        }
+                        self.notify_passphrase_update(
+                            t!(
+                                "notifications.known_networks.passphrase_unreadable",
+                                network_name = ssid
+                            ),
+                            "error",
+                        );
+                        return Ok(true);
+                    }
+                };
+
+                known_network.forget().await?;
+
+                let result = Self::connect_with_passphrase(
+                    &self.agent_manager,
+                    network.connect(),
+                    Some(passphrase),
+                )
+                .await;
+
+                let msg = match result {
+                    Ok(()) => {
+                        self.restore_autoconnect(known_network).await;
+                        t!(
+                            "notifications.known_networks.passphrase_updated",
+                            network_name = ssid
+                        )
+                    }
+                    Err(e) => {
+                        warn!("Failed to connect to {ssid} with the new passphrase: {e}");
+
+                        match Self::connect_with_passphrase(
+                            &self.agent_manager,
+                            network.connect(),
+                            Some(previous),
+                        )
+                        .await
+                        {
+                            Ok(()) => {
+                                self.restore_autoconnect(known_network).await;
+                                t!(
+                                    "notifications.known_networks.passphrase_restored",
+                                    network_name = ssid
+                                )
+                            }
+                            Err(e) => {
+                                warn!(
+                                    "Failed to connect to {ssid} with the previous passphrase: {e}"
+                                );
+                                t!(
+                                    "notifications.known_networks.passphrase_lost",
+                                    network_name = ssid
+                                )
+                            }
+                        }
+                    }
+                };
+
+                self.notify_passphrase_update(msg, "set_passphrase");
+
+                self.sync_state().await?;
+
+                // The known network was recreated, so its menu has to be opened again
+                Ok(false)
+            }
+        }
+    }
+
+    async fn perform_reconnection(&mut self, network: &Network) -> Result<()> {
+        if let Some(station) = self.adapter.device.station.as_mut() {
+            station.disconnect().await?;
+        }
+
+        Self::connect_with_passphrase(&self.agent_manager, network.connect(), None).await
+    }
+
+    /// Forgetting a network resets its settings, so autoconnect is turned off again when the
+    /// user had disabled it.
+    async fn restore_autoconnect(&self, known_network: &KnownNetwork) {
+        if known_network.is_autoconnect {
+            return;
+        }
+
+        let result = async {
+            match KnownNetwork::all(&self.connection)
+                .await?
+                .into_iter()
+                .find(|net| net.name == known_network.name)
+            {
+                Some(saved) => saved.toggle_autoconnect(false).await,
+                None => Ok(()),
+            }
+        }
+        .await;
+
+        if let Err(e) = result {
+            warn!(
+                "Failed to disable autoconnect for {}: {e}",
+                known_network.name
+            );
+        }
+    }
+
+    fn notify_passphrase_update(&self, msg: Cow<'_, str>, icon: &str) {
+        info!("{msg}");
+        try_send_notification!(
+            self.notification_manager,
+            None,
+            Some(msg.to_string()),
+            Some(icon),
+            None
+        );
+    }
+
     async fn perform_toggle_autoconnect(
         &self,
         known_network: &KnownNetwork,
@@ -876,36 +2791,65 @@ impl App {
                 return Ok(());
             }
 
-            let ssid = if ap.ssid.is_empty() {
-                menu.prompt_ap_ssid(menu_command, icon_type)
-                    .unwrap_or_else(|| "MySSID".to_string())
-            } else {
-                ap.ssid.clone()
-            };
-
-            let psk = if ap.psk.is_empty() {
-                menu.prompt_ap_passphrase(menu_command, icon_type)
-                    .unwrap_or_else(|| "MyPassword".to_string())
-            } else {
-                ap.psk.clone()
-            };
+            if ap.ssid.is_empty() {
+                match menu.prompt_ap_ssid(menu_command, icon_type) {
+                    Some(ssid) => ap.set_ssid(ssid),
+                    None => {
+                        debug!("No SSID set, not starting access point");
+                        return Ok(());
+                    }
+                }
+            }
 
-            ap.set_ssid(ssid);
-            ap.set_psk(psk);
+            let mut generated_passphrase = false;
+            if ap.psk.is_empty() {
+                match menu.prompt_ap_passphrase(menu_command, icon_type) {
+                    Some(psk) => ap.set_psk(psk),
+                    None => {
+                        ap.set_psk(generate_passphrase()?);
+                        generated_passphrase = true;
+                    }
+                }
+            }
 
             ap.start().await?;
 
-            let msg = t!("notifications.device.access_point_started").to_string();
-            info!("{msg}");
-            try_send_notification!(
-                self.notification_manager,
-                None,
-                Some(msg),
-                Some("access_point"),
-                None
-            );
+            let settings = AccessPointSettings {
+                ssid: ap.ssid.clone(),
+                passphrase: ap.psk.clone(),
+            };
+            if let Err(e) = settings.save() {
+                warn!("Failed to save access point settings: {e:#}");
+            }
+
+            if generated_passphrase {
+                let msg = t!(
+                    "notifications.device.access_point_started_with_passphrase",
+                    ssid = ap.ssid,
+                    passphrase = ap.psk
+                )
+                .to_string();
+                info!("{}", t!("notifications.device.access_point_started"));
+                try_send_notification!(
+                    self.notification_manager,
+                    None,
+                    Some(msg),
+                    Some("access_point"),
+                    Some(Timeout::Milliseconds(15000))
+                );
+            } else {
+                let msg = t!("notifications.device.access_point_started").to_string();
+                info!("{msg}");
+                try_send_notification!(
+                    self.notification_manager,
+                    None,
+                    Some(msg),
+                    Some("access_point"),
+                    None
+                );
+            }
 
-            self.adapter.refresh().await?;
+            self.sync_state().await?;
         } else {
             let msg = t!("notifications.device.no_access_point_available").to_string();
             error!("{msg}");
@@ -924,7 +2868,7 @@ impl App {
     async fn perform_ap_stop(&mut self) -> Result<()> {
         if let Some(ap) = &self.adapter.device.access_point {
             ap.stop().await?;
-            self.adapter.refresh().await?;
+            self.sync_state().await?;
 
             let msg = t!("notifications.device.access_point_stopped").to_string();
             info!("{msg}");
@@ -942,30 +2886,135 @@ impl App {
         Ok(())
     }
 
-    async fn run_station_mode(
+    async fn show_main_menu(
         &mut self,
         menu: &Menu,
         menu_command: &Option<String>,
         icon_type: &str,
         spaces: usize,
-    ) -> Result<()> {
-        let station = match self.adapter.device.station.as_mut() {
-            Some(station) => station,
-            None => {
-                error!("{}", t!("notifications.app.no_station_available"));
-                self.running = false;
-                return Ok(());
+    ) -> Result<Option<MainMenuOptions>> {
+        if self
+            .adapter
+            .device
+            .station
+            .as_ref()
+            .is_some_and(|station| station.is_scanning)
+        {
+            self.wait_for_scan_completion().await?;
+        }
+
+        let station = self
+            .adapter
+            .device
+            .station
+            .as_mut()
+            .ok_or_else(|| anyhow!("No station available for the main menu"))?;
+
+        menu.show_main_menu(menu_command, station, icon_type, spaces)
+            .await
+    }
+
+    /// Shows the main menu in a launcher kept open while networks change, as iwd events update
+    /// them. Scanning leaves the menu open.
+    /// Falls back to the regular menu when the launcher cannot open it.
+    async fn show_live_main_menu(
+        &mut self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        icon_type: &str,
+        spaces: usize,
+    ) -> Result<Option<MainMenuOptions>> {
+        let mut live = match menu.open_live(icon_type).await {
+            Ok(live) => live,
+            Err(e) => {
+                warn!("Failed to open the live menu, using the regular one: {e:#}");
+                self.live_menu = false;
+                return self
+                    .show_main_menu(menu, menu_command, icon_type, spaces)
+                    .await;
             }
         };
 
-        if station.is_scanning {
-            Self::wait_for_scan_completion(station).await?;
+        self.sync_state().await?;
+
+        let station = self
+            .adapter
+            .device
+            .station
+            .as_mut()
+            .ok_or_else(|| anyhow!("No station available for the main menu"))?;
+
+        loop {
+            let entries = menu.main_menu_entries(station, icon_type, spaces);
+            let keys = entries.values().iter().map(MainMenuOptions::key).collect();
+            let message = station
+                .is_scanning
+                .then(|| t!("notifications.station.scan_in_progress"));
+
+            live.update(entries.lines(), keys, None, message.as_deref())?;
+
+            tokio::select! {
+                event = live.next_event() => {
+                    let (position, action) = match event {
+                        LiveEvent::Select { key, text } => (live.position(&key, &text), None),
+                        LiveEvent::CustomKey { number, key } => (
+                            live.position(&key, "").or_else(|| live.active_position()),
+                            KeyAction::from_custom_key(number),
+                        ),
+                        LiveEvent::Active { .. } | LiveEvent::Closed => return Ok(None),
+                    };
+
+                    let selection =
+                        position.and_then(|position| entries.resolve(&position.to_string(), true));
+
+                    match (selection, action) {
+                        (Some(MainMenuOptions::Scan), None) | (_, Some(KeyAction::Scan)) => {
+                            if !station.is_scanning {
+                                station.scan().await?;
+                                station.is_scanning = true;
+                            }
+                        }
+                        (Some(MainMenuOptions::Network(network)), Some(action)) => {
+                            return Ok(Some(MainMenuOptions::NetworkAction(network, action)));
+                        }
+                        (Some(option), None) => return Ok(Some(option)),
+                        (_, Some(action)) => {
+                            debug!("Ignoring {} outside of a network entry", action.name());
+                        }
+                        (None, None) => debug!("Ignoring a selection matching no entry"),
+                    }
+                }
+                Some(event) = self.events.next() => {
+                    if let Err(e) = station.apply_event(&event).await {
+                        debug!("Failed to apply iwd event: {e:?}");
+                    }
+                }
+            }
+        }
+    }
+
+    async fn run_station_mode(
+        &mut self,
+        menu: &Menu,
+        menu_command: &Option<String>,
+        icon_type: &str,
+        spaces: usize,
+    ) -> Result<()> {
+        if self.adapter.device.station.is_none() {
+            error!("{}", t!("notifications.app.no_station_available"));
+            self.running = false;
+            return Ok(());
         }
 
-        match menu
-            .show_main_menu(menu_command, station, icon_type, spaces)
-            .await?
-        {
+        let main_menu_option = if self.live_menu {
+            self.show_live_main_menu(menu, menu_command, icon_type, spaces)
+                .await
+        } else {
+            self.show_main_menu(menu, menu_command, icon_type, spaces)
+                .await
+        };
+
+        match main_menu_option? {
             Some(main_menu_option) => {
                 self.handle_main_options(menu, menu_command, icon_type, spaces, main_menu_option)
                     .await?;
@@ -0,0 +1,666 @@
+use crate::icons::Icons;
+use crate::iw::{
+    adapter::Adapter,
+    agent::{AgentManager, AuthRequest},
+    events::IwdEvents,
+    known_network::KnownNetwork,
+    network::{connect_error, is_not_found, network_type_name, Network},
+    objects::{DeviceSelection, IwdObjects},
+    station::Station,
+};
+use crate::json::{self, StatusJson};
+use crate::qr::{WifiCredentials, WifiQr};
+use crate::status_bar::{BarState, StatusFormat};
+use anyhow::{anyhow, Context, Result};
+use iwdrs::error::network::ConnectError;
+use log::{debug, info};
+use rust_i18n::t;
+use serde::Serialize;
+use std::{
+    fmt,
+    future::Future,
+    io::{self, BufRead},
+    path::{Path, PathBuf},
+    pin::Pin,
+};
+use zbus::Connection;
+
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_NO_ADAPTER: i32 = 3;
+pub const EXIT_NOT_FOUND: i32 = 4;
+pub const EXIT_AUTH_FAILED: i32 = 5;
+
+#[derive(Debug, Clone)]
+pub enum CliCommand {
+    List {
+        json: bool,
+    },
+    Status {
+        json: bool,
+        follow: bool,
+        format: Option<StatusFormat>,
+        icon_type: String,
+        spaces: usize,
+    },
+    Connect {
+        ssid: String,
+        passphrase_stdin: bool,
+        qr: Option<PathBuf>,
+    },
+    Disconnect,
+    Scan {
+        wait: bool,
+    },
+    Forget {
+        ssid: String,
+    },
+    Autoconnect {
+        ssid: String,
+        enable: bool,
+    },
+    Share {
+        ssid: Option<String>,
+        output: Option<PathBuf>,
+        iwd_state_dir: PathBuf,
+    },
+}
+
+#[derive(Debug)]
+pub enum CliError {
+    NoAdapter(anyhow::Error),
+    NotFound(String),
+    AuthFailed(anyhow::Error),
+    Other(anyhow::Error),
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::NoAdapter(_) => EXIT_NO_ADAPTER,
+            CliError::NotFound(_) => EXIT_NOT_FOUND,
+            CliError::AuthFailed(_) => EXIT_AUTH_FAILED,
+            CliError::Other(_) => EXIT_FAILURE,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::NoAdapter(e) | CliError::AuthFailed(e) | CliError::Other(e) => {
+                write!(f, "{e}")
+            }
+            CliError::NotFound(ssid) => {
+                write!(
+                    f,
+                    "{}",
+                    t!("notifications.network.not_found", network_name = ssid)
+                )
+            }
+        }
+    }
+}
+
+impl From<anyhow::Error> for CliError {
+    fn from(error: anyhow::Error) -> Self {
+        CliError::Other(error)
+    }
+}
+
+pub struct Cli {
+    connection: Connection,
+    agent_manager: AgentManager,
+    adapter: Adapter,
+}
+
+impl Cli {
+    pub async fn new(selection: &DeviceSelection) -> Result<Self, CliError> {
+        let agent_manager = AgentManager::new().await.map_err(CliError::NoAdapter)?;
+        let objects = IwdObjects::new().await.map_err(CliError::NoAdapter)?;
+
+        let device = match objects.matching_devices(selection).as_slice() {
+            [device] => device.clone(),
+            [] => {
+                return Err(CliError::NoAdapter(anyhow!(
+                    "No matching Wi-Fi device found"
+                )))
+            }
+            devices => {
+                let names = devices
+                    .iter()
+                    .map(|device| device.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                return Err(CliError::NoAdapter(anyhow!(
+                    "Multiple Wi-Fi devices found ({names}), select one with --interface"
+                )));
+            }
+        };
+
+        let adapter = Adapter::new(&objects, device.path)
+            .await
+            .map_err(CliError::NoAdapter)?;
+
+        Ok(Self {
+            connection: objects.connection().clone(),
+            agent_manager,
+            adapter,
+        })
+    }
+
+    pub async fn run(&mut self, command: CliCommand) -> Result<(), CliError> {
+        match command {
+            CliCommand::List { json } => self.list(json),
+            CliCommand::Status {
+                follow: true,
+                format,
+                icon_type,
+                spaces,
+                ..
+            } => {
+                self.follow_status(format.unwrap_or(StatusFormat::Plain), &icon_type, spaces)
+                    .await
+            }
+            CliCommand::Status {
+                format: Some(format),
+                icon_type,
+                spaces,
+                ..
+            } => {
+                self.print_bar_state(format, &icon_type, spaces);
+                Ok(())
+            }
+            CliCommand::Status { json, .. } => self.status(json),
+            CliCommand::Connect { qr: Some(path), .. } => self.connect_qr(&path).await,
+            CliCommand::Connect {
+                ssid,
+                passphrase_stdin,
+                ..
+            } => {
+                let secrets = if passphrase_stdin {
+                    Secrets::Stdin
+                } else {
+                    Secrets::Given(Vec::new())
+                };
+                self.connect(&ssid, secrets, false).await
+            }
+            CliCommand::Disconnect => self.disconnect().await,
+            CliCommand::Scan { wait } => self.scan(wait).await,
+            CliCommand::Forget { ssid } => self.forget(&ssid).await,
+            CliCommand::Autoconnect { ssid, enable } => self.autoconnect(&ssid, enable).await,
+            CliCommand::Share {
+                ssid,
+                output,
+                iwd_state_dir,
+            } => {
+                self.share(ssid.as_deref(), output.as_deref(), &iwd_state_dir)
+                    .await
+            }
+        }
+    }
+
+    fn station(&self) -> Result<&Station, CliError> {
+        self.adapter.device.station.as_ref().ok_or_else(|| {
+            CliError::NoAdapter(anyhow!(
+                "No station available (adapter powered off or in access point mode)"
+            ))
+        })
+    }
+
+    fn station_mut(&mut self) -> Result<&mut Station, CliError> {
+        self.adapter.device.station.as_mut().ok_or_else(|| {
+            CliError::NoAdapter(anyhow!(
+                "No station available (adapter powered off or in access point mode)"
+            ))
+        })
+    }
+
+    fn list(&self, json: bool) -> Result<(), CliError> {
+        let station = self.station()?;
+
+        if json {
+            return print_json(&json::list_networks(station));
+        }
+
+        for (network, signal_strength) in station
+            .known_networks
+            .iter()
+            .chain(station.new_networks.iter())
+        {
+            let status = if network.is_connected {
+                "connected"
+            } else if network.known_network.is_some() {
+                "known"
+            } else {
+                "new"
+            };
+
+            println!(
+                "{}\t{}\t{}\t{}",
+                network.name,
+                network_type_name(network.network_type),
+                signal_strength / 100,
+                status
+            );
+        }
+
+        Ok(())
+    }
+
+    fn status(&self, json: bool) -> Result<(), CliError> {
+        if json {
+            return print_json(&StatusJson::from(&self.adapter));
+        }
+
+        let device = &self.adapter.device;
+
+        println!("Adapter: {}", self.adapter.name);
+        println!("Device: {}", device.name);
+        println!("Powered: {}", yes_no(device.is_powered));
+        println!("Mode: {}", device.mode);
+
+        if let Some(station) = &device.station {
+            println!("State: {}", station.state.to_string().to_lowercase());
+            println!("Scanning: {}", yes_no(station.is_scanning));
+
+            if let Some(network) = &station.connected_network {
+                println!("Network: {}", network.name);
+                println!("Security: {}", network_type_name(network.network_type));
+
+                if let Some((_, signal_strength)) = station
+                    .known_networks
+                    .iter()
+                    .find(|(net, _)| net.name == network.name)
+                {
+                    println!("Signal: {} dBm", signal_strength / 100);
+                }
+            }
+        }
+
+        if let Some(access_point) = &device.access_point {
+            println!("Started: {}", yes_no(access_point.has_started));
+
+            if let Some(name) = &access_point.name {
+                println!("Network: {name}");
+            }
+        }
+
+        Ok(())
+    }
+
+    fn print_bar_state(&self, format: StatusFormat, icon_type: &str, spaces: usize) {
+        let state = BarState::from_adapter(&self.adapter);
+        println!("{}", state.render(format, &Icons::new(), icon_type, spaces));
+    }
+
+    async fn follow_status(
+        &mut self,
+        format: StatusFormat,
+        icon_type: &str,
+        spaces: usize,
+    ) -> Result<(), CliError> {
+        let icons = Icons::new();
+        let mut events = IwdEvents::new().await?;
+        events.watch_device(self.adapter.path.clone(), self.adapter.device.path.clone());
+
+        let mut last_state = None;
+
+        loop {
+            let state = BarState::from_adapter(&self.adapter);
+
+            if last_state.as_ref() != Some(&state) {
+                println!("{}", state.render(format, &icons, icon_type, spaces));
+                last_state = Some(state);
+            }
+
+            let event = events
+                .next()
+                .await
+                .ok_or_else(|| anyhow!("Lost connection to iwd"))?;
+
+            if let Err(e) = self.adapter.apply_event(&event).await {
+                debug!("Failed to apply iwd event: {e:?}");
+            }
+        }
+    }
+
+    async fn connect_qr(&mut self, path: &Path) -> Result<(), CliError> {
+        let credentials = WifiCredentials::from_image(path)?;
+
+        self.connect(
+            &credentials.ssid,
+            Secrets::Given(credentials.passphrase.into_iter().collect()),
+            credentials.hidden,
+        )
+        .await
+    }
+
+    async fn connect(
+        &mut self,
+        ssid: &str,
+        mut secrets: Secrets,
+        hidden: bool,
+    ) -> Result<(), CliError> {
+        let network = match self.find_network(ssid) {
+            Ok(network) => Some(network),
+            Err(CliError::NotFound(_)) if hidden => None,
+            Err(e) => return Err(e),
+        };
+
+        let connection: Pin<Box<dyn Future<Output = Result<()>> + '_>> = match &network {
+            Some(network) => {
+                info!(target: "network", "Connecting to network: {}", network.name);
+                Box::pin(network.connect())
+            }
+            None => {
+                info!(target: "network", "Connecting to hidden network: {ssid}");
+                Box::pin(self.station()?.connect_hidden_network(ssid))
+            }
+        };
+
+        let mut auth_requested = false;
+        tokio::pin!(connection);
+
+        let result = loop {
+            tokio::select! {
+                result = &mut connection => break result,
+                Some(request) = self.agent_manager.next_request() => {
+                    debug!("Authentication requested: {request:?}");
+                    auth_requested = true;
+
+                    let response = match request {
+                        AuthRequest::UserNameAndPassword { .. } => {
+                            match (secrets.next()?, secrets.next()?) {
+                                (Some(user_name), Some(password)) => {
+                                    self.agent_manager.send_credentials(user_name, password)
+                                }
+                                _ => self.agent_manager.cancel_auth(),
+                            }
+                        }
+                        _ => match secrets.next()? {
+                            Some(secret) => self.agent_manager.send_passkey(secret),
+                            None => self.agent_manager.cancel_auth(),
+                        },
+                    };
+                    response?;
+                }
+            }
+        };
+
+        result.map_err(|e| connect_cli_error(e, ssid, auth_requested))?;
+
+        println!(
+            "{}",
+            t!("notifications.network.connected", network_name = ssid)
+        );
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<(), CliError> {
+        let station = self.station_mut()?;
+
+        let connected_network_name = station
+            .connected_network
+            .as_ref()
+            .ok_or_else(|| anyhow!("No network is currently connected"))?
+            .name
+            .clone();
+
+        station.disconnect().await?;
+
+        println!(
+            "{}",
+            t!(
+                "notifications.station.disconnected_from_network",
+                network_name = connected_network_name
+            )
+        );
+
+        Ok(())
+    }
+
+    async fn scan(&mut self, wait: bool) -> Result<(), CliError> {
+        let station = self.station_mut()?;
+
+        if station.is_scanning {
+            println!("{}", t!("notifications.station.scan_already_in_progress"));
+        } else {
+            station.scan().await?;
+            println!("{}", t!("notifications.station.start_scanning"));
+        }
+
+        if wait {
+            station.wait_for_scan_complete().await?;
+            println!("{}", t!("notifications.station.scan_completed"));
+        }
+
+        Ok(())
+    }
+
+    async fn forget(&self, ssid: &str) -> Result<(), CliError> {
+        let known_network = self.find_known_network(ssid).await?;
+
+        known_network
+            .forget()
+            .await
+            .with_context(|| format!("Failed to forget network {ssid}"))?;
+
+        println!("{}", t!("notifications.known_networks.forget_network"));
+
+        Ok(())
+    }
+
+    async fn autoconnect(&self, ssid: &str, enable: bool) -> Result<(), CliError> {
+        let known_network = self.find_known_network(ssid).await?;
+
+        known_network.toggle_autoconnect(enable).await?;
+
+        let msg = if enable {
+            t!(
+                "notifications.known_networks.enable_autoconnect",
+                network_name = ssid
+            )
+        } else {
+            t!(
+                "notifications.known_networks.disable_autoconnect",
+                network_name = ssid
+            )
+        };
+        println!("{msg}");
+
+        Ok(())
+    }
+
+    async fn share(
+        &self,
+        ssid: Option<&str>,
+        output: Option<&Path>,
+        iwd_state_dir: &Path,
+    ) -> Result<(), CliError> {
+        let payload = match ssid {
+            Some(ssid) => self
+                .find_known_network(ssid)
+                .await?
+                .share_payload(iwd_state_dir)?,
+            None => self
+                .adapter
+                .device
+                .access_point
+                .as_ref()
+                .ok_or_else(|| {
+                    CliError::NoAdapter(anyhow!(
+                        "No access point available (pass a network name to share a known network)"
+                    ))
+                })?
+                .share_payload()?,
+        };
+
+        let qr = WifiQr::new(&payload)?;
+        println!("{}", qr.to_terminal());
+
+        if let Some(path) = output {
+            qr.save_png(path)?;
+        }
+
+        Ok(())
+    }
+
+    fn find_network(&self, ssid: &str) -> Result<Network, CliError> {
+        let station = self.station()?;
+
+        station
+            .known_networks
+            .iter()
+            .chain(station.new_networks.iter())
+            .find(|(network, _)| network.name == ssid)
+            .map(|(network, _)| network.clone())
+            .ok_or_else(|| CliError::NotFound(ssid.to_string()))
+    }
+
+    async fn find_known_network(&self, ssid: &str) -> Result<KnownNetwork, CliError> {
+        KnownNetwork::all(&self.connection)
+            .await?
+            .into_iter()
+            .find(|known_network| known_network.name == ssid)
+            .ok_or_else(|| CliError::NotFound(ssid.to_string()))
+    }
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<(), CliError> {
+    let output = serde_json::to_string(value).context("Failed to serialize JSON output")?;
+    println!("{output}");
+    Ok(())
+}
+
+/// Answers to the agent's secret requests, in the order they are asked for.
+enum Secrets {
+    Given(Vec<String>),
+    /// Read a line at a time, so `stdin` is only consumed as far as iwd asks and a terminal
+    /// or an open pipe is not waited on until it closes
+    Stdin,
+}
+
+impl Secrets {
+    fn next(&mut self) -> Result<Option<String>> {
+        match self {
+            Secrets::Given(secrets) if secrets.is_empty() => Ok(None),
+            Secrets::Given(secrets) => Ok(Some(secrets.remove(0))),
+            Secrets::Stdin => read_secret(&mut io::stdin().lock()),
+        }
+    }
+}
+
+fn read_secret(reader: &mut impl BufRead) -> Result<Option<String>> {
+    let mut line = String::new();
+
+    if reader
+        .read_line(&mut line)
+        .context("Failed to read secrets from stdin")?
+        == 0
+    {
+        return Ok(None);
+    }
+
+    let secret = line.strip_suffix('\n').unwrap_or(&line);
+    Ok(Some(
+        secret.strip_suffix('\r').unwrap_or(secret).to_string(),
+    ))
+}
+
+fn connect_cli_error(error: anyhow::Error, ssid: &str, auth_requested: bool) -> CliError {
+    if is_not_found(&error) {
+        return CliError::NotFound(ssid.to_string());
+    }
+
+    match connect_error(&error) {
+        Some(ConnectError::InvalidFormat) => CliError::AuthFailed(error),
+        Some(ConnectError::Failed | ConnectError::Aborted) if auth_requested => {
+            CliError::AuthFailed(error)
+        }
+        _ => CliError::Other(error),
+    }
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iw::network::map_connect_error;
+    use iwdrs::error::IWDError;
+    use std::{convert::TryInto, io::Cursor};
+
+    #[test]
+    fn read_secret_reads_one_line_at_a_time() {
+        let mut input = Cursor::new("user\r\npass word\nunused\n");
+
+        assert_eq!(read_secret(&mut input).unwrap().as_deref(), Some("user"));
+        assert_eq!(
+            read_secret(&mut input).unwrap().as_deref(),
+            Some("pass word")
+        );
+        assert_eq!(input.position(), 16);
+    }
+
+    #[test]
+    fn read_secret_stops_at_end_of_input() {
+        let mut input = Cursor::new("last");
+
+        assert_eq!(read_secret(&mut input).unwrap().as_deref(), Some("last"));
+        assert_eq!(read_secret(&mut input).unwrap(), None);
+    }
+
+    fn hidden_network_error(name: &str) -> anyhow::Error {
+        let message = zbus::Message::method_call("/net/connman/iwd/0/4", "ConnectHiddenNetwork")
+            .unwrap()
+            .build(&())
+            .unwrap();
+        let error = zbus::Error::MethodError(name.try_into().unwrap(), None, message);
+
+        map_connect_error(IWDError::ZbusError(error), "Hidden")
+    }
+
+    #[test]
+    fn hidden_network_not_found_exits_with_not_found() {
+        let error = connect_cli_error(
+            hidden_network_error("net.connman.iwd.NotFound"),
+            "Hidden",
+            false,
+        );
+
+        assert!(matches!(&error, CliError::NotFound(ssid) if ssid == "Hidden"));
+        assert_eq!(error.exit_code(), EXIT_NOT_FOUND);
+    }
+
+    #[test]
+    fn other_connect_errors_keep_their_exit_codes() {
+        let error = connect_cli_error(
+            hidden_network_error("net.connman.iwd.NotHidden"),
+            "Hidden",
+            false,
+        );
+        assert_eq!(error.exit_code(), EXIT_FAILURE);
+
+        let invalid = anyhow!(IWDError::OperationError(ConnectError::InvalidFormat));
+        assert_eq!(
+            connect_cli_error(invalid, "Home", false).exit_code(),
+            EXIT_AUTH_FAILED
+        );
+
+        let failed = || anyhow!(IWDError::OperationError(ConnectError::Failed));
+        assert_eq!(
+            connect_cli_error(failed(), "Home", true).exit_code(),
+            EXIT_AUTH_FAILED
+        );
+        assert_eq!(
+            connect_cli_error(failed(), "Home", false).exit_code(),
+            EXIT_FAILURE
+        );
+    }
+}
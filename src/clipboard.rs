@@ -0,0 +1,54 @@
+use anyhow::{anyhow, Context, Result};
+use std::{
+    env,
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+const WAYLAND_COMMANDS: &[&[&str]] = &[&["wl-copy"]];
+const X11_COMMANDS: &[&[&str]] = &[
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+];
+
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    let commands = if env::var_os("WAYLAND_DISPLAY").is_some() {
+        WAYLAND_COMMANDS.iter().chain(X11_COMMANDS)
+    } else {
+        X11_COMMANDS.iter().chain(WAYLAND_COMMANDS)
+    };
+
+    for command in commands {
+        let mut child = match Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e).with_context(|| format!("Failed to run {}", command[0])),
+        };
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .with_context(|| format!("Failed to write to {}", command[0]))?;
+        }
+
+        let status = child
+            .wait()
+            .with_context(|| format!("Failed to wait for {}", command[0]))?;
+
+        if status.success() {
+            return Ok(());
+        }
+
+        return Err(anyhow!("{} exited with {status}", command[0]));
+    }
+
+    Err(anyhow!(
+        "No clipboard tool found, install wl-clipboard, xclip or xsel"
+    ))
+}
@@ -0,0 +1,213 @@
+use crate::{
+    iw::{objects::DeviceSelection, profile::DEFAULT_STATE_DIR},
+    launcher::LauncherProfile,
+    menu::KeyAction,
+};
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+const ICON_TYPES: [&str; 2] = ["font", "xdg"];
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub launcher: Option<String>,
+    pub launcher_command: Option<String>,
+    pub icon: Option<String>,
+    pub spaces: Option<usize>,
+    pub back_on_escape: Option<bool>,
+    pub iwd_state_dir: Option<PathBuf>,
+    pub ap_profile_dir: Option<PathBuf>,
+    pub qr_image_command: Option<String>,
+    pub auth_retries: Option<usize>,
+    pub live_menu: Option<bool>,
+    pub interface: Option<String>,
+    pub adapter: Option<String>,
+    pub keybindings: BTreeMap<String, String>,
+    pub launchers: HashMap<String, LauncherProfile>,
+}
+
+impl Config {
+    pub fn default_path() -> Option<PathBuf> {
+        env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .map(|dir| dir.join("iwmenu").join("config.toml"))
+    }
+
+    /// Loads `path`, or the default location when none is given. Only a missing default
+    /// file is treated as an empty configuration.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let (path, is_explicit) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match Self::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !is_explicit => {
+                return Ok(Self::default());
+            }
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to read config file {}", path.display()));
+            }
+        };
+
+        let config: Self = toml::from_str(&contents)
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+
+        config
+            .validate()
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let Some(icon) = &self.icon {
+            if !ICON_TYPES.contains(&icon.as_str()) {
+                return Err(anyhow!(
+                    "invalid value `{icon}` for `icon`, expected one of: {}",
+                    ICON_TYPES.join(", ")
+                ));
+            }
+        }
+
+        for action in self.keybindings.keys() {
+            if KeyAction::from_name(action).is_none() {
+                return Err(anyhow!(
+                    "unknown action `{action}` in `keybindings`, expected one of: {}",
+                    KeyAction::ALL.map(|action| action.name()).join(", ")
+                ));
+            }
+        }
+
+        for (name, profile) in &self.launchers {
+            if name == "custom" {
+                return Err(anyhow!(
+                    "`custom` is reserved for `launcher-command` and cannot name a launcher"
+                ));
+            }
+
+            if profile.program.is_none() && !LauncherProfile::is_bundled(name) {
+                return Err(anyhow!("missing `program` for launcher `{name}`"));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn icon_type(&self) -> &str {
+        self.icon.as_deref().unwrap_or("font")
+    }
+
+    pub fn spaces(&self) -> usize {
+        self.spaces.unwrap_or(1)
+    }
+
+    pub fn back_on_escape(&self) -> bool {
+        self.back_on_escape.unwrap_or(false)
+    }
+
+    pub fn iwd_state_dir(&self) -> PathBuf {
+        self.iwd_state_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_STATE_DIR))
+    }
+
+    pub fn ap_profile_dir(&self) -> PathBuf {
+        self.ap_profile_dir
+            .clone()
+            .unwrap_or_else(|| self.iwd_state_dir().join("ap"))
+    }
+
+    pub fn auth_retries(&self) -> usize {
+        self.auth_retries.unwrap_or(2)
+    }
+
+    pub fn live_menu(&self) -> bool {
+        self.live_menu.unwrap_or(false)
+    }
+
+    pub fn keybindings(&self) -> Vec<(KeyAction, String)> {
+        self.keybindings
+            .iter()
+            .filter_map(|(action, key)| Some((KeyAction::from_name(action)?, key.clone())))
+            .collect()
+    }
+
+    pub fn device_selection(&self) -> DeviceSelection {
+        DeviceSelection {
+            interface: self.interface.clone(),
+            adapter: self.adapter.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(contents: &str) -> Result<Config> {
+        let config: Config = toml::from_str(contents)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    #[test]
+    fn kebab_case_keys_are_read() {
+        let config = parse(
+            "back-on-escape = true\n\
+             iwd-state-dir = \"/tmp/iwd\"\n\
+             auth-retries = 5\n\
+             live-menu = true\n\
+             [keybindings]\n\
+             forget = \"Alt+f\"\n",
+        )
+        .unwrap();
+
+        assert!(config.back_on_escape());
+        assert_eq!(config.iwd_state_dir(), PathBuf::from("/tmp/iwd"));
+        assert_eq!(config.ap_profile_dir(), PathBuf::from("/tmp/iwd/ap"));
+        assert_eq!(config.auth_retries(), 5);
+        assert!(config.live_menu());
+        assert_eq!(config.keybindings().len(), 1);
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(parse("back_on_escape = true").is_err());
+        assert!(parse("colour = \"red\"").is_err());
+        assert!(parse("[launchers.mine]\nprogram = \"mine\"\nflags = []").is_err());
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        assert!(parse("icon = \"emoji\"").is_err());
+        assert!(parse("spaces = -1").is_err());
+        assert!(parse("[keybindings]\nreboot = \"Alt+r\"").is_err());
+        assert!(parse("[launchers.custom]\nprogram = \"mine\"").is_err());
+        assert!(parse("[launchers.mine]\nargs = []").is_err());
+    }
+
+    #[test]
+    fn missing_keys_use_defaults() {
+        let config = parse("").unwrap();
+
+        assert_eq!(config.icon_type(), "font");
+        assert_eq!(config.spaces(), 1);
+        assert!(!config.back_on_escape());
+        assert_eq!(config.iwd_state_dir(), PathBuf::from(DEFAULT_STATE_DIR));
+        assert_eq!(config.auth_retries(), 2);
+    }
+}
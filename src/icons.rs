@@ -1,4 +1,11 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+const ICON_EXTENSIONS: [&str; 2] = ["svg", "png"];
 
 #[derive(Clone)]
 pub struct IconDefinition {
@@ -27,6 +34,47 @@ impl IconDefinition {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalTier {
+    Weak,
+    Ok,
+    Good,
+    Excellent,
+}
+
+impl SignalTier {
+    pub fn from_strength(signal_strength: i16) -> Self {
+        match signal_strength {
+            -10000..=-7500 => SignalTier::Weak,
+            -7499..=-5000 => SignalTier::Ok,
+            -4999..=-2500 => SignalTier::Good,
+            _ => SignalTier::Excellent,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignalTier::Weak => "weak",
+            SignalTier::Ok => "ok",
+            SignalTier::Good => "good",
+            SignalTier::Excellent => "excellent",
+        }
+    }
+
+    pub fn icon_key(&self, is_secure_network: bool) -> &'static str {
+        match (self, is_secure_network) {
+            (SignalTier::Weak, true) => "signal_weak_secure",
+            (SignalTier::Weak, false) => "signal_weak_open",
+            (SignalTier::Ok, true) => "signal_ok_secure",
+            (SignalTier::Ok, false) => "signal_ok_open",
+            (SignalTier::Good, true) => "signal_good_secure",
+            (SignalTier::Good, false) => "signal_good_open",
+            (SignalTier::Excellent, true) => "signal_excellent_secure",
+            (SignalTier::Excellent, false) => "signal_excellent_open",
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Icons {
     generic_icons: HashMap<&'static str, char>,
@@ -55,17 +103,34 @@ impl Icons {
         font_icons.insert("connect", '\u{f0337}');
         font_icons.insert("disconnect", '\u{f0338}');
         font_icons.insert("scan", '\u{f46a}');
+        font_icons.insert("hidden_network", '\u{f0209}');
         font_icons.insert("settings", '\u{f0493}');
         font_icons.insert("disable_adapter", '\u{f092d}');
         font_icons.insert("power_on_device", '\u{f0425}');
         font_icons.insert("switch_mode", '\u{f0fe2}');
         font_icons.insert("start_ap", '\u{f040d}');
         font_icons.insert("stop_ap", '\u{f0667}');
+        font_icons.insert("connected_clients", '\u{f0849}');
+        font_icons.insert("ap_client", '\u{f011c}');
+        font_icons.insert("ap_profiles", '\u{f0219}');
+        font_icons.insert("create_ap_profile", '\u{f0415}');
         font_icons.insert("set_ssid", '\u{f08d5}');
         font_icons.insert("set_passphrase", '\u{f0bc5}');
         font_icons.insert("enable_autoconnect", '\u{f006a}');
         font_icons.insert("disable_autoconnect", '\u{f19e7}');
         font_icons.insert("forget_network", '\u{f0377}');
+        font_icons.insert("connection_details", '\u{f02fd}');
+        font_icons.insert("share_network", '\u{f0432}');
+        font_icons.insert("qr_code", '\u{f0433}');
+        font_icons.insert("easy_connect", '\u{f0337}');
+        font_icons.insert("dpp_enrollee", '\u{f0432}');
+        font_icons.insert("dpp_configurator", '\u{f0415}');
+        font_icons.insert("stop", '\u{f04db}');
+        font_icons.insert("wps", '\u{f0451}');
+        font_icons.insert("wps_push_button", '\u{f0451}');
+        font_icons.insert("wps_generate_pin", '\u{f0306}');
+        font_icons.insert("wps_enter_pin", '\u{f030c}');
+        font_icons.insert("saved_networks", '\u{f0bc0}');
         font_icons.insert("station", '\u{f059f}');
         font_icons.insert("access_point", '\u{f0003}');
         font_icons.insert("back", '\u{f004d}');
@@ -135,6 +200,13 @@ impl Icons {
                 "sync-synchronizing-symbolic,emblem-synchronizing-symbolic,view-refresh-symbolic",
             ),
         );
+        xdg_icons.insert(
+            "hidden_network",
+            IconDefinition::with_fallbacks(
+                Some("network-wireless-hidden-symbolic"),
+                "network-wireless-hidden-symbolic,view-conceal-symbolic",
+            ),
+        );
         xdg_icons.insert(
             "scan_in_progress",
             IconDefinition::simple("network-wireless-acquiring-symbolic"),
@@ -214,11 +286,69 @@ impl Icons {
             "stop_ap",
             IconDefinition::simple("media-playback-stop-symbolic"),
         );
+        xdg_icons.insert(
+            "connected_clients",
+            IconDefinition::simple("system-users-symbolic"),
+        );
+        xdg_icons.insert(
+            "ap_client",
+            IconDefinition::with_fallbacks(None, "phone-symbolic,computer-symbolic"),
+        );
+        xdg_icons.insert(
+            "ap_profiles",
+            IconDefinition::simple("document-properties-symbolic"),
+        );
+        xdg_icons.insert(
+            "create_ap_profile",
+            IconDefinition::simple("list-add-symbolic"),
+        );
         xdg_icons.insert("set_ssid", IconDefinition::simple("edit-symbolic"));
+        xdg_icons.insert(
+            "saved_networks",
+            IconDefinition::with_fallbacks(
+                None,
+                "document-open-recent-symbolic,network-wireless-symbolic",
+            ),
+        );
         xdg_icons.insert(
             "forget_network",
             IconDefinition::simple("list-remove-symbolic"),
         );
+        xdg_icons.insert(
+            "connection_details",
+            IconDefinition::simple("dialog-information-symbolic"),
+        );
+        xdg_icons.insert("qr_code", IconDefinition::simple("camera-photo-symbolic"));
+        xdg_icons.insert(
+            "easy_connect",
+            IconDefinition::simple("network-wireless-connected-symbolic"),
+        );
+        xdg_icons.insert(
+            "dpp_enrollee",
+            IconDefinition::simple("emblem-shared-symbolic"),
+        );
+        xdg_icons.insert(
+            "dpp_configurator",
+            IconDefinition::simple("list-add-symbolic"),
+        );
+        xdg_icons.insert("stop", IconDefinition::simple("process-stop-symbolic"));
+        xdg_icons.insert("wps", IconDefinition::simple("network-wireless-symbolic"));
+        xdg_icons.insert(
+            "wps_push_button",
+            IconDefinition::simple("input-mouse-symbolic"),
+        );
+        xdg_icons.insert(
+            "wps_generate_pin",
+            IconDefinition::simple("dialog-password-symbolic"),
+        );
+        xdg_icons.insert(
+            "wps_enter_pin",
+            IconDefinition::simple("input-keyboard-symbolic"),
+        );
+        xdg_icons.insert(
+            "share_network",
+            IconDefinition::simple("emblem-shared-symbolic"),
+        );
         xdg_icons.insert(
             "station",
             IconDefinition::simple("network-wireless-symbolic"),
@@ -324,3 +454,70 @@ impl Default for Icons {
         Self::new()
     }
 }
+
+/// Finds the file of the first available icon of a comma-separated list in the installed icon
+/// themes, for launchers taking image paths instead of icon names.
+pub fn find_xdg_icon_file(names: &str) -> Option<PathBuf> {
+    static ICON_DIRS: OnceLock<Vec<PathBuf>> = OnceLock::new();
+
+    let dirs = ICON_DIRS.get_or_init(xdg_icon_dirs);
+
+    names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .find_map(|name| {
+            dirs.iter().find_map(|dir| {
+                ICON_EXTENSIONS
+                    .iter()
+                    .map(|extension| dir.join(format!("{name}.{extension}")))
+                    .find(|path| path.is_file())
+            })
+        })
+}
+
+/// Lists the `<theme>/<size>/<context>` directories of every icon theme, then the pixmaps.
+fn xdg_icon_dirs() -> Vec<PathBuf> {
+    let home = env::var_os("HOME").map(PathBuf::from);
+
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home.as_ref().map(|home| home.join(".local/share")));
+
+    let data_dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| String::from("/usr/local/share:/usr/share"));
+
+    let mut bases: Vec<PathBuf> = home.iter().map(|home| home.join(".icons")).collect();
+    bases.extend(data_home.iter().map(|dir| dir.join("icons")));
+    bases.extend(data_dirs.split(':').map(|dir| Path::new(dir).join("icons")));
+
+    let subdirs = |dir: &Path| -> Vec<PathBuf> {
+        let mut dirs: Vec<PathBuf> = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect();
+        dirs.sort();
+        dirs
+    };
+
+    let mut dirs: Vec<PathBuf> = bases
+        .iter()
+        .flat_map(|base| subdirs(base))
+        .flat_map(|theme| subdirs(&theme))
+        .flat_map(|size| subdirs(&size))
+        .collect();
+
+    dirs.extend(
+        data_dirs
+            .split(':')
+            .map(|dir| Path::new(dir).join("pixmaps")),
+    );
+
+    dirs
+}
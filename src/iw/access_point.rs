@@ -1,141 +1,229 @@
+use crate::{
+    iw::{
+        events::{IwdEvent, ACCESS_POINT_DIAGNOSTIC_INTERFACE, ACCESS_POINT_INTERFACE},
+        objects::interface_proxy,
+    },
+    qr::wifi_payload,
+    state::AccessPointSettings,
+};
 use anyhow::{anyhow, Context, Result};
-use iwdrs::session::Session;
-use std::sync::Arc;
+use iwdrs::network::NetworkType;
+use std::{collections::HashMap, convert::TryFrom, time::Duration};
+use zbus::{
+    zvariant::{OwnedObjectPath, OwnedValue},
+    Connection, Proxy,
+};
+
+#[derive(Debug, Clone)]
+pub struct AccessPointClient {
+    pub address: String,
+    pub rssi: Option<i16>,
+    pub rx_mode: Option<String>,
+    pub rx_rate_kbps: Option<u64>,
+    pub tx_mode: Option<String>,
+    pub tx_rate_kbps: Option<u64>,
+    pub inactive_time: Option<Duration>,
+    pub connected_time: Option<Duration>,
+}
+
+impl AccessPointClient {
+    fn from_diagnostic(data: &HashMap<String, OwnedValue>) -> Option<Self> {
+        let string = |key: &str| {
+            data.get(key)
+                .and_then(|v| <&str>::try_from(v).ok())
+                .map(str::to_string)
+        };
+        let number = |key: &str| data.get(key).and_then(|v| u32::try_from(v).ok());
+        let rate = |key: &str| number(key).map(|rate_100_kbps| 100 * u64::from(rate_100_kbps));
+
+        Some(Self {
+            address: string("Address")?,
+            rssi: data.get("RSSI").and_then(|v| i16::try_from(v).ok()),
+            rx_mode: string("RxMode"),
+            rx_rate_kbps: rate("RxBitrate"),
+            tx_mode: string("TxMode"),
+            tx_rate_kbps: rate("TxBitrate"),
+            inactive_time: number("InactiveTime").map(|ms| Duration::from_millis(ms.into())),
+            connected_time: number("ConnectedTime").map(|secs| Duration::from_secs(secs.into())),
+        })
+    }
+}
+
+const PASSPHRASE_LENGTH: usize = 16;
+// Letters and digits that cannot be mistaken for one another when read aloud or copied
+const PASSPHRASE_CHARSET: &[u8] = b"abcdefghijkmnpqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+pub fn generate_passphrase() -> Result<String> {
+    // Rejecting bytes past the last full multiple keeps every character equally likely
+    let limit = 256 - 256 % PASSPHRASE_CHARSET.len();
+    let mut passphrase = String::with_capacity(PASSPHRASE_LENGTH);
+    let mut buffer = [0u8; 32];
+
+    while passphrase.len() < PASSPHRASE_LENGTH {
+        getrandom::fill(&mut buffer)
+            .map_err(|e| anyhow!("Failed to gather randomness for passphrase: {e}"))?;
+
+        passphrase.extend(
+            buffer
+                .iter()
+                .filter(|&&byte| usize::from(byte) < limit)
+                .map(|&byte| {
+                    char::from(PASSPHRASE_CHARSET[usize::from(byte) % PASSPHRASE_CHARSET.len()])
+                })
+                .take(PASSPHRASE_LENGTH - passphrase.len()),
+        );
+    }
+
+    Ok(passphrase)
+}
 
 #[derive(Debug, Clone)]
 pub struct AccessPoint {
-    session: Arc<Session>,
+    connection: Connection,
+    proxy: Proxy<'static>,
+    pub device_path: OwnedObjectPath,
     pub has_started: bool,
     pub name: Option<String>,
     pub frequency: Option<u32>,
     pub is_scanning: Option<bool>,
     pub supported_ciphers: Option<Vec<String>>,
     pub used_cipher: Option<String>,
-    pub connected_devices: Vec<String>,
+    pub clients: Vec<AccessPointClient>,
     pub ssid: String,
     pub psk: String,
 }
 
 impl AccessPoint {
-    pub async fn new(session: Arc<Session>) -> Result<Self> {
-        let access_points = session.access_points().await?;
-        let iwd_access_point = access_points
-            .into_iter()
-            .next()
-            .ok_or_else(|| anyhow!("No access point available"))?;
-
-        let iwd_access_point_diagnostic = session
-            .access_points_diagnostics()
-            .await
-            .ok()
-            .and_then(|v| v.into_iter().next());
+    pub async fn new(connection: Connection, device_path: OwnedObjectPath) -> Result<Self> {
+        let proxy = interface_proxy(&connection, &device_path, ACCESS_POINT_INTERFACE).await?;
 
-        let has_started = iwd_access_point
-            .has_started()
+        let has_started = proxy
+            .get_property("Started")
             .await
             .context("Failed to retrieve access point status")?;
-        let name = iwd_access_point.name().await?;
-        let frequency = iwd_access_point.frequency().await?;
-        let is_scanning = iwd_access_point.is_scanning().await.ok();
-        let supported_ciphers = iwd_access_point.pairwise_ciphers().await?;
-        let used_cipher = iwd_access_point.group_cipher().await?;
-
-        let connected_devices = if let Some(diagnostic) = iwd_access_point_diagnostic {
-            match diagnostic.get().await {
-                Ok(data) => data
-                    .iter()
-                    .filter_map(|v| v.get("Address"))
-                    .map(|addr| addr.trim_matches('"').to_string())
-                    .collect(),
-                Err(_) => Vec::new(),
-            }
-        } else {
-            Vec::new()
-        };
 
-        Ok(Self {
-            session,
+        let settings = AccessPointSettings::load().unwrap_or_default();
+
+        let mut access_point = Self {
+            connection,
+            proxy,
+            device_path,
             has_started,
-            name,
-            frequency,
-            is_scanning,
-            supported_ciphers,
-            used_cipher,
-            connected_devices,
-            ssid: String::new(),
-            psk: String::new(),
-        })
+            name: None,
+            frequency: None,
+            is_scanning: None,
+            supported_ciphers: None,
+            used_cipher: None,
+            clients: Vec::new(),
+            ssid: settings.ssid,
+            psk: settings.passphrase,
+        };
+        access_point.refresh().await?;
+
+        Ok(access_point)
     }
 
-    pub async fn refresh(&mut self) -> Result<()> {
-        let access_points = self.session.access_points().await?;
-        let iwd_access_point = access_points
-            .into_iter()
-            .next()
-            .ok_or_else(|| anyhow!("No access point available for refresh"))?;
+    async fn refresh(&mut self) -> Result<()> {
+        self.has_started = self.proxy.get_property("Started").await?;
+        // These are only set while the access point is started
+        self.name = self.proxy.get_property("Name").await.ok();
+        self.frequency = self.proxy.get_property("Frequency").await.ok();
+        self.is_scanning = self.proxy.get_property("Scanning").await.ok();
+        self.supported_ciphers = self.proxy.get_property("PairwiseCiphers").await.ok();
+        self.used_cipher = self.proxy.get_property("GroupCipher").await.ok();
 
-        let iwd_access_point_diagnostic = self
-            .session
-            .access_points_diagnostics()
-            .await
-            .ok()
-            .and_then(|v| v.into_iter().next());
-
-        self.has_started = iwd_access_point.has_started().await?;
-        self.name = iwd_access_point.name().await?;
-        self.frequency = iwd_access_point.frequency().await?;
-        self.is_scanning = iwd_access_point.is_scanning().await.ok();
-        self.supported_ciphers = iwd_access_point.pairwise_ciphers().await?;
-        self.used_cipher = iwd_access_point.group_cipher().await?;
-
-        if let Some(diagnostic) = iwd_access_point_diagnostic {
-            if let Ok(data) = diagnostic.get().await {
-                self.connected_devices = data
-                    .iter()
-                    .filter_map(|v| v.get("Address"))
-                    .map(|addr| addr.trim_matches('"').to_string())
-                    .collect();
-            }
+        self.refresh_clients().await
+    }
+
+    pub async fn refresh_clients(&mut self) -> Result<()> {
+        self.clients = self.fetch_clients().await.unwrap_or_default();
+
+        Ok(())
+    }
+
+    /// Diagnostics only exist while started, so any failure means there are no clients.
+    async fn fetch_clients(&self) -> Option<Vec<AccessPointClient>> {
+        let proxy = interface_proxy(
+            &self.connection,
+            &self.device_path,
+            ACCESS_POINT_DIAGNOSTIC_INTERFACE,
+        )
+        .await
+        .ok()?;
+
+        let data: Vec<HashMap<String, OwnedValue>> =
+            proxy.call("GetDiagnostics", &()).await.ok()?;
+
+        Some(
+            data.iter()
+                .filter_map(AccessPointClient::from_diagnostic)
+                .collect(),
+        )
+    }
+
+    pub async fn apply_event(&mut self, event: &IwdEvent) -> Result<()> {
+        if !event.has_interface(ACCESS_POINT_INTERFACE) {
+            return Ok(());
+        }
+
+        if let Some(value) = event.changed_property(ACCESS_POINT_INTERFACE, "Started") {
+            self.has_started = bool::try_from(value)?;
+
+            self.refresh_clients().await?;
+        }
+        if event.touches_property(ACCESS_POINT_INTERFACE, "Name") {
+            self.name = event
+                .changed_property(ACCESS_POINT_INTERFACE, "Name")
+                .and_then(|v| <&str>::try_from(v).ok())
+                .map(str::to_string);
+        }
+        if event.touches_property(ACCESS_POINT_INTERFACE, "Frequency") {
+            self.frequency = event
+                .changed_property(ACCESS_POINT_INTERFACE, "Frequency")
+                .and_then(|v| u32::try_from(v).ok());
+        }
+        if let Some(value) = event.changed_property(ACCESS_POINT_INTERFACE, "Scanning") {
+            self.is_scanning = bool::try_from(value).ok();
+        }
+        if event.touches_property(ACCESS_POINT_INTERFACE, "PairwiseCiphers") {
+            self.supported_ciphers = event
+                .changed_property(ACCESS_POINT_INTERFACE, "PairwiseCiphers")
+                .and_then(|v| Vec::<String>::try_from(v.try_clone().ok()?).ok());
+        }
+        if event.touches_property(ACCESS_POINT_INTERFACE, "GroupCipher") {
+            self.used_cipher = event
+                .changed_property(ACCESS_POINT_INTERFACE, "GroupCipher")
+                .and_then(|v| <&str>::try_from(v).ok())
+                .map(str::to_string);
         }
 
         Ok(())
     }
 
     pub async fn scan(&self) -> Result<()> {
-        let access_points = self.session.access_points().await?;
-        let iwd_access_point = access_points
-            .into_iter()
-            .next()
-            .ok_or_else(|| anyhow!("No access point available for scanning"))?;
-
-        iwd_access_point
-            .scan()
+        self.proxy
+            .call::<_, _, ()>("Scan", &())
             .await
             .context("Failed to initiate scan")
     }
 
     pub async fn start(&self) -> Result<()> {
-        let access_points = self.session.access_points().await?;
-        let iwd_access_point = access_points
-            .into_iter()
-            .next()
-            .ok_or_else(|| anyhow!("No access point available to start"))?;
-
-        iwd_access_point
-            .start(&self.ssid, &self.psk)
+        self.proxy
+            .call::<_, _, ()>("Start", &(&self.ssid, &self.psk))
             .await
             .context("Failed to start access point")
     }
 
+    pub async fn start_profile(&self, ssid: &str) -> Result<()> {
+        self.proxy
+            .call::<_, _, ()>("StartProfile", &(ssid,))
+            .await
+            .with_context(|| format!("Failed to start access point from profile {ssid}"))
+    }
+
     pub async fn stop(&self) -> Result<()> {
-        let access_points = self.session.access_points().await?;
-        let iwd_access_point = access_points
-            .into_iter()
-            .next()
-            .ok_or_else(|| anyhow!("No access point available to stop"))?;
-
-        iwd_access_point
-            .stop()
+        self.proxy
+            .call::<_, _, ()>("Stop", &())
             .await
             .context("Failed to stop access point")
     }
@@ -147,4 +235,73 @@ impl AccessPoint {
     pub fn set_psk(&mut self, psk: String) {
         self.psk = psk;
     }
+
+    pub fn can_share(&self) -> bool {
+        !self.ssid.is_empty() && !self.psk.is_empty()
+    }
+
+    pub fn share_payload(&self) -> Result<String> {
+        if !self.can_share() {
+            return Err(anyhow!(
+                "The access point network name or passphrase is not set"
+            ));
+        }
+
+        wifi_payload(&self.ssid, NetworkType::Psk, Some(&self.psk), false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zbus::zvariant::Value;
+
+    fn owned(value: Value<'_>) -> OwnedValue {
+        value.try_to_owned().unwrap()
+    }
+
+    #[test]
+    fn client_is_read_from_typed_diagnostic_values() {
+        let data = HashMap::from([
+            (
+                "Address".to_string(),
+                owned(Value::from("aa:bb:cc:dd:ee:ff")),
+            ),
+            ("RSSI".to_string(), owned(Value::from(-52i16))),
+            ("RxMode".to_string(), owned(Value::from("802.11ac"))),
+            ("RxBitrate".to_string(), owned(Value::from(8667u32))),
+            ("TxMode".to_string(), owned(Value::from("802.11n"))),
+            ("TxBitrate".to_string(), owned(Value::from(1444u32))),
+            ("InactiveTime".to_string(), owned(Value::from(250u32))),
+            ("ConnectedTime".to_string(), owned(Value::from(90u32))),
+        ]);
+
+        let client = AccessPointClient::from_diagnostic(&data).unwrap();
+
+        assert_eq!(client.address, "aa:bb:cc:dd:ee:ff");
+        assert_eq!(client.rssi, Some(-52));
+        assert_eq!(client.rx_mode.as_deref(), Some("802.11ac"));
+        assert_eq!(client.rx_rate_kbps, Some(866_700));
+        assert_eq!(client.tx_mode.as_deref(), Some("802.11n"));
+        assert_eq!(client.tx_rate_kbps, Some(144_400));
+        assert_eq!(client.inactive_time, Some(Duration::from_millis(250)));
+        assert_eq!(client.connected_time, Some(Duration::from_secs(90)));
+    }
+
+    #[test]
+    fn client_needs_an_address_and_skips_mistyped_values() {
+        let address_only = HashMap::from([
+            (
+                "Address".to_string(),
+                owned(Value::from("aa:bb:cc:dd:ee:ff")),
+            ),
+            ("RSSI".to_string(), owned(Value::from("-52"))),
+        ]);
+        let client = AccessPointClient::from_diagnostic(&address_only).unwrap();
+        assert_eq!(client.rssi, None);
+        assert_eq!(client.rx_rate_kbps, None);
+
+        let no_address = HashMap::from([("RSSI".to_string(), owned(Value::from(-52i16)))]);
+        assert!(AccessPointClient::from_diagnostic(&no_address).is_none());
+    }
 }
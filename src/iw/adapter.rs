@@ -1,12 +1,16 @@
-use crate::iw::device::Device;
-use anyhow::{anyhow, Context, Result};
-use iwdrs::{adapter::Adapter as IwdAdapter, session::Session};
+use crate::iw::{
+    device::Device,
+    events::{IwdEvent, ADAPTER_INTERFACE},
+    objects::{interface_proxy, IwdObjects},
+};
+use anyhow::{Context, Result};
 use log::warn;
-use std::sync::Arc;
+use std::convert::TryFrom;
+use zbus::zvariant::OwnedObjectPath;
 
 #[derive(Debug, Clone)]
 pub struct Adapter {
-    pub adapter: IwdAdapter,
+    pub path: OwnedObjectPath,
     pub is_powered: bool,
     pub name: String,
     pub model: Option<String>,
@@ -16,44 +20,44 @@ pub struct Adapter {
 }
 
 impl Adapter {
-    pub async fn new(session: Arc<Session>) -> Result<Self> {
-        let adapters = session.adapters().await?;
-        let adapter = adapters
-            .into_iter()
-            .next()
-            .ok_or_else(|| anyhow!("No adapter found"))?;
+    pub async fn new(objects: &IwdObjects, device_path: OwnedObjectPath) -> Result<Self> {
+        let path = objects.device(&device_path)?.adapter_path;
 
-        let is_powered = adapter
-            .is_powered()
+        let device = Device::new(objects, device_path)
+            .await
+            .context("Failed to initialize device")?;
+
+        let proxy = interface_proxy(objects.connection(), &path, ADAPTER_INTERFACE)
+            .await
+            .context("Failed to retrieve adapter of device")?;
+
+        let is_powered = proxy
+            .get_property("Powered")
             .await
             .context("Failed to get adapter power state")?;
 
-        let name = adapter.name().await?;
+        let name = proxy.get_property("Name").await?;
 
-        let model = adapter
-            .model()
+        let model = proxy
+            .get_property("Model")
             .await
             .map_err(|e| {
                 warn!("Failed to get adapter model: {e}");
             })
             .ok();
 
-        let vendor = adapter
-            .vendor()
+        let vendor = proxy
+            .get_property("Vendor")
             .await
             .map_err(|e| {
                 warn!("Failed to get adapter vendor: {e}");
             })
             .ok();
 
-        let supported_modes = adapter.supported_modes().await?;
-
-        let device = Device::new(session.clone())
-            .await
-            .context("Failed to initialize device")?;
+        let supported_modes = proxy.get_property("SupportedModes").await?;
 
         Ok(Self {
-            adapter,
+            path,
             is_powered,
             name,
             model,
@@ -63,14 +67,14 @@ impl Adapter {
         })
     }
 
-    pub async fn refresh(&mut self) -> Result<()> {
-        self.is_powered = self.adapter.is_powered().await?;
+    pub async fn apply_event(&mut self, event: &IwdEvent) -> Result<()> {
+        if let Some(value) = event.changed_property(ADAPTER_INTERFACE, "Powered") {
+            self.is_powered = bool::try_from(value)?;
+        }
 
         self.device
-            .refresh()
+            .apply_event(event)
             .await
-            .context("Failed to refresh device")?;
-
-        Ok(())
+            .context("Failed to update device")
     }
 }
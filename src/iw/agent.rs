@@ -14,11 +14,41 @@ use tokio::sync::{
     Mutex,
 };
 
+#[derive(Debug, Clone)]
+pub enum AuthRequest {
+    Passphrase {
+        ssid: String,
+    },
+    PrivateKeyPassphrase {
+        ssid: String,
+    },
+    UserNameAndPassword {
+        ssid: String,
+    },
+    UserPassword {
+        ssid: String,
+        user_name: Option<String>,
+    },
+}
+
+impl AuthRequest {
+    pub fn ssid(&self) -> &str {
+        match self {
+            AuthRequest::Passphrase { ssid }
+            | AuthRequest::PrivateKeyPassphrase { ssid }
+            | AuthRequest::UserNameAndPassword { ssid }
+            | AuthRequest::UserPassword { ssid, .. } => ssid,
+        }
+    }
+}
+
 pub struct AgentManager {
     session: Arc<Session>,
     authentication_required: Arc<AtomicBool>,
     passkey_sender: UnboundedSender<String>,
+    credentials_sender: UnboundedSender<(String, String)>,
     cancel_signal_sender: UnboundedSender<()>,
+    request_receiver: Mutex<UnboundedReceiver<AuthRequest>>,
 }
 
 impl AgentManager {
@@ -30,16 +60,21 @@ impl AgentManager {
         );
 
         let (passkey_sender, passkey_receiver) = unbounded_channel::<String>();
+        let (credentials_sender, credentials_receiver) = unbounded_channel::<(String, String)>();
         let (cancel_signal_sender, cancel_signal_receiver) = unbounded_channel::<()>();
+        let (request_sender, request_receiver) = unbounded_channel::<AuthRequest>();
 
         let passkey_receiver = Arc::new(Mutex::new(passkey_receiver));
+        let credentials_receiver = Arc::new(Mutex::new(credentials_receiver));
         let cancel_signal_receiver = Arc::new(Mutex::new(cancel_signal_receiver));
         let authentication_required = Arc::new(AtomicBool::new(false));
 
         let agent = CustomAgent {
             authentication_required: authentication_required.clone(),
             passkey_receiver: passkey_receiver.clone(),
+            credentials_receiver: credentials_receiver.clone(),
             cancel_signal_receiver: cancel_signal_receiver.clone(),
+            request_sender,
         };
 
         session
@@ -51,7 +86,9 @@ impl AgentManager {
             session,
             authentication_required,
             passkey_sender,
+            credentials_sender,
             cancel_signal_sender,
+            request_receiver: Mutex::new(request_receiver),
         })
     }
 
@@ -59,6 +96,10 @@ impl AgentManager {
         self.session.clone()
     }
 
+    pub async fn next_request(&self) -> Option<AuthRequest> {
+        self.request_receiver.lock().await.recv().await
+    }
+
     pub fn send_passkey(&self, passkey: String) -> Result<()> {
         self.passkey_sender
             .send(passkey)
@@ -68,6 +109,15 @@ impl AgentManager {
         Ok(())
     }
 
+    pub fn send_credentials(&self, user_name: String, password: String) -> Result<()> {
+        self.credentials_sender
+            .send((user_name, password))
+            .context("Failed to send credentials")?;
+
+        self.authentication_required.store(false, Relaxed);
+        Ok(())
+    }
+
     pub fn cancel_auth(&self) -> Result<()> {
         self.cancel_signal_sender
             .send(())
@@ -81,14 +131,26 @@ impl AgentManager {
 struct CustomAgent {
     authentication_required: Arc<AtomicBool>,
     passkey_receiver: Arc<Mutex<UnboundedReceiver<String>>>,
+    credentials_receiver: Arc<Mutex<UnboundedReceiver<(String, String)>>>,
     cancel_signal_receiver: Arc<Mutex<UnboundedReceiver<()>>>,
+    request_sender: UnboundedSender<AuthRequest>,
 }
 
-impl Agent for CustomAgent {
-    async fn request_passphrase(&self, _network: &Network) -> Result<String, Canceled> {
-        let mut rx_key = self.passkey_receiver.lock().await;
+impl CustomAgent {
+    async fn request<T>(
+        &self,
+        receiver: &Mutex<UnboundedReceiver<T>>,
+        request: AuthRequest,
+    ) -> Result<T, Canceled> {
+        let mut rx_key = receiver.lock().await;
         let mut rx_cancel = self.cancel_signal_receiver.lock().await;
 
+        // Drop answers left over from a request iwd gave up on
+        while rx_key.try_recv().is_ok() {}
+        while rx_cancel.try_recv().is_ok() {}
+
+        self.request_sender.send(request).map_err(|_| Canceled())?;
+
         request_confirmation(
             self.authentication_required.clone(),
             &mut rx_key,
@@ -97,53 +159,58 @@ impl Agent for CustomAgent {
         .await
         .map_err(|_| Canceled())
     }
+}
 
-    async fn request_private_key_passphrase(&self, _network: &Network) -> Result<String, Canceled> {
-        let mut rx_key = self.passkey_receiver.lock().await;
-        let mut rx_cancel = self.cancel_signal_receiver.lock().await;
+async fn network_ssid(network: &Network) -> String {
+    network.name().await.unwrap_or_default()
+}
 
-        request_confirmation(
-            self.authentication_required.clone(),
-            &mut rx_key,
-            &mut rx_cancel,
+impl Agent for CustomAgent {
+    async fn request_passphrase(&self, network: &Network) -> Result<String, Canceled> {
+        let ssid = network_ssid(network).await;
+
+        self.request(&self.passkey_receiver, AuthRequest::Passphrase { ssid })
+            .await
+    }
+
+    async fn request_private_key_passphrase(&self, network: &Network) -> Result<String, Canceled> {
+        let ssid = network_ssid(network).await;
+
+        self.request(
+            &self.passkey_receiver,
+            AuthRequest::PrivateKeyPassphrase { ssid },
         )
         .await
-        .map_err(|_| Canceled())
     }
 
     async fn request_user_name_and_passphrase(
         &self,
-        _network: &Network,
+        network: &Network,
     ) -> Result<(String, String), Canceled> {
-        let mut rx_key = self.passkey_receiver.lock().await;
-        let mut rx_cancel = self.cancel_signal_receiver.lock().await;
+        let ssid = network_ssid(network).await;
 
-        let passphrase = request_confirmation(
-            self.authentication_required.clone(),
-            &mut rx_key,
-            &mut rx_cancel,
+        self.request(
+            &self.credentials_receiver,
+            AuthRequest::UserNameAndPassword { ssid },
         )
         .await
-        .map_err(|_| Canceled())?;
-
-        Ok((String::new(), passphrase))
     }
 
     async fn request_user_password(
         &self,
-        _network: &Network,
-        _user_name: Option<&String>,
+        network: &Network,
+        user_name: Option<&String>,
     ) -> Result<String, Canceled> {
-        let mut rx_key = self.passkey_receiver.lock().await;
-        let mut rx_cancel = self.cancel_signal_receiver.lock().await;
-
-        request_confirmation(
-            self.authentication_required.clone(),
-            &mut rx_key,
-            &mut rx_cancel,
+        let ssid = network_ssid(network).await;
+
+        self.request(
+            &self.passkey_receiver,
+            AuthRequest::UserPassword {
+                ssid,
+                user_name: user_name.cloned(),
+            },
         )
         .await
-        .map_err(|_| Canceled())
     }
 
     fn cancel(&self, _reason: CancellationReason) {
@@ -155,11 +222,11 @@ impl Agent for CustomAgent {
     }
 }
 
-pub async fn request_confirmation(
+pub async fn request_confirmation<T>(
     authentication_required: Arc<AtomicBool>,
-    rx_key: &mut UnboundedReceiver<String>,
+    rx_key: &mut UnboundedReceiver<T>,
     rx_cancel: &mut UnboundedReceiver<()>,
-) -> Result<String> {
+) -> Result<T> {
     authentication_required.store(true, Relaxed);
 
     let result = tokio::select! {
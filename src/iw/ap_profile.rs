@@ -0,0 +1,503 @@
+use crate::iw::{network::is_valid_passphrase, profile::write_profile};
+use anyhow::{anyhow, Context, Result};
+use std::{
+    fs, io,
+    net::Ipv4Addr,
+    path::{Path, PathBuf},
+};
+
+const PROFILE_EXTENSION: &str = "ap";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ApProfileField {
+    Passphrase,
+    Channel,
+    Address,
+    Gateway,
+    Netmask,
+    DnsList,
+    LeaseTime,
+}
+
+impl ApProfileField {
+    pub const ALL: [ApProfileField; 7] = [
+        ApProfileField::Passphrase,
+        ApProfileField::Channel,
+        ApProfileField::Address,
+        ApProfileField::Gateway,
+        ApProfileField::Netmask,
+        ApProfileField::DnsList,
+        ApProfileField::LeaseTime,
+    ];
+
+    pub fn section(&self) -> &'static str {
+        match self {
+            ApProfileField::Passphrase => "Security",
+            ApProfileField::Channel => "General",
+            _ => "IPv4",
+        }
+    }
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            ApProfileField::Passphrase => "Passphrase",
+            ApProfileField::Channel => "Channel",
+            ApProfileField::Address => "Address",
+            ApProfileField::Gateway => "Gateway",
+            ApProfileField::Netmask => "Netmask",
+            ApProfileField::DnsList => "DNSList",
+            ApProfileField::LeaseTime => "LeaseTime",
+        }
+    }
+
+    /// Keys the field is stored under. A passphrase of 64 hex digits is the pre-shared key
+    /// itself, which iwd reads from `PreSharedKey`.
+    fn keys(&self) -> &'static [&'static str] {
+        match self {
+            ApProfileField::Passphrase => &["Passphrase", "PreSharedKey"],
+            ApProfileField::Channel => &["Channel"],
+            ApProfileField::Address => &["Address"],
+            ApProfileField::Gateway => &["Gateway"],
+            ApProfileField::Netmask => &["Netmask"],
+            ApProfileField::DnsList => &["DNSList"],
+            ApProfileField::LeaseTime => &["LeaseTime"],
+        }
+    }
+
+    fn key_for(&self, value: &str) -> &'static str {
+        match self {
+            ApProfileField::Passphrase if value.len() == 64 => "PreSharedKey",
+            _ => self.key(),
+        }
+    }
+
+    pub fn is_secret(&self) -> bool {
+        matches!(self, ApProfileField::Passphrase)
+    }
+
+    pub fn validate(&self, value: &str) -> Result<()> {
+        match self {
+            ApProfileField::Passphrase => {
+                if !is_valid_passphrase(value) {
+                    return Err(anyhow!(
+                        "passphrase must be 8 to 63 printable ASCII characters or 64 hex digits"
+                    ));
+                }
+            }
+            ApProfileField::Channel => {
+                value
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|channel| *channel > 0)
+                    .ok_or_else(|| anyhow!("`{value}` is not a valid channel"))?;
+            }
+            ApProfileField::Address | ApProfileField::Gateway | ApProfileField::Netmask => {
+                value
+                    .parse::<Ipv4Addr>()
+                    .map_err(|_| anyhow!("`{value}` is not a valid IPv4 address"))?;
+            }
+            ApProfileField::DnsList => {
+                for address in value.split(',').map(str::trim) {
+                    address
+                        .parse::<Ipv4Addr>()
+                        .map_err(|_| anyhow!("`{address}` is not a valid IPv4 address"))?;
+                }
+            }
+            ApProfileField::LeaseTime => {
+                value
+                    .parse::<u32>()
+                    .map_err(|_| anyhow!("`{value}` is not a number of seconds"))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Line {
+    Entry(String, String),
+    /// Comments and blank lines, written back unchanged.
+    Verbatim(String),
+}
+
+impl Line {
+    fn is_blank(&self) -> bool {
+        matches!(self, Line::Verbatim(line) if line.is_empty())
+    }
+}
+
+/// An iwd access point profile, see iwd.ap(5).
+///
+/// Sections, keys and comments iwmenu does not edit are kept as they are when the profile is
+/// saved.
+#[derive(Debug, Clone)]
+pub struct ApProfile {
+    pub ssid: String,
+    header: Vec<Line>,
+    sections: Vec<(String, Vec<Line>)>,
+}
+
+impl ApProfile {
+    pub fn new(ssid: &str) -> Self {
+        Self {
+            ssid: ssid.to_string(),
+            header: Vec::new(),
+            sections: Vec::new(),
+        }
+    }
+
+    pub fn path(dir: &Path, ssid: &str) -> PathBuf {
+        dir.join(format!("{ssid}.{PROFILE_EXTENSION}"))
+    }
+
+    /// Lists the SSIDs of the profiles stored in `dir`, sorted by name.
+    pub fn list(dir: &Path) -> Result<Vec<String>> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", dir.display()));
+            }
+        };
+
+        let mut profiles: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == PROFILE_EXTENSION)
+            })
+            .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+            .collect();
+
+        profiles.sort();
+        Ok(profiles)
+    }
+
+    pub fn load(dir: &Path, ssid: &str) -> Result<Self> {
+        let path = Self::path(dir, ssid);
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        Ok(Self::parse(ssid, &contents))
+    }
+
+    fn parse(ssid: &str, contents: &str) -> Self {
+        let mut profile = Self::new(ssid);
+
+        for line in contents.lines().map(str::trim) {
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                profile.sections.push((name.to_string(), Vec::new()));
+                continue;
+            }
+
+            let parsed = match line.split_once('=') {
+                Some((key, value)) if !line.starts_with('#') => {
+                    Line::Entry(key.trim().to_string(), value.trim().to_string())
+                }
+                _ => Line::Verbatim(line.to_string()),
+            };
+
+            match profile.sections.last_mut() {
+                Some((_, lines)) => lines.push(parsed),
+                None => profile.header.push(parsed),
+            }
+        }
+
+        profile
+    }
+
+    pub fn get(&self, field: ApProfileField) -> Option<&str> {
+        self.sections
+            .iter()
+            .filter(|(section, _)| section == field.section())
+            .flat_map(|(_, lines)| lines.iter())
+            .find_map(|line| match line {
+                Line::Entry(key, value) if field.keys().contains(&key.as_str()) => {
+                    Some(value.as_str())
+                }
+                _ => None,
+            })
+    }
+
+    /// Sets `field` to `value`, or removes it when `value` is `None`. A section left without
+    /// entries or comments by the removal is dropped.
+    pub fn set(&mut self, field: ApProfileField, value: Option<String>) {
+        let is_field = |line: &Line| matches!(line, Line::Entry(key, _) if field.keys().contains(&key.as_str()));
+
+        let existing = self
+            .sections
+            .iter()
+            .enumerate()
+            .filter(|(_, (section, _))| section == field.section())
+            .find_map(|(index, (_, lines))| Some((index, lines.iter().position(is_field)?)));
+
+        self.sections.retain_mut(|(section, lines)| {
+            if section != field.section() || !lines.iter().any(is_field) {
+                return true;
+            }
+            lines.retain(|line| !is_field(line));
+            value.is_some() || lines.iter().any(|line| !line.is_blank())
+        });
+
+        let Some(value) = value else {
+            return;
+        };
+        let entry = Line::Entry(field.key_for(&value).to_string(), value);
+
+        if let Some((index, position)) = existing {
+            self.sections[index].1.insert(position, entry);
+            return;
+        }
+
+        let index = match self
+            .sections
+            .iter()
+            .position(|(section, _)| section == field.section())
+        {
+            Some(index) => index,
+            None => {
+                let previous = match self.sections.last_mut() {
+                    Some((_, lines)) => lines,
+                    None => &mut self.header,
+                };
+                if previous.last().is_some_and(|line| !line.is_blank()) {
+                    previous.push(Line::Verbatim(String::new()));
+                }
+
+                self.sections
+                    .push((field.section().to_string(), Vec::new()));
+                self.sections.len() - 1
+            }
+        };
+
+        // Keep blank lines separating this section from the next one after the new entry
+        let lines = &mut self.sections[index].1;
+        let position = lines
+            .iter()
+            .rposition(|line| !line.is_blank())
+            .map_or(0, |position| position + 1);
+        lines.insert(position, entry);
+    }
+
+    pub fn to_config(&self) -> String {
+        let mut config = String::new();
+
+        let mut write_lines = |lines: &[Line]| {
+            for line in lines {
+                match line {
+                    Line::Entry(key, value) => config.push_str(&format!("{key}={value}\n")),
+                    Line::Verbatim(text) => config.push_str(&format!("{text}\n")),
+                }
+            }
+        };
+
+        write_lines(&self.header);
+        for (section, lines) in &self.sections {
+            write_lines(&[Line::Verbatim(format!("[{section}]"))]);
+            write_lines(lines);
+        }
+
+        let trimmed = config.trim_end_matches('\n').len();
+        config.truncate(trimmed);
+        if !config.is_empty() {
+            config.push('\n');
+        }
+        config
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<PathBuf> {
+        if self.ssid.is_empty() || self.ssid.contains('/') {
+            return Err(anyhow!("`{}` is not a valid profile name", self.ssid));
+        }
+
+        let path = Self::path(dir, &self.ssid);
+        write_profile(&path, &self.to_config())?;
+
+        Ok(path)
+    }
+
+    pub fn delete(dir: &Path, ssid: &str) -> Result<()> {
+        let path = Self::path(dir, ssid);
+
+        fs::remove_file(&path).with_context(|| format!("Failed to delete {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("iwmenu-ap-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    const ORIGINAL: &str = "# Managed by hand\n\
+                            \n\
+                            [General]\n\
+                            Channel=6\n\
+                            DisableHT=true\n\
+                            \n\
+                            [Security]\n\
+                            # Rotated monthly\n\
+                            Passphrase=old-secret\n\
+                            \n\
+                            [IPv4]\n\
+                            Address=192.168.80.1\n\
+                            LeaseTime=3600\n\
+                            \n\
+                            [Custom]\n";
+
+    fn round_trip(name: &str, edit: impl FnOnce(&mut ApProfile)) -> String {
+        let dir = profile_dir(name);
+        fs::write(ApProfile::path(&dir, "Hotspot"), ORIGINAL).unwrap();
+
+        let mut profile = ApProfile::load(&dir, "Hotspot").unwrap();
+        edit(&mut profile);
+        let path = profile.save(&dir).unwrap();
+        let contents = fs::read_to_string(path).unwrap();
+
+        fs::remove_dir_all(dir).unwrap();
+        contents
+    }
+
+    #[test]
+    fn unchanged_profile_is_saved_as_loaded() {
+        assert_eq!(round_trip("unchanged", |_| {}), ORIGINAL);
+    }
+
+    #[test]
+    fn loaded_profile_reads_fields() {
+        let profile = ApProfile::parse("Hotspot", ORIGINAL);
+
+        assert_eq!(profile.get(ApProfileField::Channel), Some("6"));
+        assert_eq!(profile.get(ApProfileField::Passphrase), Some("old-secret"));
+        assert_eq!(profile.get(ApProfileField::Address), Some("192.168.80.1"));
+        assert_eq!(profile.get(ApProfileField::LeaseTime), Some("3600"));
+        assert_eq!(profile.get(ApProfileField::Gateway), None);
+    }
+
+    #[test]
+    fn setting_existing_field_replaces_it_in_place() {
+        let saved = round_trip("replace", |profile| {
+            profile.set(ApProfileField::Channel, Some("11".to_string()));
+        });
+
+        assert_eq!(saved, ORIGINAL.replace("Channel=6", "Channel=11"));
+    }
+
+    #[test]
+    fn setting_new_field_appends_it_to_its_section() {
+        let saved = round_trip("append", |profile| {
+            profile.set(ApProfileField::DnsList, Some("1.1.1.1,9.9.9.9".to_string()));
+        });
+
+        assert_eq!(
+            saved,
+            ORIGINAL.replace(
+                "LeaseTime=3600\n",
+                "LeaseTime=3600\nDNSList=1.1.1.1,9.9.9.9\n"
+            )
+        );
+    }
+
+    #[test]
+    fn every_field_can_be_set_and_cleared() {
+        for field in ApProfileField::ALL {
+            let mut profile = ApProfile::parse("Hotspot", ORIGINAL);
+            profile.set(field, Some("value".to_string()));
+            assert_eq!(profile.get(field), Some("value"));
+
+            profile.set(field, None);
+            assert_eq!(profile.get(field), None);
+            assert!(profile.to_config().contains("[Custom]"));
+            assert!(profile.to_config().contains("DisableHT=true"));
+        }
+    }
+
+    #[test]
+    fn clearing_last_entry_drops_only_that_section() {
+        let saved = round_trip("clear-channel", |profile| {
+            profile.set(ApProfileField::Channel, None);
+        });
+
+        // DisableHT keeps [General], [Custom] was never touched
+        assert_eq!(saved, ORIGINAL.replace("Channel=6\n", ""));
+
+        let mut profile = ApProfile::parse("Hotspot", "[General]\nChannel=6\n\n[Custom]\n");
+        profile.set(ApProfileField::Channel, None);
+        assert_eq!(profile.to_config(), "[Custom]\n");
+    }
+
+    #[test]
+    fn clearing_entry_keeps_section_comments() {
+        let saved = round_trip("clear-passphrase", |profile| {
+            profile.set(ApProfileField::Passphrase, None);
+        });
+
+        assert_eq!(saved, ORIGINAL.replace("Passphrase=old-secret\n", ""));
+    }
+
+    #[test]
+    fn hex_passphrase_is_stored_as_pre_shared_key() {
+        let psk = "0123456789abcdef".repeat(4);
+        let mut profile = ApProfile::parse("Hotspot", ORIGINAL);
+
+        profile.set(ApProfileField::Passphrase, Some(psk.clone()));
+
+        assert_eq!(profile.get(ApProfileField::Passphrase), Some(psk.as_str()));
+        assert!(profile
+            .to_config()
+            .contains(&format!("PreSharedKey={psk}\n")));
+        assert!(!profile.to_config().contains("Passphrase="));
+    }
+
+    #[test]
+    fn new_profile_adds_sections_separated_by_blank_lines() {
+        let mut profile = ApProfile::new("Hotspot");
+        profile.set(ApProfileField::Passphrase, Some("secret-pass".to_string()));
+        profile.set(ApProfileField::Channel, Some("1".to_string()));
+
+        assert_eq!(
+            profile.to_config(),
+            "[Security]\nPassphrase=secret-pass\n\n[General]\nChannel=1\n"
+        );
+    }
+
+    #[test]
+    fn missing_profile_fails_to_load() {
+        let dir = profile_dir("missing");
+
+        assert!(ApProfile::load(&dir, "Hotspot").is_err());
+        assert!(ApProfile::list(&dir.join("absent")).unwrap().is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn list_returns_sorted_ap_profiles_only() {
+        let dir = profile_dir("list");
+        for name in ["b.ap", "a.ap", "Home.psk"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        assert_eq!(ApProfile::list(&dir).unwrap(), ["a", "b"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn passphrase_validation_matches_network_rules() {
+        let field = ApProfileField::Passphrase;
+
+        assert!(field.validate("12345678").is_ok());
+        assert!(field.validate(&"0123456789abcdef".repeat(4)).is_ok());
+        assert!(field.validate("1234567").is_err());
+        assert!(field.validate("pässword").is_err());
+        assert!(field.validate("pass\tword").is_err());
+    }
+}
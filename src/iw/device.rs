@@ -1,14 +1,23 @@
 use anyhow::{anyhow, Context, Result};
-use iwdrs::{device::Device as IwdDevice, modes::Mode, session::Session};
+use iwdrs::modes::Mode;
 use log::warn;
-use std::sync::Arc;
-
-use crate::iw::{access_point::AccessPoint, station::Station};
+use std::{convert::TryFrom, str::FromStr};
+
+use crate::iw::{
+    access_point::AccessPoint,
+    dpp::DeviceProvisioning,
+    events::{IwdEvent, ACCESS_POINT_INTERFACE, DEVICE_INTERFACE, STATION_INTERFACE},
+    objects::{interface_proxy, IwdObjects},
+    station::Station,
+    wps::SimpleConfiguration,
+};
+use zbus::{zvariant::OwnedObjectPath, Connection, Proxy};
 
 #[derive(Debug, Clone)]
 pub struct Device {
-    session: Arc<Session>,
-    pub device: IwdDevice,
+    connection: Connection,
+    proxy: Proxy<'static>,
+    pub path: OwnedObjectPath,
     pub name: String,
     pub address: String,
     pub mode: Mode,
@@ -18,31 +27,36 @@ pub struct Device {
 }
 
 impl Device {
-    pub async fn new(session: Arc<Session>) -> Result<Self> {
-        let devices = session.devices().await?;
-        let device = devices
-            .into_iter()
-            .next()
-            .ok_or_else(|| anyhow!("No device found"))?;
-
-        let name = device.name().await?;
-        let address = device.address().await?;
-
-        let mode = device
-            .get_mode()
+    pub async fn new(objects: &IwdObjects, path: OwnedObjectPath) -> Result<Self> {
+        let connection = objects.connection().clone();
+        let proxy = interface_proxy(&connection, &path, DEVICE_INTERFACE).await?;
+
+        let name = proxy.get_property("Name").await?;
+        let address = proxy.get_property("Address").await?;
+
+        let mode = Self::fetch_mode(&proxy)
             .await
             .context("Failed to retrieve device mode")?;
-        let is_powered = device
-            .is_powered()
+        let is_powered = proxy
+            .get_property("Powered")
             .await
             .context("Failed to check if the device is powered")?;
 
-        let station = Self::initialize_station(session.clone()).await;
-        let access_point = Self::initialize_access_point(session.clone()).await;
+        let station = if objects.has_interface(&path, STATION_INTERFACE) {
+            Self::initialize_station(&connection, &path).await
+        } else {
+            None
+        };
+        let access_point = if objects.has_interface(&path, ACCESS_POINT_INTERFACE) {
+            Self::initialize_access_point(&connection, &path).await
+        } else {
+            None
+        };
 
         Ok(Self {
-            session,
-            device,
+            connection,
+            proxy,
+            path,
             name,
             address,
             mode,
@@ -52,99 +66,110 @@ impl Device {
         })
     }
 
-    async fn initialize_station(session: Arc<Session>) -> Option<Station> {
-        match session.stations().await {
-            Ok(stations) if !stations.is_empty() => match Station::new(session).await {
-                Ok(station) => Some(station),
-                Err(e) => {
-                    warn!("Failed to initialize Station: {e}");
-                    None
-                }
-            },
-            _ => None,
+    async fn fetch_mode(proxy: &Proxy<'static>) -> Result<Mode> {
+        let mode: String = proxy.get_property("Mode").await?;
+
+        Mode::from_str(&mode).map_err(|e| anyhow!("Invalid device mode: {e}"))
+    }
+
+    async fn initialize_station(
+        connection: &Connection,
+        path: &OwnedObjectPath,
+    ) -> Option<Station> {
+        match Station::new(connection.clone(), path.clone()).await {
+            Ok(station) => Some(station),
+            Err(e) => {
+                warn!("Failed to initialize Station: {e}");
+                None
+            }
         }
     }
 
-    async fn initialize_access_point(session: Arc<Session>) -> Option<AccessPoint> {
-        match session.access_points().await {
-            Ok(access_points) if !access_points.is_empty() => {
-                match AccessPoint::new(session).await {
-                    Ok(access_point) => Some(access_point),
-                    Err(e) => {
-                        warn!("Failed to initialize AccessPoint: {e}");
-                        None
-                    }
-                }
+    async fn initialize_access_point(
+        connection: &Connection,
+        path: &OwnedObjectPath,
+    ) -> Option<AccessPoint> {
+        match AccessPoint::new(connection.clone(), path.clone()).await {
+            Ok(access_point) => Some(access_point),
+            Err(e) => {
+                warn!("Failed to initialize AccessPoint: {e}");
+                None
             }
-            _ => None,
         }
     }
 
     pub async fn set_mode(&self, mode: Mode) -> Result<()> {
-        self.device
-            .set_mode(mode)
+        self.proxy
+            .set_property("Mode", mode.to_string())
             .await
             .context("Failed to set device mode")
     }
 
     pub async fn power_off(&self) -> Result<()> {
-        self.device
-            .set_power(false)
+        self.proxy
+            .set_property("Powered", false)
             .await
             .context("Failed to power off the device")
     }
 
     pub async fn power_on(&self) -> Result<()> {
-        self.device
-            .set_power(true)
+        self.proxy
+            .set_property("Powered", true)
             .await
             .context("Failed to power on the device")
     }
 
-    pub async fn refresh(&mut self) -> Result<()> {
-        self.is_powered = self.device.is_powered().await?;
-
-        let current_mode = self
-            .device
-            .get_mode()
-            .await
-            .context("Failed to retrieve current device mode")?;
-
-        self.update_mode(current_mode).await?;
+    pub async fn provisioning(&self) -> Result<DeviceProvisioning> {
+        DeviceProvisioning::new(&self.connection, &self.path).await
+    }
 
-        self.mode = current_mode;
-        Ok(())
+    pub async fn simple_configuration(&self) -> Result<SimpleConfiguration> {
+        SimpleConfiguration::new(&self.connection, &self.path).await
     }
 
-    async fn update_mode(&mut self, current_mode: Mode) -> Result<()> {
-        match current_mode {
-            Mode::Station => {
-                if self.mode == Mode::Station {
-                    if let Some(station) = &mut self.station {
-                        station
-                            .refresh()
-                            .await
-                            .context("Failed to refresh Station")?;
-                    }
-                } else {
-                    self.access_point = None;
-                    self.station = Self::initialize_station(self.session.clone()).await;
+    pub async fn apply_event(&mut self, event: &IwdEvent) -> Result<()> {
+        if let Some(value) = event.changed_property(DEVICE_INTERFACE, "Powered") {
+            self.is_powered = bool::try_from(value)?;
+        }
+        if let Some(value) = event.changed_property(DEVICE_INTERFACE, "Mode") {
+            self.mode = Mode::from_str(<&str>::try_from(value)?)
+                .map_err(|e| anyhow!("Invalid device mode: {e}"))?;
+        }
+
+        match event {
+            IwdEvent::InterfacesAdded { path, .. } if path == &self.path => {
+                if event.has_interface(STATION_INTERFACE) {
+                    self.station = Self::initialize_station(&self.connection, &self.path).await;
+                }
+                if event.has_interface(ACCESS_POINT_INTERFACE) {
+                    self.access_point =
+                        Self::initialize_access_point(&self.connection, &self.path).await;
                 }
             }
-            Mode::Ap => {
-                if self.mode == Mode::Ap {
-                    if let Some(access_point) = &mut self.access_point {
-                        access_point
-                            .refresh()
-                            .await
-                            .context("Failed to refresh AccessPoint")?;
-                    }
-                } else {
+            IwdEvent::InterfacesRemoved { path, .. } if path == &self.path => {
+                if event.has_interface(STATION_INTERFACE) {
                     self.station = None;
-                    self.access_point = Self::initialize_access_point(self.session.clone()).await;
+                }
+                if event.has_interface(ACCESS_POINT_INTERFACE) {
+                    self.access_point = None;
                 }
             }
+            _ => {}
+        }
+
+        if let Some(station) = &mut self.station {
+            station
+                .apply_event(event)
+                .await
+                .context("Failed to update Station")?;
+        }
+        if let Some(access_point) = &mut self.access_point {
+            access_point
+                .apply_event(event)
+                .await
+                .context("Failed to update AccessPoint")?;
         }
+
         Ok(())
     }
 }
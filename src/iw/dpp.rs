@@ -0,0 +1,40 @@
+use crate::iw::{events::DEVICE_PROVISIONING_INTERFACE, objects::interface_proxy};
+use anyhow::{Context, Result};
+use zbus::{zvariant::OwnedObjectPath, Connection, Proxy};
+
+/// Wi-Fi Easy Connect (DPP) on a station device.
+#[derive(Debug, Clone)]
+pub struct DeviceProvisioning {
+    proxy: Proxy<'static>,
+}
+
+impl DeviceProvisioning {
+    pub async fn new(connection: &Connection, device_path: &OwnedObjectPath) -> Result<Self> {
+        let proxy = interface_proxy(connection, device_path, DEVICE_PROVISIONING_INTERFACE).await?;
+
+        Ok(Self { proxy })
+    }
+
+    /// Waits for a configurator and returns the `DPP:` URI it has to scan.
+    pub async fn start_enrollee(&self) -> Result<String> {
+        self.proxy
+            .call("StartEnrollee", &())
+            .await
+            .context("Failed to start Easy Connect as enrollee")
+    }
+
+    /// Sends the connected network to the enrollee identified by `uri`.
+    pub async fn configure_enrollee(&self, uri: &str) -> Result<()> {
+        self.proxy
+            .call("ConfigureEnrollee", &(uri,))
+            .await
+            .context("Failed to configure the Easy Connect enrollee")
+    }
+
+    pub async fn stop(&self) -> Result<()> {
+        self.proxy
+            .call("Stop", &())
+            .await
+            .context("Failed to stop Easy Connect")
+    }
+}
@@ -0,0 +1,276 @@
+use crate::iw::profile::ssid_hex;
+use anyhow::{Context, Result};
+use futures_util::{FutureExt, StreamExt};
+use std::collections::HashMap;
+use zbus::{
+    message::Type,
+    zvariant::{OwnedObjectPath, OwnedValue},
+    Connection, MatchRule, Message, MessageStream,
+};
+
+pub const IWD_SERVICE: &str = "net.connman.iwd";
+pub const ADAPTER_INTERFACE: &str = "net.connman.iwd.Adapter";
+pub const DEVICE_INTERFACE: &str = "net.connman.iwd.Device";
+pub const STATION_INTERFACE: &str = "net.connman.iwd.Station";
+pub const ACCESS_POINT_INTERFACE: &str = "net.connman.iwd.AccessPoint";
+pub const NETWORK_INTERFACE: &str = "net.connman.iwd.Network";
+pub const KNOWN_NETWORK_INTERFACE: &str = "net.connman.iwd.KnownNetwork";
+pub const STATION_DIAGNOSTIC_INTERFACE: &str = "net.connman.iwd.StationDiagnostic";
+pub const ACCESS_POINT_DIAGNOSTIC_INTERFACE: &str = "net.connman.iwd.AccessPointDiagnostic";
+pub const DEVICE_PROVISIONING_INTERFACE: &str = "net.connman.iwd.DeviceProvisioning";
+pub const SIMPLE_CONFIGURATION_INTERFACE: &str = "net.connman.iwd.SimpleConfiguration";
+
+pub const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+pub const OBJECT_MANAGER_INTERFACE: &str = "org.freedesktop.DBus.ObjectManager";
+
+pub type Properties = HashMap<String, OwnedValue>;
+pub type ManagedObjects = HashMap<OwnedObjectPath, HashMap<String, Properties>>;
+
+#[derive(Debug)]
+pub enum IwdEvent {
+    PropertiesChanged {
+        path: OwnedObjectPath,
+        interface: String,
+        changed: Properties,
+        invalidated: Vec<String>,
+    },
+    InterfacesAdded {
+        path: OwnedObjectPath,
+        interfaces: Vec<String>,
+    },
+    InterfacesRemoved {
+        path: OwnedObjectPath,
+        interfaces: Vec<String>,
+    },
+}
+
+impl IwdEvent {
+    pub fn path(&self) -> &OwnedObjectPath {
+        match self {
+            IwdEvent::PropertiesChanged { path, .. }
+            | IwdEvent::InterfacesAdded { path, .. }
+            | IwdEvent::InterfacesRemoved { path, .. } => path,
+        }
+    }
+
+    pub fn has_interface(&self, name: &str) -> bool {
+        match self {
+            IwdEvent::PropertiesChanged { interface, .. } => interface == name,
+            IwdEvent::InterfacesAdded { interfaces, .. }
+            | IwdEvent::InterfacesRemoved { interfaces, .. } => {
+                interfaces.iter().any(|interface| interface == name)
+            }
+        }
+    }
+
+    pub fn changed_property(&self, name: &str, property: &str) -> Option<&OwnedValue> {
+        match self {
+            IwdEvent::PropertiesChanged {
+                interface, changed, ..
+            } if interface == name => changed.get(property),
+            _ => None,
+        }
+    }
+
+    pub fn touches_property(&self, name: &str, property: &str) -> bool {
+        match self {
+            IwdEvent::PropertiesChanged {
+                interface,
+                changed,
+                invalidated,
+                ..
+            } if interface == name => {
+                changed.contains_key(property) || invalidated.iter().any(|p| p == property)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the event announces the known 802.1X network iwd created for `ssid`, whose object
+    /// path ends with the hex-encoded SSID and the network type.
+    pub fn adds_known_network(&self, ssid: &str) -> bool {
+        match self {
+            IwdEvent::InterfacesAdded { path, interfaces } => {
+                interfaces
+                    .iter()
+                    .any(|interface| interface == KNOWN_NETWORK_INTERFACE)
+                    && path.as_str().rsplit('/').next()
+                        == Some(format!("{}_8021x", ssid_hex(ssid)).as_str())
+            }
+            _ => false,
+        }
+    }
+
+    fn from_message(message: &Message) -> Option<Self> {
+        let header = message.header();
+        let body = message.body();
+
+        match (header.interface()?.as_str(), header.member()?.as_str()) {
+            (PROPERTIES_INTERFACE, "PropertiesChanged") => {
+                let (interface, changed, invalidated): (String, Properties, Vec<String>) =
+                    body.deserialize().ok()?;
+
+                Some(IwdEvent::PropertiesChanged {
+                    path: header.path()?.to_owned().into(),
+                    interface,
+                    changed,
+                    invalidated,
+                })
+            }
+            (OBJECT_MANAGER_INTERFACE, "InterfacesAdded") => {
+                let (path, interfaces): (OwnedObjectPath, HashMap<String, Properties>) =
+                    body.deserialize().ok()?;
+
+                Some(IwdEvent::InterfacesAdded {
+                    path,
+                    interfaces: interfaces.into_keys().collect(),
+                })
+            }
+            (OBJECT_MANAGER_INTERFACE, "InterfacesRemoved") => {
+                let (path, interfaces): (OwnedObjectPath, Vec<String>) = body.deserialize().ok()?;
+
+                Some(IwdEvent::InterfacesRemoved { path, interfaces })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct WatchedDevice {
+    adapter_path: OwnedObjectPath,
+    device_path: OwnedObjectPath,
+}
+
+impl WatchedDevice {
+    fn is_relevant(&self, event: &IwdEvent) -> bool {
+        let path = event.path().as_str();
+        let device_path = self.device_path.as_str();
+
+        path == self.adapter_path.as_str()
+            || path == device_path
+            || path
+                .strip_prefix(device_path)
+                .is_some_and(|rest| rest.starts_with('/'))
+            || event.has_interface(KNOWN_NETWORK_INTERFACE)
+    }
+}
+
+pub struct IwdEvents {
+    connection: Connection,
+    stream: MessageStream,
+    watched_device: Option<WatchedDevice>,
+}
+
+impl IwdEvents {
+    pub async fn new() -> Result<Self> {
+        let connection = Connection::system()
+            .await
+            .context("Failed to connect to the system bus")?;
+
+        let rule = MatchRule::builder()
+            .msg_type(Type::Signal)
+            .sender(IWD_SERVICE)?
+            .build();
+
+        let stream = MessageStream::for_match_rule(rule, &connection, None)
+            .await
+            .context("Failed to subscribe to iwd signals")?;
+
+        Ok(Self {
+            connection,
+            stream,
+            watched_device: None,
+        })
+    }
+
+    pub fn watch_device(&mut self, adapter_path: OwnedObjectPath, device_path: OwnedObjectPath) {
+        self.watched_device = Some(WatchedDevice {
+            adapter_path,
+            device_path,
+        });
+    }
+
+    pub async fn next(&mut self) -> Option<IwdEvent> {
+        while let Some(message) = self.stream.next().await {
+            if let Some(event) = self.relevant_event(message) {
+                return Some(event);
+            }
+        }
+
+        None
+    }
+
+    /// Returns the events iwd sent so far, without waiting for new ones.
+    ///
+    /// A call to iwd acts as a barrier: its reply arrives after every signal iwd emitted before
+    /// handling it. Signals are consumed meanwhile, as a full queue would hold the reply back.
+    pub async fn pending(&mut self) -> Result<Vec<IwdEvent>> {
+        let barrier = self.connection.call_method(
+            Some(IWD_SERVICE),
+            "/",
+            Some(OBJECT_MANAGER_INTERFACE),
+            "GetManagedObjects",
+            &(),
+        );
+        tokio::pin!(barrier);
+
+        let mut events = Vec::new();
+        loop {
+            tokio::select! {
+                reply = &mut barrier => {
+                    reply.context("Failed to reach iwd")?;
+                    break;
+                }
+                Some(message) = self.stream.next() => events.extend(self.relevant_event(message)),
+            }
+        }
+
+        while let Some(Some(message)) = self.stream.next().now_or_never() {
+            events.extend(self.relevant_event(message));
+        }
+
+        Ok(events)
+    }
+
+    fn relevant_event(&self, message: zbus::Result<Message>) -> Option<IwdEvent> {
+        let event = IwdEvent::from_message(&message.ok()?)?;
+
+        self.watched_device
+            .as_ref()
+            .is_none_or(|watched| watched.is_relevant(&event))
+            .then_some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    fn known_network_added(path: &str) -> IwdEvent {
+        IwdEvent::InterfacesAdded {
+            path: OwnedObjectPath::try_from(path).unwrap(),
+            interfaces: vec![KNOWN_NETWORK_INTERFACE.to_string()],
+        }
+    }
+
+    #[test]
+    fn known_network_is_matched_by_its_whole_path_segment() {
+        // "ab" is 6162, "abc" is 616263
+        assert!(known_network_added("/net/connman/iwd/6162_8021x").adds_known_network("ab"));
+        assert!(!known_network_added("/net/connman/iwd/616263_8021x").adds_known_network("ab"));
+        assert!(!known_network_added("/net/connman/iwd/6162_psk").adds_known_network("ab"));
+        assert!(!known_network_added("/net/connman/iwd/006162_8021x").adds_known_network("ab"));
+    }
+
+    #[test]
+    fn other_interfaces_are_not_known_networks() {
+        let event = IwdEvent::InterfacesAdded {
+            path: OwnedObjectPath::try_from("/net/connman/iwd/6162_8021x").unwrap(),
+            interfaces: vec![NETWORK_INTERFACE.to_string()],
+        };
+
+        assert!(!event.adds_known_network("ab"));
+    }
+}
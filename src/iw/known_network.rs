@@ -1,47 +1,125 @@
-use anyhow::{Context, Result};
-use iwdrs::known_network::KnownNetwork as IwdKnownNetwork;
+use crate::{
+    iw::{
+        events::{IwdEvent, KNOWN_NETWORK_INTERFACE},
+        objects::{interface_proxy, IwdObjects},
+        profile::read_passphrase,
+    },
+    qr::wifi_payload,
+};
+use anyhow::{anyhow, Context, Result};
+use futures_util::future::join_all;
 use iwdrs::network::NetworkType;
+use log::warn;
+use std::{convert::TryFrom, path::Path};
+use zbus::{zvariant::OwnedObjectPath, Connection, Proxy};
 
 #[derive(Debug, Clone)]
 pub struct KnownNetwork {
-    pub n: IwdKnownNetwork,
+    proxy: Proxy<'static>,
+    pub path: OwnedObjectPath,
     pub name: String,
     pub network_type: NetworkType,
     pub is_autoconnect: bool,
     pub is_hidden: bool,
+    pub last_connected_time: Option<String>,
 }
 
 impl KnownNetwork {
-    pub async fn new(n: IwdKnownNetwork) -> Result<Self> {
-        let name = n.name().await?;
-        let network_type = n.network_type().await?;
+    pub async fn new(connection: &Connection, path: OwnedObjectPath) -> Result<Self> {
+        let proxy = interface_proxy(connection, &path, KNOWN_NETWORK_INTERFACE).await?;
 
-        let is_autoconnect = n
-            .get_autoconnect()
+        let name = proxy.get_property("Name").await?;
+        let network_type = proxy.get_property("Type").await?;
+
+        let is_autoconnect = proxy
+            .get_property("AutoConnect")
             .await
             .context("Failed to check the autoconnect setting")?;
 
-        let is_hidden = n.hidden().await?;
+        let is_hidden = proxy.get_property("Hidden").await?;
+
+        let last_connected_time = proxy.get_property("LastConnectedTime").await.ok();
 
         Ok(Self {
-            n,
+            proxy,
+            path,
             name,
             network_type,
             is_autoconnect,
             is_hidden,
+            last_connected_time,
         })
     }
 
+    pub async fn all(connection: &Connection) -> Result<Vec<Self>> {
+        let objects = IwdObjects::load(connection.clone())
+            .await
+            .context("Failed to retrieve known networks")?;
+
+        let paths = objects.paths(KNOWN_NETWORK_INTERFACE);
+        let mut networks = join_all(paths.into_iter().map(|path| Self::new(connection, path)))
+            .await
+            .into_iter()
+            .filter_map(|result| {
+                result
+                    .map_err(|e| warn!("Failed to load known network: {e}"))
+                    .ok()
+            })
+            .collect::<Vec<_>>();
+
+        networks.sort_by(|a, b| {
+            b.last_connected_time
+                .cmp(&a.last_connected_time)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        Ok(networks)
+    }
+
     pub async fn forget(&self) -> Result<()> {
-        self.n
-            .forget()
+        self.proxy
+            .call::<_, _, ()>("Forget", &())
             .await
             .context("Failed to forget the known network")
     }
 
+    pub fn apply_event(&mut self, event: &IwdEvent) -> Result<()> {
+        if let Some(value) = event.changed_property(KNOWN_NETWORK_INTERFACE, "AutoConnect") {
+            self.is_autoconnect = bool::try_from(value)?;
+        }
+        if let Some(value) = event.changed_property(KNOWN_NETWORK_INTERFACE, "Hidden") {
+            self.is_hidden = bool::try_from(value)?;
+        }
+        if event.touches_property(KNOWN_NETWORK_INTERFACE, "LastConnectedTime") {
+            self.last_connected_time = event
+                .changed_property(KNOWN_NETWORK_INTERFACE, "LastConnectedTime")
+                .and_then(|v| <&str>::try_from(v).ok())
+                .map(str::to_string);
+        }
+
+        Ok(())
+    }
+
+    /// Builds the Wi-Fi QR payload, reading the passphrase from the iwd profile in `state_dir`.
+    pub fn share_payload(&self, state_dir: &Path) -> Result<String> {
+        let passphrase = match self.network_type {
+            NetworkType::Psk => Some(read_passphrase(state_dir, &self.name)?.ok_or_else(|| {
+                anyhow!("The iwd profile only holds the pre-shared key, not the passphrase")
+            })?),
+            _ => None,
+        };
+
+        wifi_payload(
+            &self.name,
+            self.network_type,
+            passphrase.as_deref(),
+            self.is_hidden,
+        )
+    }
+
     pub async fn toggle_autoconnect(&self, enable: bool) -> Result<()> {
-        self.n
-            .set_autoconnect(enable)
+        self.proxy
+            .set_property("AutoConnect", enable)
             .await
             .context("Failed to toggle the autoconnect setting")
     }
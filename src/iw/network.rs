@@ -1,13 +1,20 @@
-use crate::iw::known_network::KnownNetwork;
+use crate::iw::{
+    events::{IwdEvent, NETWORK_INTERFACE},
+    known_network::KnownNetwork,
+    objects::interface_proxy,
+};
 use anyhow::{anyhow, Context, Result};
 use iwdrs::{
     error::{network::ConnectError, IWDError},
-    network::{Network as IwdNetwork, NetworkType},
+    network::NetworkType,
 };
+use std::convert::TryFrom;
+use zbus::{zvariant::OwnedObjectPath, Connection, Proxy};
 
 #[derive(Debug, Clone)]
 pub struct Network {
-    pub n: IwdNetwork,
+    proxy: Proxy<'static>,
+    pub path: OwnedObjectPath,
     pub name: String,
     pub network_type: NetworkType,
     pub is_connected: bool,
@@ -15,30 +22,30 @@ pub struct Network {
 }
 
 impl Network {
-    pub async fn new(n: IwdNetwork) -> Result<Self> {
-        let name = n.name().await?;
-        let network_type = n.network_type().await?;
+    pub async fn new(connection: &Connection, path: OwnedObjectPath) -> Result<Self> {
+        let proxy = interface_proxy(connection, &path, NETWORK_INTERFACE).await?;
+
+        let name = proxy.get_property("Name").await?;
+        let network_type = proxy.get_property("Type").await?;
 
-        let is_connected = n
-            .connected()
+        let is_connected = proxy
+            .get_property("Connected")
             .await
             .context("Failed to check if the network is connected")?;
 
-        let known_network = match n.known_network().await {
-            Ok(Some(net)) => Some(
-                KnownNetwork::new(net)
+        // Only networks with a profile have the property
+        let known_network = match proxy.get_property::<OwnedObjectPath>("KnownNetwork").await {
+            Ok(known_network_path) => Some(
+                KnownNetwork::new(connection, known_network_path)
                     .await
                     .context("Failed to initialize the known network")?,
             ),
-            Ok(None) => None,
-            Err(e) => {
-                eprintln!("Warning: Failed to retrieve known network information: {e}");
-                None
-            }
+            Err(_) => None,
         };
 
         Ok(Self {
-            n,
+            proxy,
+            path,
             name,
             network_type,
             is_connected,
@@ -47,33 +54,32 @@ impl Network {
     }
 
     pub async fn connect(&self) -> Result<()> {
-        self.n.connect().await.map_err(|e| match e {
-            IWDError::OperationError(ConnectError::Aborted) => {
-                anyhow!(t!("notifications.network.connection_canceled"))
-            }
-            IWDError::OperationError(ConnectError::InvalidFormat) => {
-                anyhow!(t!(
-                    "notifications.network.invalid_passphrase",
-                    network_name = self.name
-                ))
-            }
-            IWDError::OperationError(ConnectError::NoAgent) => {
-                anyhow!(t!("notifications.network.no_agent"))
-            }
-            IWDError::OperationError(ConnectError::NotConfigured) => {
-                anyhow!(t!(
-                    "notifications.network.not_configured",
-                    network_name = self.name
-                ))
-            }
-            IWDError::OperationError(ConnectError::Failed) => {
-                anyhow!(t!(
-                    "notifications.network.connection_failed",
-                    network_name = self.name
-                ))
-            }
-            other => other.into(),
-        })
+        self.proxy
+            .call::<_, _, ()>("Connect", &())
+            .await
+            .map_err(|e| map_connect_error(e.into(), &self.name))
+    }
+
+    pub async fn apply_event(&mut self, event: &IwdEvent) -> Result<()> {
+        if let Some(value) = event.changed_property(NETWORK_INTERFACE, "Connected") {
+            self.is_connected = bool::try_from(value)?;
+        }
+
+        if event.touches_property(NETWORK_INTERFACE, "KnownNetwork") {
+            self.known_network = match event.changed_property(NETWORK_INTERFACE, "KnownNetwork") {
+                Some(value) => {
+                    let path = OwnedObjectPath::try_from(value.try_clone()?)?;
+                    Some(
+                        KnownNetwork::new(self.proxy.connection(), path)
+                            .await
+                            .context("Failed to initialize the known network")?,
+                    )
+                }
+                None => None,
+            };
+        }
+
+        Ok(())
     }
 
     pub fn is_secure(&self) -> bool {
@@ -83,3 +89,148 @@ impl Network {
         )
     }
 }
+
+pub fn network_type_name(network_type: NetworkType) -> &'static str {
+    match network_type {
+        NetworkType::Open => "open",
+        NetworkType::Wep => "wep",
+        NetworkType::Psk => "psk",
+        NetworkType::Eap => "8021x",
+    }
+}
+
+pub fn connect_error(error: &anyhow::Error) -> Option<&ConnectError> {
+    match error.downcast_ref::<IWDError<ConnectError>>() {
+        Some(IWDError::OperationError(e)) => Some(e),
+        _ => None,
+    }
+}
+
+/// Whether iwd found no network with the requested name, as `ConnectHiddenNetwork` reports.
+pub fn is_not_found(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<IWDError<ConnectError>>(),
+        Some(IWDError::ZbusError(zbus::Error::MethodError(name, _, _)))
+            if name.as_str() == "net.connman.iwd.NotFound"
+    )
+}
+
+/// WPA-PSK passphrases are 8 to 63 printable ASCII characters, or the 64 hex digits of the
+/// pre-shared key itself.
+pub fn is_valid_passphrase(passphrase: &str) -> bool {
+    match passphrase.len() {
+        8..=63 => passphrase.chars().all(|c| (' '..='~').contains(&c)),
+        64 => passphrase.chars().all(|c| c.is_ascii_hexdigit()),
+        _ => false,
+    }
+}
+
+/// Whether iwd rejected the secrets given for the network. A wrong passphrase fails the
+/// handshake and is reported as `Failed`, which also covers the network being out of range, so
+/// it only counts when a passphrase was asked for during the attempt.
+pub fn is_auth_failure(error: &anyhow::Error, passphrase_requested: bool) -> bool {
+    match connect_error(error) {
+        Some(ConnectError::InvalidFormat) => true,
+        Some(ConnectError::Failed) => passphrase_requested,
+        _ => false,
+    }
+}
+
+pub fn map_connect_error(error: IWDError<ConnectError>, network_name: &str) -> anyhow::Error {
+    let msg = match &error {
+        IWDError::OperationError(ConnectError::Aborted) => {
+            t!("notifications.network.connection_canceled")
+        }
+        IWDError::OperationError(ConnectError::InvalidFormat) => t!(
+            "notifications.network.invalid_passphrase",
+            network_name = network_name
+        ),
+        IWDError::OperationError(ConnectError::NoAgent) => t!("notifications.network.no_agent"),
+        IWDError::OperationError(ConnectError::NotConfigured) => t!(
+            "notifications.network.not_configured",
+            network_name = network_name
+        ),
+        IWDError::OperationError(ConnectError::Failed) => t!(
+            "notifications.network.connection_failed",
+            network_name = network_name
+        ),
+        IWDError::ZbusError(zbus::Error::MethodError(name, _, _)) => match name.as_str() {
+            "net.connman.iwd.NotFound" => t!(
+                "notifications.network.not_found",
+                network_name = network_name
+            ),
+            "net.connman.iwd.NotHidden" => t!(
+                "notifications.network.not_hidden",
+                network_name = network_name
+            ),
+            _ => return error.into(),
+        },
+        _ => return error.into(),
+    };
+
+    anyhow!(error).context(msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operation_error(error: ConnectError) -> anyhow::Error {
+        anyhow!(IWDError::OperationError(error))
+    }
+
+    #[test]
+    fn passphrase_length_boundaries() {
+        assert!(!is_valid_passphrase("1234567"));
+        assert!(is_valid_passphrase("12345678"));
+        assert!(is_valid_passphrase(&"a".repeat(63)));
+        assert!(is_valid_passphrase(&"a".repeat(64)));
+        assert!(!is_valid_passphrase(&"a".repeat(65)));
+    }
+
+    #[test]
+    fn passphrase_of_64_characters_must_be_hex() {
+        assert!(is_valid_passphrase(
+            &"0123456789abcdefABCDEF".repeat(3)[..64]
+        ));
+        assert!(!is_valid_passphrase(&"g".repeat(64)));
+        assert!(!is_valid_passphrase(&format!("{} ", "a".repeat(63))));
+    }
+
+    #[test]
+    fn passphrase_rejects_non_printable_characters() {
+        assert!(is_valid_passphrase("pass word~"));
+        assert!(!is_valid_passphrase("pass\tword"));
+        assert!(!is_valid_passphrase("password\n"));
+        assert!(!is_valid_passphrase("pässword"));
+    }
+
+    #[test]
+    fn failed_after_a_prompt_is_an_auth_failure() {
+        assert!(is_auth_failure(
+            &operation_error(ConnectError::Failed),
+            true
+        ));
+        assert!(is_auth_failure(
+            &operation_error(ConnectError::Failed).context("Connection failed"),
+            true
+        ));
+        assert!(!is_auth_failure(
+            &operation_error(ConnectError::Failed),
+            false
+        ));
+    }
+
+    #[test]
+    fn invalid_format_is_an_auth_failure_and_aborted_is_not() {
+        assert!(is_auth_failure(
+            &operation_error(ConnectError::InvalidFormat).context("Invalid passphrase"),
+            true
+        ));
+        assert!(!is_auth_failure(
+            &operation_error(ConnectError::Aborted),
+            true
+        ));
+        assert!(!is_auth_failure(&anyhow!("Failed to connect"), true));
+    }
+}
@@ -0,0 +1,150 @@
+use crate::iw::events::{
+    ManagedObjects, Properties, ADAPTER_INTERFACE, DEVICE_INTERFACE, IWD_SERVICE,
+    OBJECT_MANAGER_INTERFACE,
+};
+use anyhow::{anyhow, Context, Result};
+use std::convert::TryFrom;
+use zbus::{
+    zvariant::{ObjectPath, OwnedObjectPath},
+    Connection, Proxy,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct DeviceSelection {
+    pub interface: Option<String>,
+    pub adapter: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeviceEntry {
+    pub path: OwnedObjectPath,
+    pub name: String,
+    pub adapter_path: OwnedObjectPath,
+    pub adapter_name: String,
+    pub adapter_model: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct IwdObjects {
+    connection: Connection,
+    objects: ManagedObjects,
+}
+
+impl IwdObjects {
+    pub async fn new() -> Result<Self> {
+        let connection = Connection::system()
+            .await
+            .context("Failed to connect to the system bus")?;
+
+        Self::load(connection).await
+    }
+
+    pub async fn load(connection: Connection) -> Result<Self> {
+        let proxy = Proxy::new(&connection, IWD_SERVICE, "/", OBJECT_MANAGER_INTERFACE)
+            .await
+            .context("Failed to create object manager proxy")?;
+
+        let objects: ManagedObjects = proxy
+            .call("GetManagedObjects", &())
+            .await
+            .context("Failed to retrieve iwd objects")?;
+
+        Ok(Self {
+            connection,
+            objects,
+        })
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    pub fn has_interface(&self, path: &OwnedObjectPath, interface: &str) -> bool {
+        self.objects
+            .get(path)
+            .is_some_and(|interfaces| interfaces.contains_key(interface))
+    }
+
+    /// Paths of the objects exposing `interface`.
+    pub fn paths(&self, interface: &str) -> Vec<OwnedObjectPath> {
+        self.objects
+            .iter()
+            .filter(|(_, interfaces)| interfaces.contains_key(interface))
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    pub fn devices(&self) -> Vec<DeviceEntry> {
+        let mut devices: Vec<DeviceEntry> = self
+            .objects
+            .iter()
+            .filter_map(|(path, interfaces)| {
+                let device = interfaces.get(DEVICE_INTERFACE)?;
+                let adapter_path = device
+                    .get("Adapter")
+                    .and_then(|v| OwnedObjectPath::try_from(v.try_clone().ok()?).ok())?;
+                let adapter = self
+                    .objects
+                    .get(&adapter_path)
+                    .and_then(|interfaces| interfaces.get(ADAPTER_INTERFACE));
+
+                Some(DeviceEntry {
+                    path: path.clone(),
+                    name: string_property(Some(device), "Name")?,
+                    adapter_name: string_property(adapter, "Name").unwrap_or_default(),
+                    adapter_model: string_property(adapter, "Model"),
+                    adapter_path,
+                })
+            })
+            .collect();
+
+        devices.sort_by(|a, b| a.name.cmp(&b.name));
+        devices
+    }
+
+    pub fn matching_devices(&self, selection: &DeviceSelection) -> Vec<DeviceEntry> {
+        self.devices()
+            .into_iter()
+            .filter(|device| {
+                selection
+                    .interface
+                    .as_ref()
+                    .is_none_or(|name| &device.name == name)
+                    && selection
+                        .adapter
+                        .as_ref()
+                        .is_none_or(|name| &device.adapter_name == name)
+            })
+            .collect()
+    }
+
+    pub fn device(&self, path: &ObjectPath<'_>) -> Result<DeviceEntry> {
+        self.devices()
+            .into_iter()
+            .find(|device| device.path.as_str() == path.as_str())
+            .ok_or_else(|| anyhow!("Device {path} not found"))
+    }
+}
+
+/// Builds a proxy for `interface` on the iwd object at `path`.
+pub async fn interface_proxy(
+    connection: &Connection,
+    path: &OwnedObjectPath,
+    interface: &'static str,
+) -> Result<Proxy<'static>> {
+    Proxy::new(
+        connection,
+        IWD_SERVICE,
+        path.clone().into_inner(),
+        interface,
+    )
+    .await
+    .with_context(|| format!("Failed to create {interface} proxy at {path}"))
+}
+
+fn string_property(properties: Option<&Properties>, name: &str) -> Option<String> {
+    properties?
+        .get(name)
+        .and_then(|v| <&str>::try_from(v).ok())
+        .map(str::to_string)
+}
@@ -0,0 +1,507 @@
+use anyhow::{Context, Result};
+use iwdrs::network::NetworkType;
+use std::{
+    fmt::Write as _,
+    fs::{self, OpenOptions},
+    io::Write,
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    path::{Path, PathBuf},
+};
+
+pub const DEFAULT_STATE_DIR: &str = "/var/lib/iwd";
+
+pub fn profile_file_name(ssid: &str, network_type: NetworkType) -> String {
+    let extension = match network_type {
+        NetworkType::Open => "open",
+        NetworkType::Wep => "wep",
+        NetworkType::Psk => "psk",
+        NetworkType::Eap => "8021x",
+    };
+
+    // iwd stores SSIDs with characters outside this set hex-encoded
+    let is_plain = ssid
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ' '));
+
+    if is_plain {
+        format!("{ssid}.{extension}")
+    } else {
+        format!("={}.{extension}", ssid_hex(ssid))
+    }
+}
+
+/// Hex form of the SSID, as used by iwd in file names and known network object paths.
+pub fn ssid_hex(ssid: &str) -> String {
+    ssid.bytes().fold(String::new(), |mut acc, b| {
+        let _ = write!(acc, "{b:02x}");
+        acc
+    })
+}
+
+pub fn profile_path(state_dir: &Path, ssid: &str, network_type: NetworkType) -> PathBuf {
+    state_dir.join(profile_file_name(ssid, network_type))
+}
+
+/// Reads the `Passphrase` iwd stored for a PSK network. Returns `None` when the profile only
+/// holds the derived `PreSharedKey`.
+pub fn read_passphrase(state_dir: &Path, ssid: &str) -> Result<Option<String>> {
+    let path = profile_path(state_dir, ssid, NetworkType::Psk);
+    let contents =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let mut in_security = false;
+    for line in contents.lines().map(str::trim) {
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_security = section == "Security";
+        } else if let Some(("Passphrase", value)) = line
+            .split_once('=')
+            .filter(|_| in_security)
+            .map(|(key, value)| (key.trim(), value))
+        {
+            return Ok(Some(unescape_value(value)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Replaces the secret stored in the iwd profile of a PSK network and returns the previous
+/// profile so it can be restored. Keys derived from the old secret are dropped so iwd computes
+/// them again on the next connection.
+pub fn update_passphrase(state_dir: &Path, ssid: &str, passphrase: &str) -> Result<String> {
+    let path = profile_path(state_dir, ssid, NetworkType::Psk);
+    let previous =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    // A 64 hex digit secret is the pre-shared key itself rather than a passphrase
+    let entry = if passphrase.len() == 64 {
+        format!("PreSharedKey={passphrase}")
+    } else {
+        format!("Passphrase={}", escape_value(passphrase))
+    };
+
+    let mut contents = String::new();
+    let mut in_security = false;
+    let mut has_security = false;
+
+    for line in previous.lines() {
+        let trimmed = line.trim();
+
+        if let Some(section) = trimmed.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_security = section == "Security";
+            let _ = writeln!(contents, "{line}");
+
+            if in_security && !has_security {
+                has_security = true;
+                let _ = writeln!(contents, "{entry}");
+            }
+            continue;
+        }
+
+        let key = trimmed.split_once('=').map(|(key, _)| key.trim());
+        let is_secret = key.is_some_and(|key| {
+            matches!(key, "Passphrase" | "PreSharedKey") || key.starts_with("SAE-PT-")
+        });
+
+        if !(in_security && is_secret) {
+            let _ = writeln!(contents, "{line}");
+        }
+    }
+
+    if !has_security {
+        if !contents.is_empty() {
+            contents.push('\n');
+        }
+        let _ = writeln!(contents, "[Security]\n{entry}");
+    }
+
+    write_profile(&path, &contents)?;
+
+    Ok(previous)
+}
+
+pub fn restore_profile(state_dir: &Path, ssid: &str, contents: &str) -> Result<()> {
+    write_profile(&profile_path(state_dir, ssid, NetworkType::Psk), contents)
+}
+
+/// Escapes a value the way iwd expects in its settings files.
+fn escape_value(value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\");
+
+    match escaped.strip_prefix(' ') {
+        Some(rest) => format!("\\s{rest}"),
+        None => escaped,
+    }
+}
+
+fn unescape_value(value: &str) -> String {
+    let mut result = String::new();
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('s')) => result.push(' '),
+            ('\\', Some('t')) => result.push('\t'),
+            ('\\', Some('n')) => result.push('\n'),
+            ('\\', Some('r')) => result.push('\r'),
+            ('\\', Some('\\')) => result.push('\\'),
+            _ => {
+                result.push(c);
+                continue;
+            }
+        }
+        chars.next();
+    }
+
+    result
+}
+
+/// Writes `contents` to `path`, only readable by the current user as the file holds secrets.
+pub(crate) fn write_profile(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+
+    // The mode above only applies to new files, an existing one keeps its permissions
+    file.set_permissions(fs::Permissions::from_mode(0o600))
+        .with_context(|| format!("Failed to restrict permissions of {}", path.display()))?;
+
+    file.write_all(contents.as_bytes())
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EapMethod {
+    Peap,
+    Ttls,
+}
+
+impl EapMethod {
+    pub const ALL: [EapMethod; 2] = [EapMethod::Peap, EapMethod::Ttls];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EapMethod::Peap => "PEAP",
+            EapMethod::Ttls => "TTLS",
+        }
+    }
+
+    pub fn phase2_methods(&self) -> &'static [Phase2Method] {
+        match self {
+            EapMethod::Peap => &[Phase2Method::MsChapV2, Phase2Method::Gtc, Phase2Method::Md5],
+            EapMethod::Ttls => &[
+                Phase2Method::TunneledPap,
+                Phase2Method::TunneledChap,
+                Phase2Method::TunneledMsChapV2,
+                Phase2Method::TunneledMsChap,
+                Phase2Method::MsChapV2,
+                Phase2Method::Gtc,
+                Phase2Method::Md5,
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase2Method {
+    MsChapV2,
+    Gtc,
+    Md5,
+    TunneledPap,
+    TunneledChap,
+    TunneledMsChapV2,
+    TunneledMsChap,
+}
+
+impl Phase2Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Phase2Method::MsChapV2 => "MSCHAPV2",
+            Phase2Method::Gtc => "GTC",
+            Phase2Method::Md5 => "MD5",
+            Phase2Method::TunneledPap => "Tunneled-PAP",
+            Phase2Method::TunneledChap => "Tunneled-CHAP",
+            Phase2Method::TunneledMsChapV2 => "Tunneled-MSCHAPv2",
+            Phase2Method::TunneledMsChap => "Tunneled-MSCHAP",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EapProfile {
+    pub ssid: String,
+    pub method: EapMethod,
+    pub phase2_method: Phase2Method,
+    pub identity: Option<String>,
+    pub ca_cert: Option<String>,
+    pub domain_mask: Option<String>,
+}
+
+impl EapProfile {
+    pub fn to_config(&self) -> String {
+        let method = self.method.as_str();
+        let mut config = String::from("[Security]\n");
+
+        let _ = writeln!(config, "EAP-Method={method}");
+        let _ = writeln!(
+            config,
+            "EAP-Identity={}",
+            self.identity.as_deref().unwrap_or("anonymous")
+        );
+
+        if let Some(ca_cert) = &self.ca_cert {
+            let _ = writeln!(config, "EAP-{method}-CACert={ca_cert}");
+        }
+        if let Some(domain_mask) = &self.domain_mask {
+            let _ = writeln!(config, "EAP-{method}-ServerDomainMask={domain_mask}");
+        }
+
+        let _ = writeln!(
+            config,
+            "EAP-{method}-Phase2-Method={}",
+            self.phase2_method.as_str()
+        );
+
+        // Without a phase 2 identity iwd asks the agent for both username and password
+        if let Some(identity) = &self.identity {
+            let _ = writeln!(config, "EAP-{method}-Phase2-Identity={identity}");
+        }
+
+        config
+    }
+
+    pub fn write(&self, state_dir: &Path) -> Result<PathBuf> {
+        let path = profile_path(state_dir, &self.ssid, NetworkType::Eap);
+        write_profile(&path, &self.to_config())?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("iwmenu-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_psk(dir: &Path, ssid: &str, contents: &str) -> PathBuf {
+        let path = profile_path(dir, ssid, NetworkType::Psk);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn write_profile_restricts_existing_files() {
+        let dir = state_dir("write-permissions");
+        let path = dir.join("settings.toml");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_profile(&path, "new").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn profile_file_name_keeps_plain_ssids() {
+        assert_eq!(profile_file_name("Home", NetworkType::Psk), "Home.psk");
+        assert_eq!(
+            profile_file_name("My-Net_2 G", NetworkType::Open),
+            "My-Net_2 G.open"
+        );
+        assert_eq!(profile_file_name("Corp", NetworkType::Eap), "Corp.8021x");
+        assert_eq!(profile_file_name("Old", NetworkType::Wep), "Old.wep");
+    }
+
+    #[test]
+    fn profile_file_name_hex_encodes_other_ssids() {
+        assert_eq!(profile_file_name("a.b", NetworkType::Psk), "=612e62.psk");
+        assert_eq!(
+            profile_file_name("Café", NetworkType::Eap),
+            "=436166c3a9.8021x"
+        );
+    }
+
+    #[test]
+    fn eap_profile_writes_peap_config() {
+        let dir = state_dir("eap-peap");
+        let profile = EapProfile {
+            ssid: "Corp".to_string(),
+            method: EapMethod::Peap,
+            phase2_method: Phase2Method::MsChapV2,
+            identity: Some("alice".to_string()),
+            ca_cert: Some("/etc/ssl/corp.pem".to_string()),
+            domain_mask: Some("radius.corp.example".to_string()),
+        };
+
+        let path = profile.write(&dir).unwrap();
+
+        assert_eq!(path, dir.join("Corp.8021x"));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[Security]\n\
+             EAP-Method=PEAP\n\
+             EAP-Identity=alice\n\
+             EAP-PEAP-CACert=/etc/ssl/corp.pem\n\
+             EAP-PEAP-ServerDomainMask=radius.corp.example\n\
+             EAP-PEAP-Phase2-Method=MSCHAPV2\n\
+             EAP-PEAP-Phase2-Identity=alice\n"
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn eap_profile_writes_ttls_config_without_identity() {
+        let dir = state_dir("eap-ttls");
+        let profile = EapProfile {
+            ssid: "Campus Wi-Fi!".to_string(),
+            method: EapMethod::Ttls,
+            phase2_method: Phase2Method::TunneledPap,
+            identity: None,
+            ca_cert: None,
+            domain_mask: None,
+        };
+
+        let path = profile.write(&dir).unwrap();
+
+        assert_eq!(path, dir.join("=43616d7075732057692d466921.8021x"));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[Security]\n\
+             EAP-Method=TTLS\n\
+             EAP-Identity=anonymous\n\
+             EAP-TTLS-Phase2-Method=Tunneled-PAP\n"
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn update_passphrase_keeps_other_sections() {
+        let dir = state_dir("update-sections");
+        let original = "[Settings]\nAutoConnect=false\n\n[Security]\nPassphrase=old-secret\n\
+                        PreSharedKey=abcd\nSAE-PT-Group19=1234\nSAE-PT-Group20=5678\n\n\
+                        [IPv4]\nAddress=192.168.1.2\n";
+        let path = write_psk(&dir, "Home", original);
+
+        let previous = update_passphrase(&dir, "Home", "new-secret").unwrap();
+
+        assert_eq!(previous, original);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[Settings]\nAutoConnect=false\n\n[Security]\nPassphrase=new-secret\n\n\
+             [IPv4]\nAddress=192.168.1.2\n"
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn update_passphrase_adds_missing_security_section() {
+        let dir = state_dir("update-missing");
+        let path = write_psk(&dir, "Home", "[Settings]\nAutoConnect=true\n");
+
+        update_passphrase(&dir, "Home", "new-secret").unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[Settings]\nAutoConnect=true\n\n[Security]\nPassphrase=new-secret\n"
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn update_passphrase_stores_hex_as_pre_shared_key() {
+        let dir = state_dir("update-psk");
+        let path = write_psk(&dir, "Home", "[Security]\nPassphrase=old-secret\n");
+        let psk = "0123456789abcdef".repeat(4);
+
+        update_passphrase(&dir, "Home", &psk).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!("[Security]\nPreSharedKey={psk}\n")
+        );
+        assert_eq!(read_passphrase(&dir, "Home").unwrap(), None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn update_passphrase_escapes_and_reads_back() {
+        let dir = state_dir("update-escape");
+        let path = write_psk(&dir, "Home", "[Security]\nPassphrase=old-secret\n");
+
+        update_passphrase(&dir, "Home", " leading space\\x").unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[Security]\nPassphrase=\\sleading space\\\\x\n"
+        );
+        assert_eq!(
+            read_passphrase(&dir, "Home").unwrap().as_deref(),
+            Some(" leading space\\x")
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn restore_profile_puts_previous_contents_back() {
+        let dir = state_dir("restore");
+        let original = "[Security]\nPassphrase=old-secret\n";
+        let path = write_psk(&dir, "Home", original);
+
+        let previous = update_passphrase(&dir, "Home", "new-secret").unwrap();
+        restore_profile(&dir, "Home", &previous).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn escape_value_handles_leading_space_and_backslashes() {
+        assert_eq!(escape_value("plain"), "plain");
+        assert_eq!(escape_value(" space"), "\\sspace");
+        assert_eq!(escape_value("inner space"), "inner space");
+        assert_eq!(escape_value("back\\slash"), "back\\\\slash");
+    }
+
+    #[test]
+    fn unescape_value_decodes_iwd_sequences() {
+        assert_eq!(unescape_value("\\sspace"), " space");
+        assert_eq!(unescape_value("a\\tb\\nc\\rd"), "a\tb\nc\rd");
+        assert_eq!(unescape_value("back\\\\slash"), "back\\slash");
+        assert_eq!(unescape_value("trailing\\"), "trailing\\");
+        assert_eq!(unescape_value("unknown\\q"), "unknown\\q");
+    }
+
+    #[test]
+    fn escaped_values_round_trip() {
+        for value in [" leading", "  two", "a\\b", "\\s literal", "plain", ""] {
+            assert_eq!(unescape_value(&escape_value(value)), value);
+        }
+    }
+}
@@ -1,133 +1,151 @@
-use anyhow::{anyhow, Result};
-use futures_util::future::join_all;
-use iwdrs::{session::Session, station::State};
-use std::sync::Arc;
-use tokio::time::Duration;
+use anyhow::{anyhow, Context, Result};
+use futures_util::{future::join_all, StreamExt};
+use iwdrs::station::State;
+use std::{cmp::Reverse, collections::HashMap, convert::TryFrom};
 
-use crate::iw::network::Network;
+use crate::iw::{
+    events::{
+        IwdEvent, KNOWN_NETWORK_INTERFACE, NETWORK_INTERFACE, STATION_DIAGNOSTIC_INTERFACE,
+        STATION_INTERFACE,
+    },
+    network::{map_connect_error, Network},
+    objects::interface_proxy,
+};
+use zbus::{
+    zvariant::{OwnedObjectPath, OwnedValue},
+    Connection, Proxy,
+};
+
+/// Link details of the connected network, from the `StationDiagnostic` interface.
+#[derive(Debug, Clone)]
+pub struct StationDiagnostic {
+    pub connected_bss: String,
+    pub frequency_mhz: u32,
+    pub channel: Option<u16>,
+    pub security: String,
+    pub rssi: Option<i16>,
+    pub rx_mode: Option<String>,
+    pub rx_rate_kbps: Option<u64>,
+    pub tx_mode: Option<String>,
+    pub tx_rate_kbps: Option<u64>,
+    pub pairwise_cipher: Option<String>,
+}
+
+impl StationDiagnostic {
+    fn from_diagnostic(data: &HashMap<String, OwnedValue>) -> Option<Self> {
+        let string = |key: &str| {
+            data.get(key)
+                .and_then(|v| <&str>::try_from(v).ok())
+                .map(str::to_string)
+        };
+        let rate = |key: &str| {
+            data.get(key)
+                .and_then(|v| u32::try_from(v).ok())
+                .map(|rate_100_kbps| 100 * u64::from(rate_100_kbps))
+        };
+
+        Some(Self {
+            connected_bss: string("ConnectedBss")?,
+            frequency_mhz: data.get("Frequency").and_then(|v| u32::try_from(v).ok())?,
+            channel: data.get("Channel").and_then(|v| u16::try_from(v).ok()),
+            security: string("Security")?,
+            rssi: data.get("RSSI").and_then(|v| i16::try_from(v).ok()),
+            rx_mode: string("RxMode"),
+            rx_rate_kbps: rate("RxBitrate"),
+            tx_mode: string("TxMode"),
+            tx_rate_kbps: rate("TxBitrate"),
+            pairwise_cipher: string("PairwiseCipher"),
+        })
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Station {
-    pub session: Arc<Session>,
+    connection: Connection,
+    proxy: Proxy<'static>,
+    pub device_path: OwnedObjectPath,
+    connected_path: Option<OwnedObjectPath>,
     pub state: State,
     pub is_scanning: bool,
     pub connected_network: Option<Network>,
     pub new_networks: Vec<(Network, i16)>,
     pub known_networks: Vec<(Network, i16)>,
-    pub diagnostic: Option<iwdrs::station::diagnostics::ActiveStationDiagnostics>,
+    pub diagnostic: Option<StationDiagnostic>,
 }
 
 impl Station {
-    pub async fn new(session: Arc<Session>) -> Result<Self> {
-        let stations = session.stations().await?;
-        let iwd_station = stations
-            .into_iter()
-            .next()
-            .ok_or_else(|| anyhow!("Failed to retrieve station from session"))?;
-
-        let iwd_station_diagnostic = session
-            .stations_diagnostics()
-            .await
-            .ok()
-            .and_then(|v| v.into_iter().next());
-
-        let state = iwd_station.state().await?;
-
-        let connected_network = if let Some(n) = iwd_station.connected_network().await? {
-            Some(Network::new(n.clone()).await?)
-        } else {
-            None
-        };
-
-        let is_scanning = iwd_station.is_scanning().await?;
-
-        let discovered_networks = iwd_station.discovered_networks().await?;
-
-        let networks = {
-            let collected_futures = discovered_networks
-                .iter()
-                .map(|(n, signal)| async move {
-                    Network::new(n.clone())
-                        .await
-                        .map(|network| (network, *signal))
-                        .map_err(|e| anyhow!("Failed to create network: {e:?}"))
-                })
-                .collect::<Vec<_>>();
-
-            join_all(collected_futures)
-                .await
-                .into_iter()
-                .filter_map(Result::ok)
-                .collect::<Vec<(Network, i16)>>()
-        };
-
-        let new_networks: Vec<(Network, i16)> = networks
-            .iter()
-            .filter(|(net, _)| net.known_network.is_none())
-            .cloned()
-            .collect();
-
-        let known_networks: Vec<(Network, i16)> = networks
-            .into_iter()
-            .filter(|(net, _)| net.known_network.is_some())
-            .collect();
+    pub async fn new(connection: Connection, device_path: OwnedObjectPath) -> Result<Self> {
+        let proxy = interface_proxy(&connection, &device_path, STATION_INTERFACE).await?;
 
-        let diagnostic = if let Some(station_diagnostic) = iwd_station_diagnostic {
-            station_diagnostic.get().await.ok()
-        } else {
-            None
-        };
+        let state = proxy.get_property("State").await?;
+        let is_scanning = proxy.get_property("Scanning").await?;
+        // Only set while connecting or connected
+        let connected_path = proxy.get_property("ConnectedNetwork").await.ok();
 
-        Ok(Self {
-            session,
+        let mut station = Self {
+            connection,
+            proxy,
+            device_path,
+            connected_path,
             state,
             is_scanning,
-            connected_network,
-            new_networks,
-            known_networks,
-            diagnostic,
-        })
+            connected_network: None,
+            new_networks: Vec::new(),
+            known_networks: Vec::new(),
+            diagnostic: None,
+        };
+        station.refresh_networks().await?;
+
+        Ok(station)
     }
 
-    pub async fn refresh(&mut self) -> Result<()> {
-        let stations = self.session.stations().await?;
-        let station = stations
-            .into_iter()
-            .next()
-            .ok_or_else(|| anyhow!("Failed to retrieve station from session"))?;
+    async fn fetch_connected_network(&self) -> Result<Option<Network>> {
+        match (&self.connected_path, self.state) {
+            (Some(path), State::Connected) => Ok(Some(self.network_at(path).await?)),
+            _ => Ok(None),
+        }
+    }
 
-        self.state = station.state().await?;
-        self.is_scanning = station.is_scanning().await?;
+    /// The network at `path`, reusing the listed entry when there is one.
+    async fn network_at(&self, path: &OwnedObjectPath) -> Result<Network> {
+        let listed = self
+            .new_networks
+            .iter()
+            .chain(&self.known_networks)
+            .find(|(network, _)| &network.path == path);
 
-        if self.is_scanning {
-            while station.is_scanning().await? {
-                tokio::time::sleep(Duration::from_millis(500)).await;
-            }
+        match listed {
+            Some((network, _)) => Ok(network.clone()),
+            None => Network::new(&self.connection, path.clone()).await,
         }
+    }
 
-        self.connected_network = if let Some(n) = station.connected_network().await? {
-            Some(Network::new(n.clone()).await?)
-        } else {
-            None
-        };
-
-        let discovered_networks = station.discovered_networks().await?;
+    async fn fetch_networks(&self) -> Result<Vec<(Network, i16)>> {
+        let discovered_networks: Vec<(OwnedObjectPath, i16)> = self
+            .proxy
+            .call("GetOrderedNetworks", &())
+            .await
+            .context("Failed to retrieve discovered networks")?;
 
         let network_futures = discovered_networks
             .into_iter()
-            .map(|(n, signal)| async move {
-                Network::new(n.clone())
+            .map(|(path, signal)| async move {
+                Network::new(&self.connection, path)
                     .await
                     .map(|network| (network, signal))
                     .map_err(|e| anyhow!("Failed to process network: {e:?}"))
             })
             .collect::<Vec<_>>();
 
-        let networks = join_all(network_futures)
+        Ok(join_all(network_futures)
             .await
             .into_iter()
             .filter_map(Result::ok)
-            .collect::<Vec<(Network, i16)>>();
+            .collect())
+    }
+
+    async fn refresh_networks(&mut self) -> Result<()> {
+        let networks = self.fetch_networks().await?;
 
         self.new_networks = networks
             .iter()
@@ -136,43 +154,228 @@ impl Station {
             .collect();
 
         self.known_networks = networks
-            .iter()
+            .into_iter()
             .filter(|(net, _)| net.known_network.is_some())
-            .cloned()
             .collect();
 
-        if let Ok(diagnostics) = self.session.stations_diagnostics().await {
-            if let Some(diagnostic) = diagnostics.into_iter().next() {
-                self.diagnostic = diagnostic.get().await.ok();
+        self.connected_network = self.fetch_connected_network().await?;
+        self.diagnostic = self.fetch_diagnostic().await;
+
+        Ok(())
+    }
+
+    fn networks_mut(&mut self) -> impl Iterator<Item = &mut Network> {
+        self.connected_network
+            .iter_mut()
+            .chain(self.new_networks.iter_mut().map(|(network, _)| network))
+            .chain(self.known_networks.iter_mut().map(|(network, _)| network))
+    }
+
+    /// Moves networks whose profile was created or removed to the matching list, both staying
+    /// ordered by signal strength.
+    fn sort_networks(&mut self) {
+        let mut networks: Vec<(Network, i16)> = self
+            .new_networks
+            .drain(..)
+            .chain(self.known_networks.drain(..))
+            .collect();
+        networks.sort_by_key(|(_, signal)| Reverse(*signal));
+
+        let (known_networks, new_networks) = networks
+            .into_iter()
+            .partition(|(network, _)| network.known_network.is_some());
+
+        self.known_networks = known_networks;
+        self.new_networks = new_networks;
+    }
+
+    async fn add_network(&mut self, path: &OwnedObjectPath) -> Result<()> {
+        if self.networks_mut().any(|network| &network.path == path) {
+            return Ok(());
+        }
+
+        // Signal strengths are only reported with the ordered list
+        let ordered: Vec<(OwnedObjectPath, i16)> = self
+            .proxy
+            .call("GetOrderedNetworks", &())
+            .await
+            .context("Failed to retrieve discovered networks")?;
+        let Some((_, signal)) = ordered.into_iter().find(|(p, _)| p == path) else {
+            return Ok(());
+        };
+
+        let network = Network::new(&self.connection, path.clone()).await?;
+        self.new_networks.push((network, signal));
+        self.sort_networks();
+
+        Ok(())
+    }
+
+    fn remove_network(&mut self, path: &OwnedObjectPath) {
+        self.new_networks
+            .retain(|(network, _)| &network.path != path);
+        self.known_networks
+            .retain(|(network, _)| &network.path != path);
+
+        if self
+            .connected_network
+            .as_ref()
+            .is_some_and(|network| &network.path == path)
+        {
+            self.connected_network = None;
+        }
+    }
+
+    /// Diagnostics only exist while connected, so any failure means there are none.
+    async fn fetch_diagnostic(&self) -> Option<StationDiagnostic> {
+        let proxy = interface_proxy(
+            &self.connection,
+            &self.device_path,
+            STATION_DIAGNOSTIC_INTERFACE,
+        )
+        .await
+        .ok()?;
+
+        let data: HashMap<String, OwnedValue> = proxy.call("GetDiagnostics", &()).await.ok()?;
+
+        StationDiagnostic::from_diagnostic(&data)
+    }
+
+    pub async fn refresh_diagnostic(&mut self) -> Result<()> {
+        self.diagnostic = self.fetch_diagnostic().await;
+
+        Ok(())
+    }
+
+    /// Updates the station from a signal, touching only the network or known network living at
+    /// the signal's path.
+    pub async fn apply_event(&mut self, event: &IwdEvent) -> Result<()> {
+        if event.path() == &self.device_path {
+            return self.apply_station_event(event).await;
+        }
+
+        let path = event.path();
+        match event {
+            IwdEvent::PropertiesChanged { interface, .. } if interface == NETWORK_INTERFACE => {
+                for network in self.networks_mut().filter(|network| &network.path == path) {
+                    network.apply_event(event).await?;
+                }
+                self.sort_networks();
+            }
+            IwdEvent::PropertiesChanged { interface, .. }
+                if interface == KNOWN_NETWORK_INTERFACE =>
+            {
+                for known_network in self
+                    .networks_mut()
+                    .filter_map(|network| network.known_network.as_mut())
+                    .filter(|known_network| &known_network.path == path)
+                {
+                    known_network.apply_event(event)?;
+                }
+            }
+            // Networks appear one by one during a scan, which reloads them once it completes
+            IwdEvent::InterfacesAdded { .. }
+                if event.has_interface(NETWORK_INTERFACE) && !self.is_scanning =>
+            {
+                self.add_network(path).await?;
+            }
+            IwdEvent::InterfacesRemoved { .. } => {
+                if event.has_interface(NETWORK_INTERFACE) {
+                    self.remove_network(path);
+                }
+                if event.has_interface(KNOWN_NETWORK_INTERFACE) {
+                    for network in self.networks_mut() {
+                        if network
+                            .known_network
+                            .as_ref()
+                            .is_some_and(|known_network| &known_network.path == path)
+                        {
+                            network.known_network = None;
+                        }
+                    }
+                    self.sort_networks();
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    async fn apply_station_event(&mut self, event: &IwdEvent) -> Result<()> {
+        let mut connection_changed = false;
+
+        if let Some(value) = event.changed_property(STATION_INTERFACE, "State") {
+            self.state = State::try_from(value.try_clone()?)?;
+            connection_changed = true;
+        }
+        if event.touches_property(STATION_INTERFACE, "ConnectedNetwork") {
+            self.connected_path = event
+                .changed_property(STATION_INTERFACE, "ConnectedNetwork")
+                .map(|value| OwnedObjectPath::try_from(value.try_clone()?))
+                .transpose()?;
+            connection_changed = true;
+        }
+
+        if connection_changed {
+            self.connected_network = self.fetch_connected_network().await?;
+            self.diagnostic = self.fetch_diagnostic().await;
+        }
+
+        if let Some(value) = event.changed_property(STATION_INTERFACE, "Scanning") {
+            self.is_scanning = bool::try_from(value)?;
+
+            // Signal strengths only come with the ordered list, so it is reloaded after a scan
+            if !self.is_scanning {
+                self.refresh_networks().await?;
             }
         }
 
         Ok(())
     }
 
-    pub async fn scan(&self) -> Result<()> {
-        let stations = self.session.stations().await?;
-        let station = stations
-            .into_iter()
-            .next()
-            .ok_or_else(|| anyhow!("Failed to retrieve station from session"))?;
+    pub async fn wait_for_scan_complete(&self) -> Result<()> {
+        let mut changes = self
+            .proxy
+            .receive_property_changed::<bool>("Scanning")
+            .await;
 
-        station
-            .scan()
+        let is_scanning: bool = self.proxy.get_property("Scanning").await?;
+        if !is_scanning {
+            return Ok(());
+        }
+
+        while let Some(change) = changes.next().await {
+            let is_scanning = change
+                .get()
+                .await
+                .context("Failed to wait for scan completion")?;
+            if !is_scanning {
+                return Ok(());
+            }
+        }
+
+        Err(anyhow!("Failed to wait for scan completion"))
+    }
+
+    pub async fn scan(&self) -> Result<()> {
+        self.proxy
+            .call::<_, _, ()>("Scan", &())
             .await
             .map_err(|e| anyhow!("Failed to start scan: {e:?}"))
     }
 
     pub async fn disconnect(&mut self) -> Result<()> {
-        let stations = self.session.stations().await?;
-        let station = stations
-            .into_iter()
-            .next()
-            .ok_or_else(|| anyhow!("Failed to retrieve station from session"))?;
-
-        station
-            .disconnect()
+        self.proxy
+            .call::<_, _, ()>("Disconnect", &())
             .await
             .map_err(|e| anyhow!("Failed to disconnect: {e:?}"))
     }
+
+    pub async fn connect_hidden_network(&self, ssid: &str) -> Result<()> {
+        self.proxy
+            .call::<_, _, ()>("ConnectHiddenNetwork", &(ssid,))
+            .await
+            .map_err(|e| map_connect_error(e.into(), ssid))
+    }
 }
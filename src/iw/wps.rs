@@ -0,0 +1,117 @@
+use crate::iw::{events::SIMPLE_CONFIGURATION_INTERFACE, objects::interface_proxy};
+use anyhow::{Context, Result};
+use zbus::{zvariant::OwnedObjectPath, Connection, Proxy};
+
+/// How long access points accept a push-button session, from the WPS specification.
+pub const WALK_TIME_SECS: u64 = 120;
+
+/// Wi-Fi Protected Setup on a station device.
+#[derive(Debug, Clone)]
+pub struct SimpleConfiguration {
+    proxy: Proxy<'static>,
+}
+
+impl SimpleConfiguration {
+    pub async fn new(connection: &Connection, device_path: &OwnedObjectPath) -> Result<Self> {
+        let proxy =
+            interface_proxy(connection, device_path, SIMPLE_CONFIGURATION_INTERFACE).await?;
+
+        Ok(Self { proxy })
+    }
+
+    /// Waits for the push button of an access point, then connects to it.
+    pub async fn push_button(&self) -> Result<()> {
+        self.proxy
+            .call("PushButton", &())
+            .await
+            .context("WPS push-button connection failed")
+    }
+
+    pub async fn generate_pin(&self) -> Result<String> {
+        self.proxy
+            .call("GeneratePin", &())
+            .await
+            .context("Failed to generate a WPS PIN")
+    }
+
+    /// Connects to the access point `pin` was entered in.
+    pub async fn start_pin(&self, pin: &str) -> Result<()> {
+        self.proxy
+            .call("StartPin", &(pin,))
+            .await
+            .context("WPS PIN connection failed")
+    }
+
+    pub async fn cancel(&self) -> Result<()> {
+        self.proxy
+            .call("Cancel", &())
+            .await
+            .context("Failed to cancel WPS")
+    }
+}
+
+/// Returns whether `error` comes from a WPS session canceled with [`SimpleConfiguration::cancel`].
+pub fn is_aborted(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<zbus::Error>(),
+        Some(zbus::Error::MethodError(name, _, _)) if name.as_str() == "net.connman.iwd.Aborted"
+    )
+}
+
+/// WPS PINs are 4 or 8 digits long, the last of 8 being a checksum.
+pub fn is_valid_pin(pin: &str) -> bool {
+    if !pin.chars().all(|c| c.is_ascii_digit()) {
+        return false;
+    }
+
+    match pin.len() {
+        4 => true,
+        8 => {
+            let digits: Vec<u32> = pin.chars().filter_map(|c| c.to_digit(10)).collect();
+            let sum: u32 = digits
+                .iter()
+                .enumerate()
+                .map(|(index, digit)| {
+                    if index.is_multiple_of(2) {
+                        digit * 3
+                    } else {
+                        *digit
+                    }
+                })
+                .sum();
+
+            sum.is_multiple_of(10)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn four_digit_pins_have_no_checksum() {
+        assert!(is_valid_pin("0000"));
+        assert!(is_valid_pin("1234"));
+    }
+
+    #[test]
+    fn eight_digit_pins_need_a_valid_checksum() {
+        assert!(is_valid_pin("12345670"));
+        assert!(is_valid_pin("00000000"));
+        assert!(!is_valid_pin("12345678"));
+        assert!(!is_valid_pin("12345671"));
+    }
+
+    #[test]
+    fn pins_of_other_lengths_or_with_non_digits_are_rejected() {
+        assert!(!is_valid_pin(""));
+        assert!(!is_valid_pin("123"));
+        assert!(!is_valid_pin("123456"));
+        assert!(!is_valid_pin("123456700"));
+        assert!(!is_valid_pin("1234567a"));
+        assert!(!is_valid_pin("12 4"));
+        assert!(!is_valid_pin("１２３４"));
+    }
+}
@@ -0,0 +1,354 @@
+use crate::iw::{
+    access_point::AccessPoint, adapter::Adapter, device::Device, network::network_type_name,
+    network::Network, station::Station,
+};
+use iwdrs::network::NetworkType;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct NetworkJson<'a> {
+    pub ssid: &'a str,
+    #[serde(rename = "type")]
+    pub network_type: &'static str,
+    pub signal_dbm: Option<i16>,
+    pub connected: bool,
+    pub known: bool,
+    pub autoconnect: bool,
+    pub hidden: bool,
+}
+
+impl<'a> NetworkJson<'a> {
+    pub fn new(network: &'a Network, signal_strength: Option<i16>) -> Self {
+        let known_flags = network
+            .known_network
+            .as_ref()
+            .map(|known| (known.is_autoconnect, known.is_hidden));
+
+        Self::from_parts(
+            &network.name,
+            network.network_type,
+            network.is_connected,
+            known_flags,
+            signal_strength,
+        )
+    }
+
+    /// `known_flags` holds the autoconnect and hidden settings of a known network, and
+    /// `signal_strength` is in hundredths of dBm as iwd reports it.
+    fn from_parts(
+        ssid: &'a str,
+        network_type: NetworkType,
+        connected: bool,
+        known_flags: Option<(bool, bool)>,
+        signal_strength: Option<i16>,
+    ) -> Self {
+        Self {
+            ssid,
+            network_type: network_type_name(network_type),
+            signal_dbm: signal_strength.map(|signal| signal / 100),
+            connected,
+            known: known_flags.is_some(),
+            autoconnect: known_flags.is_some_and(|(autoconnect, _)| autoconnect),
+            hidden: known_flags.is_some_and(|(_, hidden)| hidden),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct StationJson<'a> {
+    pub state: String,
+    pub scanning: bool,
+    pub connected_network: Option<NetworkJson<'a>>,
+}
+
+impl<'a> From<&'a Station> for StationJson<'a> {
+    fn from(station: &'a Station) -> Self {
+        let connected_network = station.connected_network.as_ref().map(|network| {
+            let signal_strength = station
+                .known_networks
+                .iter()
+                .find(|(net, _)| net.name == network.name)
+                .map(|(_, signal)| *signal);
+
+            NetworkJson::new(network, signal_strength)
+        });
+
+        Self {
+            state: station.state.to_string().to_lowercase(),
+            scanning: station.is_scanning,
+            connected_network,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeviceJson<'a> {
+    pub name: &'a str,
+    pub address: &'a str,
+    pub mode: String,
+    pub powered: bool,
+}
+
+impl<'a> From<&'a Device> for DeviceJson<'a> {
+    fn from(device: &'a Device) -> Self {
+        Self {
+            name: &device.name,
+            address: &device.address,
+            mode: device.mode.to_string(),
+            powered: device.is_powered,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdapterJson<'a> {
+    pub name: &'a str,
+    pub model: Option<&'a str>,
+    pub vendor: Option<&'a str>,
+    pub supported_modes: &'a [String],
+    pub powered: bool,
+    pub device: DeviceJson<'a>,
+}
+
+impl<'a> From<&'a Adapter> for AdapterJson<'a> {
+    fn from(adapter: &'a Adapter) -> Self {
+        Self {
+            name: &adapter.name,
+            model: adapter.model.as_deref(),
+            vendor: adapter.vendor.as_deref(),
+            supported_modes: &adapter.supported_modes,
+            powered: adapter.is_powered,
+            device: DeviceJson::from(&adapter.device),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccessPointJson<'a> {
+    pub started: bool,
+    pub name: Option<&'a str>,
+    pub frequency: Option<u32>,
+    pub ciphers: Option<&'a [String]>,
+    pub group_cipher: Option<&'a str>,
+    pub connected_devices: Vec<&'a str>,
+}
+
+impl<'a> From<&'a AccessPoint> for AccessPointJson<'a> {
+    fn from(access_point: &'a AccessPoint) -> Self {
+        Self {
+            started: access_point.has_started,
+            name: access_point.name.as_deref(),
+            frequency: access_point.frequency,
+            ciphers: access_point.supported_ciphers.as_deref(),
+            group_cipher: access_point.used_cipher.as_deref(),
+            connected_devices: access_point
+                .clients
+                .iter()
+                .map(|client| client.address.as_str())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatusJson<'a> {
+    pub adapter: AdapterJson<'a>,
+    pub station: Option<StationJson<'a>>,
+    pub access_point: Option<AccessPointJson<'a>>,
+}
+
+impl<'a> From<&'a Adapter> for StatusJson<'a> {
+    fn from(adapter: &'a Adapter) -> Self {
+        Self {
+            adapter: AdapterJson::from(adapter),
+            station: adapter.device.station.as_ref().map(StationJson::from),
+            access_point: adapter
+                .device
+                .access_point
+                .as_ref()
+                .map(AccessPointJson::from),
+        }
+    }
+}
+
+pub fn list_networks(station: &Station) -> Vec<NetworkJson<'_>> {
+    station
+        .known_networks
+        .iter()
+        .chain(station.new_networks.iter())
+        .map(|(network, signal_strength)| NetworkJson::new(network, Some(*signal_strength)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, to_value};
+
+    fn network() -> NetworkJson<'static> {
+        NetworkJson {
+            ssid: "Home",
+            network_type: "psk",
+            signal_dbm: Some(-52),
+            connected: true,
+            known: true,
+            autoconnect: true,
+            hidden: false,
+        }
+    }
+
+    #[test]
+    fn network_fields() {
+        assert_eq!(
+            to_value(network()).unwrap(),
+            json!({
+                "ssid": "Home",
+                "type": "psk",
+                "signal_dbm": -52,
+                "connected": true,
+                "known": true,
+                "autoconnect": true,
+                "hidden": false,
+            })
+        );
+    }
+
+    #[test]
+    fn network_signal_is_converted_to_dbm() {
+        let network = NetworkJson::from_parts("Home", NetworkType::Psk, true, None, Some(-5250));
+
+        assert_eq!(network.signal_dbm, Some(-52));
+        assert_eq!(network.network_type, "psk");
+        assert!(network.connected);
+    }
+
+    #[test]
+    fn network_flags_come_from_the_known_network() {
+        let unknown = NetworkJson::from_parts("Cafe", NetworkType::Open, false, None, None);
+        assert!(!unknown.known && !unknown.autoconnect && !unknown.hidden);
+
+        let known = NetworkJson::from_parts(
+            "Home",
+            NetworkType::Eap,
+            false,
+            Some((true, false)),
+            Some(-7000),
+        );
+        assert!(known.known && known.autoconnect && !known.hidden);
+        assert_eq!(known.network_type, "8021x");
+
+        let hidden =
+            NetworkJson::from_parts("Lab", NetworkType::Psk, false, Some((false, true)), None);
+        assert!(hidden.known && !hidden.autoconnect && hidden.hidden);
+    }
+
+    #[test]
+    fn network_without_signal_is_null() {
+        let network = NetworkJson {
+            signal_dbm: None,
+            ..network()
+        };
+
+        assert_eq!(to_value(network).unwrap()["signal_dbm"], json!(null));
+    }
+
+    #[test]
+    fn status_fields() {
+        let modes = ["station".to_string(), "ap".to_string()];
+        let status = StatusJson {
+            adapter: AdapterJson {
+                name: "phy0",
+                model: Some("AX200"),
+                vendor: None,
+                supported_modes: &modes,
+                powered: true,
+                device: DeviceJson {
+                    name: "wlan0",
+                    address: "aa:bb:cc:dd:ee:ff",
+                    mode: "station".to_string(),
+                    powered: true,
+                },
+            },
+            station: Some(StationJson {
+                state: "connected".to_string(),
+                scanning: false,
+                connected_network: Some(network()),
+            }),
+            access_point: None,
+        };
+
+        assert_eq!(
+            to_value(status).unwrap(),
+            json!({
+                "adapter": {
+                    "name": "phy0",
+                    "model": "AX200",
+                    "vendor": null,
+                    "supported_modes": ["station", "ap"],
+                    "powered": true,
+                    "device": {
+                        "name": "wlan0",
+                        "address": "aa:bb:cc:dd:ee:ff",
+                        "mode": "station",
+                        "powered": true,
+                    },
+                },
+                "station": {
+                    "state": "connected",
+                    "scanning": false,
+                    "connected_network": to_value(network()).unwrap(),
+                },
+                "access_point": null,
+            })
+        );
+    }
+
+    #[test]
+    fn access_point_fields() {
+        let ciphers = ["CCMP".to_string()];
+        let access_point = AccessPointJson {
+            started: true,
+            name: Some("Hotspot"),
+            frequency: Some(2437),
+            ciphers: Some(&ciphers),
+            group_cipher: Some("CCMP"),
+            connected_devices: vec!["11:22:33:44:55:66"],
+        };
+
+        assert_eq!(
+            to_value(access_point).unwrap(),
+            json!({
+                "started": true,
+                "name": "Hotspot",
+                "frequency": 2437,
+                "ciphers": ["CCMP"],
+                "group_cipher": "CCMP",
+                "connected_devices": ["11:22:33:44:55:66"],
+            })
+        );
+    }
+
+    #[test]
+    fn stopped_access_point_has_null_details() {
+        let access_point = AccessPointJson {
+            started: false,
+            name: None,
+            frequency: None,
+            ciphers: None,
+            group_cipher: None,
+            connected_devices: Vec::new(),
+        };
+
+        assert_eq!(
+            to_value(access_point).unwrap(),
+            json!({
+                "started": false,
+                "name": null,
+                "frequency": null,
+                "ciphers": null,
+                "group_cipher": null,
+                "connected_devices": [],
+            })
+        );
+    }
+}
@@ -1,135 +1,388 @@
+use crate::icons::find_xdg_icon_file;
 use anyhow::{anyhow, Context, Result};
-use clap::ValueEnum;
+use log::warn;
 use nix::{
     libc,
     sys::signal::{kill, killpg, Signal},
     unistd::Pid,
 };
-use process_wrap::std::{CommandWrap, ProcessGroup};
+use process_wrap::std::{ChildWrapper, CommandWrap, ProcessGroup};
+use serde::Deserialize;
 use signal_hook::iterator::Signals;
 use std::{
+    collections::HashMap,
     io::Write,
     process::{exit, Command, Stdio},
     sync::{
         atomic::{AtomicI32, Ordering},
-        Once,
+        Once, OnceLock,
     },
     thread,
 };
 
-#[derive(Debug, Clone, ValueEnum)]
+const CUSTOM_LAUNCHER: &str = "custom";
+
+#[derive(Debug, Clone)]
 pub enum LauncherType {
-    Fuzzel,
-    Rofi,
-    Dmenu,
-    Bemenu,
+    Profile(Box<LauncherProfile>),
     Custom,
 }
 
-#[derive(Debug, Clone)]
-pub enum LauncherCommand {
-    Fuzzel {
-        icon_type: String,
-        placeholder: Option<String>,
-        password_mode: bool,
-    },
-    Rofi {
-        icon_type: String,
-        placeholder: Option<String>,
-        password_mode: bool,
-    },
-    Dmenu {
-        prompt: Option<String>,
-    },
-    Bemenu {
-        prompt: Option<String>,
+impl LauncherType {
+    /// Command and protocol of the launcher's live menu, for launchers that have one.
+    pub fn live_command(&self, icon_type: &str) -> Option<(LauncherCommand, LiveProtocol)> {
+        match self {
+            LauncherType::Profile(profile) => profile.live_command(icon_type),
+            LauncherType::Custom => None,
+        }
+    }
+
+    /// Resolves `name` to a bundled launcher, a profile from the config file, or `custom`.
+    pub fn from_name(name: &str, profiles: &HashMap<String, LauncherProfile>) -> Result<Self> {
+        if name == CUSTOM_LAUNCHER {
+            return Ok(LauncherType::Custom);
+        }
+
+        LauncherProfile::resolve(name, profiles)
+            .map(|profile| LauncherType::Profile(Box::new(profile)))
+            .ok_or_else(|| {
+                let mut names = LauncherProfile::names(profiles);
+                names.push(CUSTOM_LAUNCHER.to_string());
+                anyhow!(
+                    "Unknown launcher `{name}`, expected one of: {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// How menu entries carry their XDG icon.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IconSyntax {
+    /// `text\0icon\x1fname`, as read by rofi and fuzzel
+    Rofi,
+    /// `img:path:text:text`, with the icon resolved to a file
+    Wofi,
+    /// Icons are left out
+    #[default]
+    None,
+}
+
+/// Describes how to run a launcher in dmenu mode. The bundled launchers are defined in
+/// `launchers.toml`; profiles from the config file override them field by field.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct LauncherProfile {
+    pub program: Option<String>,
+    pub args: Option<Vec<String>>,
+    pub hint_args: Option<Vec<String>>,
+    pub prompt_args: Option<Vec<String>>,
+    pub password_args: Option<Vec<String>>,
+    pub font_icon_args: Option<Vec<String>>,
+    pub xdg_icon_args: Option<Vec<String>>,
+    pub index_args: Option<Vec<String>>,
+    pub index_base: Option<usize>,
+    pub keybinding_args: Option<Vec<String>>,
+    /// Whether the launcher reports custom keys through its exit code without
+    /// `keybinding-args`, its keys being set in its own config
+    pub custom_keys: Option<bool>,
+    pub icon_syntax: Option<IconSyntax>,
+    pub live_args: Option<Vec<String>>,
+    pub live_protocol: Option<LiveProtocol>,
+}
+
+impl LauncherProfile {
+    fn bundled() -> &'static HashMap<String, LauncherProfile> {
+        static BUNDLED: OnceLock<HashMap<String, LauncherProfile>> = OnceLock::new();
+
+        BUNDLED.get_or_init(|| {
+            toml::from_str(include_str!("launchers.toml"))
+                .expect("bundled launcher profiles are valid")
+        })
+    }
+
+    pub fn is_bundled(name: &str) -> bool {
+        Self::bundled().contains_key(name)
+    }
+
+    /// Names of the bundled launchers and of the profiles from the config file, sorted.
+    pub fn names(profiles: &HashMap<String, LauncherProfile>) -> Vec<String> {
+        let mut names: Vec<String> = Self::bundled()
+            .keys()
+            .chain(profiles.keys())
+            .cloned()
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    pub fn resolve(name: &str, profiles: &HashMap<String, LauncherProfile>) -> Option<Self> {
+        match (profiles.get(name), Self::bundled().get(name)) {
+            (Some(profile), Some(bundled)) => Some(profile.clone().merged_over(bundled)),
+            (Some(profile), None) => Some(profile.clone()),
+            (None, bundled) => bundled.cloned(),
+        }
+    }
+
+    fn merged_over(self, base: &Self) -> Self {
+        Self {
+            program: self.program.or_else(|| base.program.clone()),
+            args: self.args.or_else(|| base.args.clone()),
+            hint_args: self.hint_args.or_else(|| base.hint_args.clone()),
+            prompt_args: self.prompt_args.or_else(|| base.prompt_args.clone()),
+            password_args: self.password_args.or_else(|| base.password_args.clone()),
+            font_icon_args: self.font_icon_args.or_else(|| base.font_icon_args.clone()),
+            xdg_icon_args: self.xdg_icon_args.or_else(|| base.xdg_icon_args.clone()),
+            index_args: self.index_args.or_else(|| base.index_args.clone()),
+            index_base: self.index_base.or(base.index_base),
+            keybinding_args: self
+                .keybinding_args
+                .or_else(|| base.keybinding_args.clone()),
+            custom_keys: self.custom_keys.or(base.custom_keys),
+            live_args: self.live_args.or_else(|| base.live_args.clone()),
+            live_protocol: self.live_protocol.or(base.live_protocol),
+            icon_syntax: self.icon_syntax.or(base.icon_syntax),
+        }
+    }
+
+    fn command(
+        &self,
+        icon_type: &str,
+        hint: Option<&str>,
         password_mode: bool,
-    },
-    Custom {
-        program: String,
-        args: Vec<String>,
-    },
+        index_mode: bool,
+    ) -> Result<LauncherCommand> {
+        let program = self
+            .program
+            .clone()
+            .ok_or_else(|| anyhow!("The launcher profile has no program"))?;
+
+        let mut args = self.args.clone().unwrap_or_default();
+        let mut extend = |extra: &Option<Vec<String>>| args.extend(extra.iter().flatten().cloned());
+
+        match icon_type {
+            "font" => extend(&self.font_icon_args),
+            "xdg" => extend(&self.xdg_icon_args),
+            _ => {}
+        }
+
+        if let Some(hint_text) = hint {
+            args.extend(
+                self.hint_args
+                    .iter()
+                    .flatten()
+                    .map(|arg| arg.replace("{hint}", hint_text)),
+            );
+        }
+
+        let mut extend = |extra: &Option<Vec<String>>| args.extend(extra.iter().flatten().cloned());
+
+        if password_mode {
+            match &self.password_args {
+                Some(_) => extend(&self.password_args),
+                None => warn_unmasked_secret(&program),
+            }
+        }
+
+        // Launchers without an index mode print the selected text instead
+        let index_base = match &self.index_args {
+            Some(index_args) if index_mode && !index_args.is_empty() => {
+                extend(&self.index_args);
+                Some(self.index_base.unwrap_or(0))
+            }
+            _ => None,
+        };
+
+        if !index_mode {
+            extend(&self.prompt_args);
+        }
+
+        Ok(LauncherCommand {
+            program,
+            args,
+            icon_syntax: self.icon_syntax.unwrap_or_default(),
+            index_base,
+            keybinding_args: self.keybinding_args.clone().unwrap_or_default(),
+            has_custom_keys: self.has_custom_keys(),
+        })
+    }
+
+    fn has_custom_keys(&self) -> bool {
+        self.custom_keys.unwrap_or_else(|| {
+            self.keybinding_args
+                .as_ref()
+                .is_some_and(|args| !args.is_empty())
+        })
+    }
+
+    /// Command opening a menu kept up to date through the launcher's live protocol, if any.
+    fn live_command(&self, icon_type: &str) -> Option<(LauncherCommand, LiveProtocol)> {
+        let protocol = self.live_protocol?;
+        let mut args = self.live_args.clone().unwrap_or_default();
+
+        match icon_type {
+            "font" => args.extend(self.font_icon_args.iter().flatten().cloned()),
+            "xdg" => args.extend(self.xdg_icon_args.iter().flatten().cloned()),
+            _ => {}
+        }
+
+        let command = LauncherCommand {
+            program: self.program.clone()?,
+            args,
+            icon_syntax: IconSyntax::Rofi,
+            index_base: None,
+            keybinding_args: self.keybinding_args.clone().unwrap_or_default(),
+            has_custom_keys: self.has_custom_keys(),
+        };
+
+        Some((command, protocol))
+    }
+}
+
+/// How a live menu exchanges rows and events with the launcher, both using the JSON messages
+/// of rofi-blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LiveProtocol {
+    /// Through `iwmenu live-bridge`, which rofi's blocks mode runs in place of `{bridge}`
+    RofiBlocks,
+    /// Through the launcher's own `stdin` and `stdout`
+    Json,
+}
+
+#[derive(Debug, Clone)]
+pub struct LauncherCommand {
+    program: String,
+    args: Vec<String>,
+    icon_syntax: IconSyntax,
+    /// Number of the first entry, when the launcher prints indices rather than text
+    index_base: Option<usize>,
+    keybinding_args: Vec<String>,
+    /// Whether exit codes from 10 on stand for custom keys rather than errors
+    has_custom_keys: bool,
+}
+
+/// What the launcher printed, and the custom key that closed it, if any.
+#[derive(Debug, Clone, Default)]
+pub struct LauncherOutput {
+    pub text: Option<String>,
+    pub custom_key: Option<usize>,
 }
 
+impl LauncherCommand {
+    /// Builds the process to spawn, with `{bridge}` in the arguments replaced by `bridge`.
+    pub(crate) fn to_command(&self, bridge: &str) -> Command {
+        let mut command = Command::new(&self.program);
+        command.args(self.args.iter().map(|arg| arg.replace("{bridge}", bridge)));
+        command
+    }
+
+    /// Binds keys to the launcher's custom actions, given as `(number, key)` pairs. Launchers
+    /// without `keybinding-args` are left as is, their keys being set in their own config.
+    pub fn bind_keys(&mut self, bindings: &[(usize, &str)]) {
+        if self.keybinding_args.is_empty() && !bindings.is_empty() {
+            UNBOUND_KEYS_WARNING.call_once(|| {
+                let keys = bindings
+                    .iter()
+                    .map(|(number, key)| format!("{key} to custom key {number}"))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                warn!(
+                    "`{}` takes no keybinding arguments, bind {keys} in its own config instead",
+                    self.program
+                );
+            });
+        }
+
+        for (number, key) in bindings {
+            self.args.extend(self.keybinding_args.iter().map(|arg| {
+                arg.replace("{n}", &number.to_string())
+                    .replace("{key}", key)
+            }));
+        }
+    }
+
+    /// Whether the launcher prints the position of the selected entry instead of its text.
+    pub fn returns_index(&self) -> bool {
+        self.index_base.is_some()
+    }
+
+    /// Adapts the menu lines, which carry XDG icons the way rofi and fuzzel expect them, to
+    /// the launcher.
+    fn format_input(&self, input: &str) -> String {
+        match self.icon_syntax {
+            IconSyntax::Rofi => input.to_string(),
+            IconSyntax::Wofi => input
+                .lines()
+                .map(|line| match line.split_once("\0icon\x1f") {
+                    Some((text, icons)) => match find_xdg_icon_file(icons) {
+                        Some(path) => format!("img:{}:text:{text}", path.display()),
+                        None => text.to_string(),
+                    },
+                    None => line.to_string(),
+                })
+                .collect::<Vec<String>>()
+                .join("\n"),
+            IconSyntax::None => input
+                .lines()
+                .map(|line| line.split('\0').next().unwrap_or(""))
+                .collect::<Vec<&str>>()
+                .join("\n"),
+        }
+    }
+
+    fn format_output(&self, output: String) -> String {
+        if let Some(base) = self.index_base.filter(|base| *base > 0) {
+            return match output.parse::<usize>() {
+                Ok(index) if index >= base => (index - base).to_string(),
+                _ => output,
+            };
+        }
+
+        match output
+            .strip_prefix("img:")
+            .and_then(|rest| rest.split_once(":text:"))
+        {
+            Some((_, text)) if self.icon_syntax == IconSyntax::Wofi => text.to_string(),
+            _ => output,
+        }
+    }
+}
+
+const FIRST_CUSTOM_KEY_CODE: i32 = 10;
+const LAST_CUSTOM_KEY_CODE: i32 = 28;
+
 static CURRENT_LAUNCHER_PID: AtomicI32 = AtomicI32::new(-1);
 static SIGNAL_HANDLER_INIT: Once = Once::new();
+static UNBOUND_KEYS_WARNING: Once = Once::new();
 
 pub struct Launcher;
 
 impl Launcher {
     pub fn run(cmd: LauncherCommand, input: Option<&str>) -> Result<Option<String>> {
-        let command = match cmd {
-            LauncherCommand::Fuzzel {
-                icon_type,
-                placeholder,
-                password_mode,
-            } => {
-                let mut cmd = Command::new("fuzzel");
-                cmd.arg("-d").arg("--minimal-lines");
-                if icon_type == "font" {
-                    cmd.arg("-I");
-                }
-                if let Some(hint_text) = placeholder {
-                    cmd.arg("--placeholder").arg(hint_text);
-                }
-                if password_mode {
-                    cmd.arg("--password");
-                }
-                cmd
-            }
-            LauncherCommand::Rofi {
-                icon_type,
-                placeholder,
-                password_mode,
-            } => {
-                let mut cmd = Command::new("rofi");
-                cmd.arg("-m").arg("-1").arg("-dmenu");
-                if icon_type == "xdg" {
-                    cmd.arg("-show-icons");
-                }
-                if let Some(hint_text) = placeholder {
-                    cmd.arg("-theme-str")
-                        .arg(format!("entry {{ placeholder: \"{hint_text}\"; }}"));
-                }
-                if password_mode {
-                    cmd.arg("-password");
-                }
-                cmd
-            }
-            LauncherCommand::Dmenu { prompt } => {
-                let mut cmd = Command::new("dmenu");
-                if let Some(hint_text) = prompt {
-                    cmd.arg("-p").arg(format!("{hint_text}: "));
-                }
-                cmd
-            }
-            LauncherCommand::Bemenu {
-                prompt,
-                password_mode,
-            } => {
-                let mut cmd = Command::new("bemenu");
-                if let Some(hint_text) = prompt {
-                    cmd.arg("-p").arg(format!("{hint_text}: "));
-                }
-                if password_mode {
-                    cmd.arg("--password").arg("indicator");
-                }
-                cmd
-            }
-            LauncherCommand::Custom { program, args } => {
-                let mut cmd = Command::new(&program);
-                cmd.args(&args);
-                cmd
-            }
-        };
+        Ok(Self::run_with_keys(cmd, input)?.text)
+    }
+
+    /// Like `run`, but also reports the custom key the launcher was closed with.
+    pub fn run_with_keys(cmd: LauncherCommand, input: Option<&str>) -> Result<LauncherOutput> {
+        let command = cmd.to_command("");
+
+        let input = input.map(|input| cmd.format_input(input));
+        let output = Self::run_command(command, input.as_deref(), cmd.has_custom_keys)?;
 
-        Self::run_command(command, input)
+        Ok(LauncherOutput {
+            text: output.text.map(|text| cmd.format_output(text)),
+            ..output
+        })
     }
 
     fn substitute_placeholders(
         template: &str,
         hint: Option<&str>,
         password_mode: bool,
+        index_mode: bool,
     ) -> Result<String> {
         if !template.contains('{') {
             return Ok(template.to_string());
@@ -151,6 +404,10 @@ impl Launcher {
             result = Self::replace_conditional_pattern(&result, "password_flag:", password_mode)?;
         }
 
+        if result.contains("{index_mode:") {
+            result = Self::replace_conditional_pattern(&result, "index_mode:", index_mode)?;
+        }
+
         Ok(result)
     }
 
@@ -191,7 +448,7 @@ impl Launcher {
         Ok(result)
     }
 
-    fn parse_command(command_str: &str) -> Result<(String, Vec<String>)> {
+    pub(crate) fn parse_command(command_str: &str) -> Result<(String, Vec<String>)> {
         let parts =
             shlex::split(command_str).ok_or_else(|| anyhow!("Invalid shell syntax in command"))?;
 
@@ -205,48 +462,74 @@ impl Launcher {
         Ok((program, args))
     }
 
-    fn run_command(mut command: Command, input: Option<&str>) -> Result<Option<String>> {
+    fn run_command(
+        command: Command,
+        input: Option<&str>,
+        has_custom_keys: bool,
+    ) -> Result<LauncherOutput> {
+        let mut child = Self::spawn(command)?;
+
+        if let Some(input_data) = input {
+            if let Some(stdin) = child.stdin().as_mut() {
+                stdin.write_all(input_data.as_bytes())?;
+            }
+        }
+
+        let output = child.wait_with_output()?;
+        let trimmed_output = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+        Self::release();
+
+        // rofi, fuzzel and bemenu exit with 10 for their first custom key, 11 for the next...
+        // Other launchers may use these codes for errors, which must not run an action
+        let custom_key = output
+            .status
+            .code()
+            .filter(|_| has_custom_keys)
+            .and_then(custom_key_from_code);
+
+        Ok(LauncherOutput {
+            text: Some(trimmed_output).filter(|text| !text.is_empty()),
+            custom_key,
+        })
+    }
+
+    /// Spawns the launcher in its own process group with piped `stdin` and `stdout`, as the one
+    /// `close` acts on until `release` is called.
+    pub(crate) fn spawn(mut command: Command) -> Result<Box<dyn ChildWrapper>> {
         command.stdin(Stdio::piped()).stdout(Stdio::piped());
 
         let mut command_wrap = CommandWrap::from(command);
         command_wrap.wrap(ProcessGroup::leader());
 
-        let mut child = command_wrap
+        let child = command_wrap
             .spawn()
             .context("Failed to spawn launcher command")?;
 
-        let pid = child.id() as i32;
-
         SIGNAL_HANDLER_INIT.call_once(|| {
             thread::spawn(|| {
                 let mut signals = Signals::new([libc::SIGTERM, libc::SIGINT]).unwrap();
                 if let Some(_signal) = signals.forever().next() {
-                    let current_pid = CURRENT_LAUNCHER_PID.load(Ordering::Relaxed);
-                    if current_pid > 0 && kill(Pid::from_raw(current_pid), None).is_ok() {
-                        let _ = killpg(Pid::from_raw(current_pid), Signal::SIGTERM);
-                    }
+                    Self::close();
                     exit(0);
                 }
             });
         });
 
-        CURRENT_LAUNCHER_PID.store(pid, Ordering::Relaxed);
+        CURRENT_LAUNCHER_PID.store(child.id() as i32, Ordering::Relaxed);
 
-        if let Some(input_data) = input {
-            if let Some(stdin) = child.stdin().as_mut() {
-                stdin.write_all(input_data.as_bytes())?;
-            }
-        }
-
-        let output = child.wait_with_output()?;
-        let trimmed_output = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Ok(child)
+    }
 
+    pub(crate) fn release() {
         CURRENT_LAUNCHER_PID.store(-1, Ordering::Relaxed);
+    }
 
-        if trimmed_output.is_empty() {
-            Ok(None)
-        } else {
-            Ok(Some(trimmed_output))
+    /// Closes the launcher waiting for input, if any. It then returns as if dismissed.
+    pub fn close() {
+        let current_pid = CURRENT_LAUNCHER_PID.load(Ordering::Relaxed);
+        if current_pid > 0 && kill(Pid::from_raw(current_pid), None).is_ok() {
+            let _ = killpg(Pid::from_raw(current_pid), Signal::SIGTERM);
         }
     }
 
@@ -256,31 +539,34 @@ impl Launcher {
         icon_type: &str,
         hint: Option<&str>,
         password_mode: bool,
+        index_mode: bool,
     ) -> Result<LauncherCommand> {
-        let hint_text = hint.filter(|h| !h.is_empty()).map(|h| h.to_string());
-
         match launcher_type {
-            LauncherType::Fuzzel => Ok(LauncherCommand::Fuzzel {
-                icon_type: icon_type.to_string(),
-                placeholder: hint_text,
-                password_mode,
-            }),
-            LauncherType::Rofi => Ok(LauncherCommand::Rofi {
-                icon_type: icon_type.to_string(),
-                placeholder: hint_text,
-                password_mode,
-            }),
-            LauncherType::Dmenu => Ok(LauncherCommand::Dmenu { prompt: hint_text }),
-            LauncherType::Bemenu => Ok(LauncherCommand::Bemenu {
-                prompt: hint_text,
+            LauncherType::Profile(profile) => profile.command(
+                icon_type,
+                hint.filter(|h| !h.is_empty()),
                 password_mode,
-            }),
+                index_mode,
+            ),
             LauncherType::Custom => {
                 if let Some(cmd) = command_str {
-                    let processed_cmd = Self::substitute_placeholders(cmd, hint, password_mode)?;
+                    let processed_cmd =
+                        Self::substitute_placeholders(cmd, hint, password_mode, index_mode)?;
                     let (program, args) = Self::parse_command(&processed_cmd)?;
 
-                    Ok(LauncherCommand::Custom { program, args })
+                    if password_mode && !cmd.contains("{password_flag:") {
+                        warn_unmasked_secret(&program);
+                    }
+
+                    // Only commands declaring how to print the index return one
+                    Ok(LauncherCommand {
+                        program,
+                        args,
+                        icon_syntax: IconSyntax::Rofi,
+                        index_base: (index_mode && cmd.contains("{index_mode:")).then_some(0),
+                        keybinding_args: Vec::new(),
+                        has_custom_keys: false,
+                    })
                 } else {
                     Err(anyhow!("No custom launcher command provided"))
                 }
@@ -288,3 +574,175 @@ impl Launcher {
         }
     }
 }
+
+fn custom_key_from_code(code: i32) -> Option<usize> {
+    (FIRST_CUSTOM_KEY_CODE..=LAST_CUSTOM_KEY_CODE)
+        .contains(&code)
+        .then(|| (code - FIRST_CUSTOM_KEY_CODE) as usize + 1)
+}
+
+/// Secrets typed into launchers without a password mode are shown as typed, so this is made
+/// visible rather than left for the user to notice on screen.
+fn warn_unmasked_secret(launcher: &str) {
+    eprintln!(
+        "WARNING: `{launcher}` is run without a password mode, so the secret will be shown as it is typed."
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundled_command(name: &str, index_mode: bool) -> LauncherCommand {
+        LauncherProfile::resolve(name, &HashMap::new())
+            .unwrap()
+            .command("", None, false, index_mode)
+            .unwrap()
+    }
+
+    #[test]
+    fn format_output_shifts_one_based_indices() {
+        let tofi = bundled_command("tofi", true);
+
+        assert!(tofi.returns_index());
+        assert_eq!(tofi.format_output("1".to_string()), "0");
+        assert_eq!(tofi.format_output("3".to_string()), "2");
+        assert_eq!(tofi.format_output("typed".to_string()), "typed");
+    }
+
+    #[test]
+    fn format_output_keeps_zero_based_indices() {
+        let fuzzel = bundled_command("fuzzel", true);
+
+        assert!(fuzzel.returns_index());
+        assert_eq!(fuzzel.format_output("0".to_string()), "0");
+    }
+
+    fn profiles(config: &str) -> HashMap<String, LauncherProfile> {
+        toml::from_str(config).unwrap()
+    }
+
+    #[test]
+    fn config_profile_overrides_one_field_of_a_bundled_launcher() {
+        let profiles = profiles("[rofi]\nargs = [\"-dmenu\", \"-i\"]\n");
+        let rofi = LauncherProfile::resolve("rofi", &profiles).unwrap();
+        let bundled = LauncherProfile::resolve("rofi", &HashMap::new()).unwrap();
+
+        assert_eq!(
+            rofi.args,
+            Some(vec!["-dmenu".to_string(), "-i".to_string()])
+        );
+        assert_eq!(rofi.program, bundled.program);
+        assert_eq!(rofi.password_args, bundled.password_args);
+        assert_eq!(rofi.index_args, bundled.index_args);
+        assert_eq!(rofi.keybinding_args, bundled.keybinding_args);
+    }
+
+    #[test]
+    fn new_profile_without_program_is_rejected() {
+        let profiles = profiles("[mine]\nargs = [\"--dmenu\"]\n");
+        let mine = LauncherProfile::resolve("mine", &profiles).unwrap();
+
+        assert!(mine.command("font", None, false, false).is_err());
+        assert!(LauncherProfile::resolve("unknown", &profiles).is_none());
+        assert!(LauncherType::from_name("unknown", &profiles).is_err());
+    }
+
+    #[test]
+    fn command_args_follow_the_profile() {
+        let profiles = profiles(
+            "[mine]\n\
+             program = \"mine\"\n\
+             args = [\"--dmenu\"]\n\
+             hint-args = [\"--prompt\", \"{hint}: \"]\n\
+             prompt-args = [\"--free-text\"]\n\
+             password-args = [\"--hidden\"]\n\
+             index-args = [\"--index\"]\n\
+             font-icon-args = [\"--font\"]\n",
+        );
+        let mine = LauncherProfile::resolve("mine", &profiles).unwrap();
+
+        let menu = mine.command("font", Some("Wi-Fi"), false, true).unwrap();
+        assert_eq!(menu.program, "mine");
+        assert_eq!(
+            menu.args,
+            ["--dmenu", "--font", "--prompt", "Wi-Fi: ", "--index"]
+        );
+        assert!(menu.returns_index());
+
+        let prompt = mine
+            .command("xdg", Some("Passphrase"), true, false)
+            .unwrap();
+        assert_eq!(
+            prompt.args,
+            [
+                "--dmenu",
+                "--prompt",
+                "Passphrase: ",
+                "--hidden",
+                "--free-text"
+            ]
+        );
+        assert!(!prompt.returns_index());
+
+        let plain = mine.command("font", None, false, false).unwrap();
+        assert_eq!(plain.args, ["--dmenu", "--font", "--free-text"]);
+    }
+
+    #[test]
+    fn bundled_launchers_are_valid() {
+        for name in LauncherProfile::names(&HashMap::new()) {
+            let profile = LauncherProfile::resolve(&name, &HashMap::new()).unwrap();
+
+            assert!(profile.command("font", Some("hint"), false, true).is_ok());
+        }
+    }
+
+    #[test]
+    fn only_launchers_declaring_keys_report_them() {
+        let has_custom_keys = |name: &str| bundled_command(name, true).has_custom_keys;
+
+        assert!(has_custom_keys("rofi"));
+        assert!(has_custom_keys("fuzzel"));
+        assert!(has_custom_keys("bemenu"));
+        for name in ["dmenu", "tofi", "wofi", "walker", "anyrun", "vicinae"] {
+            assert!(!has_custom_keys(name), "{}", name);
+        }
+
+        let profiles = profiles(
+            "[mine]\nprogram = \"mine\"\n\
+             [keyed]\nprogram = \"keyed\"\nkeybinding-args = [\"--key-{n}\", \"{key}\"]\n\
+             [rofi]\ncustom-keys = false\n",
+        );
+        let profile_has_custom_keys = |name: &str| {
+            LauncherProfile::resolve(name, &profiles)
+                .unwrap()
+                .command("", None, false, true)
+                .unwrap()
+                .has_custom_keys
+        };
+        assert!(!profile_has_custom_keys("mine"));
+        assert!(profile_has_custom_keys("keyed"));
+        assert!(!profile_has_custom_keys("rofi"));
+
+        let custom = Launcher::create_command(
+            &LauncherType::Custom,
+            &Some("rofi -dmenu".to_string()),
+            "",
+            None,
+            false,
+            true,
+        )
+        .unwrap();
+        assert!(!custom.has_custom_keys);
+    }
+
+    #[test]
+    fn custom_keys_map_from_exit_codes() {
+        assert_eq!(custom_key_from_code(9), None);
+        assert_eq!(custom_key_from_code(10), Some(1));
+        assert_eq!(custom_key_from_code(13), Some(4));
+        assert_eq!(custom_key_from_code(28), Some(19));
+        assert_eq!(custom_key_from_code(29), None);
+    }
+}
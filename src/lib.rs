@@ -7,16 +7,30 @@ mod macros;
 i18n!("locales", fallback = "en");
 
 pub mod app;
+pub mod cli;
+pub mod clipboard;
+pub mod config;
 pub mod icons;
+pub mod json;
 pub mod launcher;
+pub mod live;
 pub mod menu;
 pub mod notification;
+pub mod qr;
+pub mod state;
+pub mod status_bar;
 pub mod iw {
     pub mod access_point;
     pub mod adapter;
     pub mod agent;
+    pub mod ap_profile;
     pub mod device;
+    pub mod dpp;
+    pub mod events;
     pub mod known_network;
     pub mod network;
+    pub mod objects;
+    pub mod profile;
     pub mod station;
+    pub mod wps;
 }
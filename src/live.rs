@@ -0,0 +1,570 @@
+use crate::launcher::{Launcher, LauncherCommand, LiveProtocol};
+use anyhow::{anyhow, Context, Result};
+use log::debug;
+use process_wrap::std::ChildWrapper;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    env, fs,
+    io::{self, BufRead, BufReader, ErrorKind, Read, Write},
+    net::Shutdown,
+    os::unix::{
+        fs::DirBuilderExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    process, thread,
+    time::{Duration, Instant},
+};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const CONNECT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Events are requested in this format so both protocols report them the same way.
+const EVENT_FORMAT: &str =
+    r#"{"name":"{{name_escaped}}","value":"{{value_escaped}}","data":"{{data_escaped}}"}"#;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LiveEvent {
+    /// An entry was picked, identified by its key or, failing that, its text
+    Select { key: String, text: String },
+    /// The highlighted entry changed
+    Active { key: String },
+    /// A custom key was pressed, on the highlighted entry when the launcher reports it
+    CustomKey { number: usize, key: String },
+    /// The launcher was dismissed or exited
+    Closed,
+}
+
+#[derive(Deserialize)]
+struct RawEvent {
+    name: String,
+    #[serde(default)]
+    value: String,
+    #[serde(default)]
+    data: String,
+}
+
+impl LiveEvent {
+    fn parse(line: &str) -> Option<Self> {
+        let event: RawEvent = serde_json::from_str(line).ok()?;
+
+        // rofi-blocks reports names as "select entry" or as "SELECT_ENTRY"
+        match event.name.to_lowercase().replace('_', " ").as_str() {
+            "select entry" => Some(LiveEvent::Select {
+                key: event.data,
+                text: event.value,
+            }),
+            "active entry" | "active entry change" => Some(LiveEvent::Active { key: event.data }),
+            "custom key" => Some(LiveEvent::CustomKey {
+                number: event.value.trim().parse().ok()?,
+                key: event.data,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// A launcher menu that stays open while its rows are replaced, following the rofi-blocks
+/// protocol: each update is a JSON object on a line, and the launcher answers with JSON events.
+pub struct LiveMenu {
+    child: Box<dyn ChildWrapper>,
+    writer: Box<dyn Write + Send>,
+    events: UnboundedReceiver<LiveEvent>,
+    socket_dir: Option<PathBuf>,
+    keys: Vec<String>,
+    texts: Vec<String>,
+    active_key: Option<String>,
+    last_update: Option<String>,
+}
+
+impl LiveMenu {
+    pub async fn open(cmd: &LauncherCommand, protocol: LiveProtocol) -> Result<Self> {
+        match protocol {
+            LiveProtocol::Json => {
+                let mut child = Launcher::spawn(cmd.to_command(""))?;
+
+                let writer = child
+                    .stdin()
+                    .take()
+                    .ok_or_else(|| anyhow!("Failed to open the launcher's stdin"))?;
+                let reader = child
+                    .stdout()
+                    .take()
+                    .ok_or_else(|| anyhow!("Failed to open the launcher's stdout"))?;
+
+                Ok(Self::new(child, Box::new(writer), Box::new(reader), None))
+            }
+            LiveProtocol::RofiBlocks => {
+                let socket_dir = socket_dir()?;
+
+                let menu = Self::open_bridged(cmd, &socket_dir).await;
+                if menu.is_err() {
+                    let _ = fs::remove_dir_all(&socket_dir);
+                }
+
+                menu
+            }
+        }
+    }
+
+    /// Starts the launcher with a bridge relaying the rofi-blocks protocol through a socket in
+    /// `socket_dir`, which the menu removes once dropped.
+    async fn open_bridged(cmd: &LauncherCommand, socket_dir: &Path) -> Result<Self> {
+        let socket_path = socket_dir.join("live.sock");
+
+        let listener = UnixListener::bind(&socket_path)
+            .with_context(|| format!("Failed to listen on {}", socket_path.display()))?;
+
+        let exe = env::current_exe().context("Failed to locate the iwmenu executable")?;
+        let bridge = shlex::try_join([
+            exe.to_string_lossy().as_ref(),
+            "live-bridge",
+            socket_path.to_string_lossy().as_ref(),
+        ])
+        .map_err(|e| anyhow!("Failed to build the bridge command: {e}"))?;
+
+        let mut child = Launcher::spawn(cmd.to_command(&bridge))?;
+
+        let stream = match accept(&listener, child.as_mut()).await {
+            Ok(stream) => stream,
+            Err(e) => {
+                let _ = child.kill();
+                Launcher::release();
+                return Err(e);
+            }
+        };
+
+        let reader = stream.try_clone()?;
+
+        Ok(Self::new(
+            child,
+            Box::new(stream),
+            Box::new(reader),
+            Some(socket_dir.to_path_buf()),
+        ))
+    }
+
+    fn new(
+        child: Box<dyn ChildWrapper>,
+        writer: Box<dyn Write + Send>,
+        reader: Box<dyn Read + Send>,
+        socket_dir: Option<PathBuf>,
+    ) -> Self {
+        let (sender, events) = unbounded_channel();
+
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+
+                match LiveEvent::parse(&line) {
+                    Some(event) => {
+                        if sender.send(event).is_err() {
+                            break;
+                        }
+                    }
+                    None => debug!("Ignoring launcher event: {line}"),
+                }
+            }
+
+            let _ = sender.send(LiveEvent::Closed);
+        });
+
+        Self {
+            child,
+            writer,
+            events,
+            socket_dir,
+            keys: Vec::new(),
+            texts: Vec::new(),
+            active_key: None,
+            last_update: None,
+        }
+    }
+
+    /// Replaces the rows, each line being paired with the key identifying it across updates.
+    /// The highlighted entry follows its key to its new position.
+    pub fn update(
+        &mut self,
+        lines: &[String],
+        keys: Vec<String>,
+        hint: Option<&str>,
+        message: Option<&str>,
+    ) -> Result<()> {
+        let rows: Vec<Value> = lines
+            .iter()
+            .zip(&keys)
+            .map(|(line, key)| {
+                let (text, icon) = match line.split_once("\0icon\x1f") {
+                    Some((text, icons)) => (text, icons.split(',').next()),
+                    None => (line.as_str(), None),
+                };
+
+                match icon {
+                    Some(icon) => json!({ "text": text, "icon": icon, "data": key }),
+                    None => json!({ "text": text, "data": key }),
+                }
+            })
+            .collect();
+
+        self.texts = rows
+            .iter()
+            .map(|row| row["text"].as_str().unwrap_or_default().to_string())
+            .collect();
+        self.keys = keys;
+
+        let mut update = json!({
+            "prompt": hint.unwrap_or_default(),
+            "message": message.unwrap_or_default(),
+            "event format": EVENT_FORMAT,
+            "lines": rows,
+        });
+
+        if let Some(position) = self
+            .active_key
+            .as_ref()
+            .and_then(|key| self.keys.iter().position(|k| k == key))
+        {
+            update["active entry"] = json!(position);
+        }
+
+        let update = update.to_string();
+        if self.last_update.as_ref() == Some(&update) {
+            return Ok(());
+        }
+
+        writeln!(self.writer, "{update}")
+            .and_then(|_| self.writer.flush())
+            .context("Failed to update the launcher")?;
+        self.last_update = Some(update);
+
+        Ok(())
+    }
+
+    /// Waits for the next event. Highlight changes are recorded and not returned.
+    pub async fn next_event(&mut self) -> LiveEvent {
+        loop {
+            match self.events.recv().await {
+                Some(LiveEvent::Active { key }) => self.active_key = Some(key),
+                Some(event) => return event,
+                None => return LiveEvent::Closed,
+            }
+        }
+    }
+
+    /// Position of the entry with `key` in the last update, or of the one showing `text`.
+    pub fn position(&self, key: &str, text: &str) -> Option<usize> {
+        self.keys
+            .iter()
+            .position(|k| !key.is_empty() && k == key)
+            .or_else(|| self.texts.iter().position(|t| t.trim() == text.trim()))
+    }
+
+    /// Position of the highlighted entry, as last reported by the launcher.
+    pub fn active_position(&self) -> Option<usize> {
+        let key = self.active_key.as_ref()?;
+        self.keys.iter().position(|k| k == key)
+    }
+}
+
+impl Drop for LiveMenu {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        Launcher::release();
+
+        if let Some(dir) = &self.socket_dir {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
+
+/// Creates a fresh directory only the current user can enter for the bridge socket, so other
+/// users can neither connect to it nor swap it for their own when it sits in a shared `/tmp`.
+fn socket_dir() -> Result<PathBuf> {
+    let mut suffix = [0u8; 8];
+    getrandom::fill(&mut suffix)
+        .map_err(|e| anyhow!("Failed to gather randomness for the socket directory: {e}"))?;
+    let suffix: String = suffix.iter().map(|byte| format!("{byte:02x}")).collect();
+
+    let dir = env::var_os("XDG_RUNTIME_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir)
+        .join(format!("iwmenu-live-{}-{suffix}", process::id()));
+
+    // Unlike `create_dir_all`, this fails on an existing entry instead of reusing it
+    fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&dir)
+        .with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    Ok(dir)
+}
+
+/// Waits for the bridge started by the launcher to connect, giving up when the launcher exits
+/// first, as rofi does without the blocks plugin.
+async fn accept(listener: &UnixListener, child: &mut dyn ChildWrapper) -> Result<UnixStream> {
+    listener.set_nonblocking(true)?;
+    let started = Instant::now();
+
+    loop {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                stream.set_nonblocking(false)?;
+                return Ok(stream);
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(e) => return Err(e).context("Failed to accept the launcher connection"),
+        }
+
+        if let Some(status) = child.try_wait()? {
+            return Err(anyhow!(
+                "The launcher exited with {status} before opening the live menu"
+            ));
+        }
+
+        if started.elapsed() > CONNECT_TIMEOUT {
+            return Err(anyhow!("The launcher did not open the live menu in time"));
+        }
+
+        tokio::time::sleep(CONNECT_POLL_INTERVAL).await;
+    }
+}
+
+/// Relays rofi-blocks' `stdin` and `stdout` to the iwmenu process listening on `socket`.
+pub fn run_bridge(socket: &Path) -> Result<()> {
+    let stream = UnixStream::connect(socket)
+        .with_context(|| format!("Failed to connect to {}", socket.display()))?;
+
+    let mut events = stream.try_clone()?;
+    thread::spawn(move || {
+        let _ = io::copy(&mut io::stdin().lock(), &mut events);
+        let _ = events.shutdown(Shutdown::Write);
+    });
+
+    let mut updates = stream;
+    let mut stdout = io::stdout().lock();
+    let mut buffer = [0; 8192];
+
+    // Updates are forwarded as they arrive rather than once a buffer fills up
+    loop {
+        let read = updates.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(());
+        }
+
+        stdout.write_all(&buffer[..read])?;
+        stdout.flush()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use process_wrap::std::CommandWrap;
+    use std::{
+        process::Command,
+        sync::{Arc, Mutex},
+    };
+
+    #[derive(Clone, Default)]
+    struct SharedWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedWriter {
+        fn updates(&self) -> Vec<Value> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        }
+    }
+
+    fn live_menu() -> (LiveMenu, SharedWriter) {
+        let child = CommandWrap::from(Command::new("true")).spawn().unwrap();
+        let writer = SharedWriter::default();
+        let menu = LiveMenu::new(child, Box::new(writer.clone()), Box::new(io::empty()), None);
+
+        (menu, writer)
+    }
+
+    /// Fills `EVENT_FORMAT` the way rofi-blocks does, with JSON-escaped values.
+    fn format_event(name: &str, value: &str, data: &str) -> String {
+        let escape = |text: &str| {
+            let quoted = serde_json::to_string(text).unwrap();
+            quoted[1..quoted.len() - 1].to_string()
+        };
+
+        EVENT_FORMAT
+            .replace("{{name_escaped}}", &escape(name))
+            .replace("{{value_escaped}}", &escape(value))
+            .replace("{{data_escaped}}", &escape(data))
+    }
+
+    #[test]
+    fn event_format_round_trips_escaped_values() {
+        let line = format_event("select entry", "Home \"5G\" \\ 🛜", "network:0");
+
+        assert_eq!(
+            LiveEvent::parse(&line),
+            Some(LiveEvent::Select {
+                key: "network:0".to_string(),
+                text: "Home \"5G\" \\ 🛜".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn event_names_are_parsed_in_both_spellings() {
+        for name in ["select entry", "SELECT_ENTRY"] {
+            assert_eq!(
+                LiveEvent::parse(&format_event(name, "Scan", "scan")),
+                Some(LiveEvent::Select {
+                    key: "scan".to_string(),
+                    text: "Scan".to_string(),
+                })
+            );
+        }
+
+        for name in ["active entry", "ACTIVE_ENTRY", "active entry change"] {
+            assert_eq!(
+                LiveEvent::parse(&format_event(name, "", "scan")),
+                Some(LiveEvent::Active {
+                    key: "scan".to_string()
+                })
+            );
+        }
+
+        for name in ["custom key", "CUSTOM_KEY"] {
+            assert_eq!(
+                LiveEvent::parse(&format_event(name, " 2", "scan")),
+                Some(LiveEvent::CustomKey {
+                    number: 2,
+                    key: "scan".to_string(),
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_or_malformed_events_are_ignored() {
+        assert_eq!(
+            LiveEvent::parse(&format_event("input change", "ho", "")),
+            None
+        );
+        assert_eq!(
+            LiveEvent::parse(&format_event("custom key", "first", "")),
+            None
+        );
+        assert_eq!(LiveEvent::parse("select entry"), None);
+        assert_eq!(
+            LiveEvent::parse(r#"{"name":"select entry"}"#),
+            Some(LiveEvent::Select {
+                key: String::new(),
+                text: String::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn update_splits_off_the_first_icon() {
+        let (mut menu, writer) = live_menu();
+
+        menu.update(
+            &[
+                "Home\0icon\x1fnetwork-wireless,network-wireless-symbolic".to_string(),
+                "Scan".to_string(),
+            ],
+            vec!["network:0".to_string(), "scan".to_string()],
+            Some("Wi-Fi"),
+            None,
+        )
+        .unwrap();
+
+        let updates = writer.updates();
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0]["prompt"], "Wi-Fi");
+        assert_eq!(updates[0]["message"], "");
+        assert_eq!(updates[0]["event format"], EVENT_FORMAT);
+        assert_eq!(
+            updates[0]["lines"],
+            json!([
+                { "text": "Home", "icon": "network-wireless", "data": "network:0" },
+                { "text": "Scan", "data": "scan" },
+            ])
+        );
+        assert!(updates[0].get("active entry").is_none());
+    }
+
+    #[tokio::test]
+    async fn update_keeps_the_active_entry_and_skips_repeats() {
+        let (mut menu, writer) = live_menu();
+        let keys = |keys: &[&str]| keys.iter().map(|key| key.to_string()).collect::<Vec<_>>();
+        let lines = |lines: &[&str]| {
+            lines
+                .iter()
+                .map(|line| line.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        menu.update(&lines(&["A", "B"]), keys(&["a", "b"]), None, None)
+            .unwrap();
+        menu.active_key = Some("b".to_string());
+        menu.update(
+            &lines(&["New", "A", "B"]),
+            keys(&["new", "a", "b"]),
+            None,
+            None,
+        )
+        .unwrap();
+        menu.update(
+            &lines(&["New", "A", "B"]),
+            keys(&["new", "a", "b"]),
+            None,
+            None,
+        )
+        .unwrap();
+
+        let updates = writer.updates();
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[1]["active entry"], 2);
+        assert_eq!(menu.active_position(), Some(2));
+
+        // The reader of an empty stream reports the launcher as closed
+        assert_eq!(menu.next_event().await, LiveEvent::Closed);
+    }
+
+    #[test]
+    fn position_prefers_the_key_and_falls_back_to_text() {
+        let (mut menu, _) = live_menu();
+        menu.update(
+            &[
+                "Scan".to_string(),
+                "Home\0icon\x1fnetwork-wireless".to_string(),
+            ],
+            vec!["scan".to_string(), "network:0".to_string()],
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(menu.position("network:0", "Scan"), Some(1));
+        assert_eq!(menu.position("gone", " Home "), Some(1));
+        assert_eq!(menu.position("", "Scan"), Some(0));
+        assert_eq!(menu.position("gone", "Nothing"), None);
+    }
+}
@@ -1,9 +1,20 @@
 use anyhow::{anyhow, Result};
-use clap::{Arg, Command};
-use iwmenu::{app::App, icons::Icons, launcher::LauncherType, menu::Menu};
+use clap::{Arg, ArgMatches, Command};
+use iwmenu::{
+    app::App,
+    cli::{Cli, CliCommand},
+    config::Config,
+    icons::Icons,
+    iw::profile::DEFAULT_STATE_DIR,
+    launcher::LauncherType,
+    live::run_bridge,
+    menu::{KeyAction, Menu},
+    status_bar::StatusFormat,
+};
 use rust_i18n::{i18n, set_locale};
-use std::{env, sync::Arc};
+use std::{env, path::PathBuf, process::exit, sync::Arc};
 use sys_locale::get_locale;
+use zbus::zvariant::OwnedObjectPath;
 
 i18n!("locales", fallback = "en");
 
@@ -18,6 +29,21 @@ fn validate_launcher_command(command: &str) -> Result<String, String> {
     Ok(command.to_string())
 }
 
+fn parse_keybinding(binding: &str) -> Result<(String, String), String> {
+    let (action, key) = binding
+        .split_once('=')
+        .ok_or_else(|| format!("expected ACTION=KEY, got `{binding}`"))?;
+
+    if KeyAction::from_name(action).is_none() {
+        return Err(format!(
+            "unknown action `{action}`, expected one of: {}",
+            KeyAction::ALL.map(|action| action.name()).join(", ")
+        ));
+    }
+
+    Ok((action.to_string(), key.to_string()))
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     env_logger::init();
@@ -33,23 +59,19 @@ async fn main() -> Result<()> {
             Arg::new("launcher")
                 .short('l')
                 .long("launcher")
-                .required(true)
-                .value_parser(clap::value_parser!(LauncherType))
                 .conflicts_with("menu")
-                .help("Launcher to use (replaces deprecated --menu)"),
+                .help("Launcher to use, bundled or defined in the config file (replaces deprecated --menu)"),
         )
         .arg(
             Arg::new("menu") // deprecated
                 .short('m')
                 .long("menu")
-                .value_parser(clap::value_parser!(LauncherType))
                 .hide(true)
                 .help("DEPRECATED: use --launcher instead"),
         )
         .arg(
             Arg::new("launcher_command")
                 .long("launcher-command")
-                .required_if_eq("launcher", "custom")
                 .conflicts_with("menu_command")
                 .value_parser(validate_launcher_command)
                 .help("Launcher command to use when --launcher is set to custom"),
@@ -57,7 +79,6 @@ async fn main() -> Result<()> {
         .arg(
             Arg::new("menu_command") // deprecated
                 .long("menu-command")
-                .required_if_eq("menu", "custom")
                 .hide(true)
                 .value_parser(validate_launcher_command)
                 .help("DEPRECATED: use --launcher-command instead"),
@@ -67,77 +88,372 @@ async fn main() -> Result<()> {
                 .short('i')
                 .long("icon")
                 .value_parser(["font", "xdg"])
-                .default_value("font")
-                .help("Choose the type of icons to use"),
+                .help("Choose the type of icons to use [default: font]"),
         )
         .arg(
             Arg::new("spaces")
                 .short('s')
                 .long("spaces")
-                .default_value("1")
-                .help("Number of spaces between icon and text when using font icons"),
+                .value_parser(clap::value_parser!(usize))
+                .help("Number of spaces between icon and text when using font icons [default: 1]"),
         )
         .arg(
             Arg::new("back_on_escape")
                 .long("back-on-escape")
                 .action(clap::ArgAction::SetTrue)
+                .overrides_with("no_back_on_escape")
                 .help("Return to previous menu on escape instead of exiting"),
         )
+        .arg(
+            Arg::new("no_back_on_escape")
+                .long("no-back-on-escape")
+                .action(clap::ArgAction::SetTrue)
+                .overrides_with("back_on_escape")
+                .help("Exit on escape, even when the config file sets back-on-escape"),
+        )
+        .arg(
+            Arg::new("iwd_state_dir")
+                .long("iwd-state-dir")
+                .value_parser(clap::value_parser!(PathBuf))
+                .help(format!(
+                    "Directory where iwd stores network profiles [default: {DEFAULT_STATE_DIR}]"
+                )),
+        )
+        .arg(
+            Arg::new("ap_profile_dir")
+                .long("ap-profile-dir")
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Directory where access point profiles are stored [default: <iwd-state-dir>/ap]"),
+        )
+        .arg(
+            Arg::new("qr_image_command")
+                .long("qr-image-command")
+                .help("Command printing the path of the image to read a Wi-Fi QR code from"),
+        )
+        .arg(
+            Arg::new("auth_retries")
+                .long("auth-retries")
+                .value_parser(clap::value_parser!(usize))
+                .help("Times to ask again for a passphrase rejected by the network [default: 2]"),
+        )
+        .arg(
+            Arg::new("live_menu")
+                .long("live-menu")
+                .action(clap::ArgAction::SetTrue)
+                .help("Keep the main menu open and up to date, with launchers supporting it"),
+        )
+        .arg(
+            Arg::new("keybinding")
+                .long("keybinding")
+                .value_name("ACTION=KEY")
+                .value_parser(parse_keybinding)
+                .action(clap::ArgAction::Append)
+                .help("Bind a launcher key to a main menu action on the selected network (e.g. forget=Alt+f)"),
+        )
+        .arg(
+            Arg::new("config")
+                .short('c')
+                .long("config")
+                .global(true)
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Configuration file to use instead of $XDG_CONFIG_HOME/iwmenu/config.toml"),
+        )
+        .arg(
+            Arg::new("interface")
+                .long("interface")
+                .global(true)
+                .help("Wireless interface to manage (e.g. wlan0)"),
+        )
+        .arg(
+            Arg::new("adapter")
+                .long("adapter")
+                .global(true)
+                .help("Wireless adapter to manage (e.g. phy0)"),
+        )
+        .subcommand(
+            Command::new("list")
+                .about("List available networks")
+                .arg(json_arg()),
+        )
+        .subcommand(
+            Command::new("status")
+                .about("Show adapter and connection status")
+                .arg(json_arg().conflicts_with_all(["follow", "format"]))
+                .arg(
+                    Arg::new("follow")
+                        .long("follow")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Keep running and print a line on every status change"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(clap::value_parser!(StatusFormat))
+                        .help("Status bar output format"),
+                ),
+        )
+        .subcommand(
+            Command::new("connect")
+                .about("Connect to a network")
+                .arg(
+                    Arg::new("ssid")
+                        .required_unless_present("qr")
+                        .help("Network name"),
+                )
+                .arg(
+                    Arg::new("passphrase_stdin")
+                        .long("passphrase-stdin")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Read the passphrase (or username and password lines) from stdin"),
+                )
+                .arg(
+                    Arg::new("qr")
+                        .long("qr")
+                        .value_name("IMAGE")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .conflicts_with_all(["ssid", "passphrase_stdin"])
+                        .help("Read the network and passphrase from a Wi-Fi QR code image"),
+                ),
+        )
+        .subcommand(Command::new("disconnect").about("Disconnect from the current network"))
+        .subcommand(
+            Command::new("scan").about("Scan for networks").arg(
+                Arg::new("wait")
+                    .long("wait")
+                    .action(clap::ArgAction::SetTrue)
+                    .help("Wait for the scan to complete"),
+            ),
+        )
+        .subcommand(
+            Command::new("forget")
+                .about("Forget a known network")
+                .arg(Arg::new("ssid").required(true).help("Network name")),
+        )
+        .subcommand(
+            Command::new("autoconnect")
+                .about("Enable or disable autoconnect for a known network")
+                .arg(Arg::new("ssid").required(true).help("Network name"))
+                .arg(
+                    Arg::new("state")
+                        .required(true)
+                        .value_parser(["on", "off"])
+                        .help("Autoconnect state"),
+                ),
+        )
+        .subcommand(
+            Command::new("share")
+                .about("Print a Wi-Fi QR code for a known network or the access point")
+                .arg(
+                    Arg::new("ssid")
+                        .help("Known network name (defaults to the access point when omitted)"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("Also save the QR code as a PNG image"),
+                ),
+        )
+        .subcommand(
+            Command::new("live-bridge")
+                .hide(true)
+                .about("Relay a live menu between rofi's blocks mode and iwmenu")
+                .arg(
+                    Arg::new("socket")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                ),
+        )
         .get_matches();
 
-    let launcher_type: LauncherType = if matches.contains_id("launcher") {
-        matches.get_one::<LauncherType>("launcher").unwrap().clone()
-    } else if matches.contains_id("menu") {
-        eprintln!("WARNING: --menu flag is deprecated. Please use --launcher instead.");
-        matches.get_one::<LauncherType>("menu").unwrap().clone()
-    } else {
-        LauncherType::Dmenu
-    };
+    if let Some(("live-bridge", sub_matches)) = matches.subcommand() {
+        let socket = sub_matches
+            .get_one::<PathBuf>("socket")
+            .ok_or_else(|| anyhow!("No socket given to the live bridge"))?;
+        return run_bridge(socket);
+    }
 
-    let command_str = if matches.contains_id("launcher_command") {
-        matches.get_one::<String>("launcher_command").cloned()
-    } else if matches.contains_id("menu_command") {
-        eprintln!(
-            "WARNING: --menu-command flag is deprecated. Please use --launcher-command instead."
-        );
-        matches.get_one::<String>("menu_command").cloned()
-    } else {
-        None
-    };
+    let mut config = Config::load(matches.get_one::<PathBuf>("config").map(PathBuf::as_path))?;
+    apply_cli_overrides(&mut config, &matches);
+
+    if let Some((name, sub_matches)) = matches.subcommand() {
+        apply_device_overrides(&mut config, sub_matches);
 
-    let icon_type = matches.get_one::<String>("icon").unwrap().clone();
-    let back_on_escape = matches.get_flag("back_on_escape");
+        let command = cli_command(name, sub_matches, &config)
+            .ok_or_else(|| anyhow!("Unknown subcommand: {name}"))?;
+
+        let result = match Cli::new(&config.device_selection()).await {
+            Ok(mut cli) => cli.run(command).await,
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = result {
+            eprintln!("Error: {err}");
+            exit(err.exit_code());
+        }
+
+        return Ok(());
+    }
+
+    let launcher_name = config.launcher.as_deref().ok_or_else(|| {
+        anyhow!("No launcher specified. Use --launcher or set `launcher` in the config file.")
+    })?;
+    let launcher_type = LauncherType::from_name(launcher_name, &config.launchers)?;
+
+    let command_str = config.launcher_command.clone();
+    if matches!(launcher_type, LauncherType::Custom) && command_str.is_none() {
+        return Err(anyhow!(
+            "A launcher command is required when the launcher is custom. Use --launcher-command or set `launcher-command` in the config file."
+        ));
+    }
+
+    let icon_type = config.icon_type();
+    let spaces = config.spaces();
 
     let icons = Arc::new(Icons::new());
-    let menu = Menu::new(launcher_type, icons.clone());
+    let menu = Menu::new(launcher_type, icons, config.keybindings());
 
-    let spaces = matches
-        .get_one::<String>("spaces")
-        .and_then(|s| s.parse::<usize>().ok())
-        .ok_or_else(|| anyhow!("Invalid value for --spaces. Must be a positive integer."))?;
+    if config.live_menu() && !menu.supports_live(icon_type) {
+        return Err(anyhow!(
+            "The launcher has no live menu support. Set `live-args` and `live-protocol` in its profile, or drop --live-menu."
+        ));
+    }
 
-    run_app_loop(
+    let Some(device_path) = App::select_device(
         &menu,
         &command_str,
-        &icon_type,
+        icon_type,
         spaces,
-        icons,
-        back_on_escape,
+        &config.device_selection(),
     )
-    .await?;
+    .await?
+    else {
+        return Ok(());
+    };
+
+    run_app_loop(&menu, &config, device_path).await?;
 
     Ok(())
 }
 
-async fn run_app_loop(
-    menu: &Menu,
-    command_str: &Option<String>,
-    icon_type: &str,
-    spaces: usize,
-    icons: Arc<Icons>,
-    back_on_escape: bool,
-) -> Result<()> {
-    let mut app = App::new(icons.clone(), back_on_escape).await?;
+fn json_arg() -> Arg {
+    Arg::new("json")
+        .long("json")
+        .action(clap::ArgAction::SetTrue)
+        .help("Print machine-readable JSON output")
+}
+
+fn apply_cli_overrides(config: &mut Config, matches: &ArgMatches) {
+    if let Some(launcher) = matches.get_one::<String>("launcher") {
+        config.launcher = Some(launcher.clone());
+    } else if let Some(launcher) = matches.get_one::<String>("menu") {
+        eprintln!("WARNING: --menu flag is deprecated. Please use --launcher instead.");
+        config.launcher = Some(launcher.clone());
+    }
+
+    if let Some(command) = matches.get_one::<String>("launcher_command") {
+        config.launcher_command = Some(command.clone());
+    } else if let Some(command) = matches.get_one::<String>("menu_command") {
+        eprintln!(
+            "WARNING: --menu-command flag is deprecated. Please use --launcher-command instead."
+        );
+        config.launcher_command = Some(command.clone());
+    }
+
+    if let Some(icon) = matches.get_one::<String>("icon") {
+        config.icon = Some(icon.clone());
+    }
+    if let Some(spaces) = matches.get_one::<usize>("spaces") {
+        config.spaces = Some(*spaces);
+    }
+    if matches.get_flag("back_on_escape") {
+        config.back_on_escape = Some(true);
+    } else if matches.get_flag("no_back_on_escape") {
+        config.back_on_escape = Some(false);
+    }
+    if let Some(dir) = matches.get_one::<PathBuf>("iwd_state_dir") {
+        config.iwd_state_dir = Some(dir.clone());
+    }
+    if let Some(dir) = matches.get_one::<PathBuf>("ap_profile_dir") {
+        config.ap_profile_dir = Some(dir.clone());
+    }
+    if let Some(command) = matches.get_one::<String>("qr_image_command") {
+        config.qr_image_command = Some(command.clone());
+    }
+    if let Some(retries) = matches.get_one::<usize>("auth_retries") {
+        config.auth_retries = Some(*retries);
+    }
+    if matches.get_flag("live_menu") {
+        config.live_menu = Some(true);
+    }
+    if let Some(bindings) = matches.get_many::<(String, String)>("keybinding") {
+        config.keybindings.extend(bindings.cloned());
+    }
+
+    apply_device_overrides(config, matches);
+}
+
+fn apply_device_overrides(config: &mut Config, matches: &ArgMatches) {
+    if let Some(interface) = matches.get_one::<String>("interface") {
+        config.interface = Some(interface.clone());
+    }
+    if let Some(adapter) = matches.get_one::<String>("adapter") {
+        config.adapter = Some(adapter.clone());
+    }
+}
+
+fn cli_command(name: &str, matches: &ArgMatches, config: &Config) -> Option<CliCommand> {
+    let ssid = || {
+        matches
+            .get_one::<String>("ssid")
+            .cloned()
+            .unwrap_or_default()
+    };
+
+    match name {
+        "list" => Some(CliCommand::List {
+            json: matches.get_flag("json"),
+        }),
+        "status" => Some(CliCommand::Status {
+            json: matches.get_flag("json"),
+            follow: matches.get_flag("follow"),
+            format: matches.get_one::<StatusFormat>("format").copied(),
+            icon_type: config.icon_type().to_string(),
+            spaces: config.spaces(),
+        }),
+        "connect" => Some(CliCommand::Connect {
+            ssid: ssid(),
+            passphrase_stdin: matches.get_flag("passphrase_stdin"),
+            qr: matches.get_one::<PathBuf>("qr").cloned(),
+        }),
+        "disconnect" => Some(CliCommand::Disconnect),
+        "scan" => Some(CliCommand::Scan {
+            wait: matches.get_flag("wait"),
+        }),
+        "forget" => Some(CliCommand::Forget { ssid: ssid() }),
+        "autoconnect" => Some(CliCommand::Autoconnect {
+            ssid: ssid(),
+            enable: matches
+                .get_one::<String>("state")
+                .is_some_and(|s| s == "on"),
+        }),
+        "share" => Some(CliCommand::Share {
+            ssid: matches.get_one::<String>("ssid").cloned(),
+            output: matches.get_one::<PathBuf>("output").cloned(),
+            iwd_state_dir: config.iwd_state_dir(),
+        }),
+        _ => None,
+    }
+}
+
+async fn run_app_loop(menu: &Menu, config: &Config, device_path: OwnedObjectPath) -> Result<()> {
+    let icons = menu.icons.clone();
+    let command_str = &config.launcher_command;
+    let icon_type = config.icon_type();
+    let spaces = config.spaces();
+
+    let mut app = App::new(icons.clone(), config, device_path.clone()).await?;
 
     loop {
         match app.run(menu, command_str, icon_type, spaces).await {
@@ -156,7 +472,7 @@ async fn run_app_loop(
         }
 
         if app.reset_mode {
-            app = App::new(icons.clone(), back_on_escape).await?;
+            app = App::new(icons.clone(), config, device_path.clone()).await?;
             app.reset_mode = false;
         }
     }
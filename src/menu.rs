@@ -1,35 +1,123 @@
-use crate::icons::Icons;
-use crate::iw::{access_point::AccessPoint, network::Network, station::Station};
+use crate::icons::{Icons, SignalTier};
+use crate::iw::{
+    access_point::{AccessPoint, AccessPointClient},
+    ap_profile::{ApProfile, ApProfileField},
+    known_network::KnownNetwork,
+    network::Network,
+    objects::DeviceEntry,
+    profile::{profile_file_name, EapMethod, Phase2Method},
+    station::{Station, StationDiagnostic},
+};
 use crate::launcher::{Launcher, LauncherType};
-use anyhow::Result;
-use iwdrs::modes::Mode;
+use crate::live::LiveMenu;
+use anyhow::{anyhow, Result};
+use iwdrs::{modes::Mode, network::NetworkType};
+use log::debug;
 use rust_i18n::t;
 use std::borrow::Cow;
 use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Debug, Clone)]
 pub enum MainMenuOptions {
     Scan,
+    HiddenNetwork,
+    QrCode,
+    EasyConnect,
+    Wps,
     Settings,
-    Network(String),
+    Network(Network),
+    NetworkAction(Network, KeyAction),
 }
 
 impl MainMenuOptions {
-    pub fn from_string(option: &str) -> Option<Self> {
-        match option {
-            s if s == t!("menus.main.options.scan.name") => Some(MainMenuOptions::Scan),
-            s if s == t!("menus.main.options.settings.name") => Some(MainMenuOptions::Settings),
-            other => Some(MainMenuOptions::Network(other.to_string())),
-        }
-    }
-
     pub fn to_str(&self) -> Cow<'static, str> {
         match self {
             MainMenuOptions::Scan => t!("menus.main.options.scan.name"),
+            MainMenuOptions::HiddenNetwork => t!("menus.main.options.hidden_network.name"),
+            MainMenuOptions::QrCode => t!("menus.main.options.qr_code.name"),
+            MainMenuOptions::EasyConnect => t!("menus.main.options.easy_connect.name"),
+            MainMenuOptions::Wps => t!("menus.main.options.wps.name"),
             MainMenuOptions::Settings => t!("menus.main.options.settings.name"),
-            MainMenuOptions::Network(_) => t!("menus.main.options.network.name"),
+            MainMenuOptions::Network(_) | MainMenuOptions::NetworkAction(..) => {
+                t!("menus.main.options.network.name")
+            }
+        }
+    }
+
+    /// Identifies the entry across updates of a live menu.
+    pub fn key(&self) -> String {
+        match self {
+            MainMenuOptions::Scan => "scan".to_string(),
+            MainMenuOptions::HiddenNetwork => "hidden_network".to_string(),
+            MainMenuOptions::QrCode => "qr_code".to_string(),
+            MainMenuOptions::EasyConnect => "easy_connect".to_string(),
+            MainMenuOptions::Wps => "wps".to_string(),
+            MainMenuOptions::Settings => "settings".to_string(),
+            MainMenuOptions::Network(network) | MainMenuOptions::NetworkAction(network, _) => {
+                format!(
+                    "network:{}",
+                    profile_file_name(&network.name, network.network_type)
+                )
+            }
+        }
+    }
+}
+
+/// Actions run from the main menu with a custom launcher key, on the highlighted network. Each
+/// one is tied to the custom key of the same number: rofi's `kb-custom-1` or fuzzel's `custom-1`
+/// triggers `Scan`, and so on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyAction {
+    Scan,
+    Connect,
+    Disconnect,
+    Forget,
+    Autoconnect,
+    Share,
+}
+
+impl KeyAction {
+    pub const ALL: [KeyAction; 6] = [
+        KeyAction::Scan,
+        KeyAction::Connect,
+        KeyAction::Disconnect,
+        KeyAction::Forget,
+        KeyAction::Autoconnect,
+        KeyAction::Share,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyAction::Scan => "scan",
+            KeyAction::Connect => "connect",
+            KeyAction::Disconnect => "disconnect",
+            KeyAction::Forget => "forget",
+            KeyAction::Autoconnect => "autoconnect",
+            KeyAction::Share => "share",
         }
     }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|action| action.name() == name)
+            .copied()
+    }
+
+    pub fn custom_key(&self) -> usize {
+        Self::ALL
+            .iter()
+            .position(|action| action == self)
+            .unwrap_or_default()
+            + 1
+    }
+
+    pub fn from_custom_key(key: usize) -> Option<Self> {
+        key.checked_sub(1)
+            .and_then(|index| Self::ALL.get(index))
+            .copied()
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -39,32 +127,13 @@ pub enum KnownNetworkOptions {
     ForgetNetwork,
     Disconnect,
     Connect,
+    ConnectionDetails,
+    Share,
+    UpdatePassphrase,
     Back,
 }
 
 impl KnownNetworkOptions {
-    pub fn from_string(option: &str) -> Option<Self> {
-        match option {
-            s if s == t!("menus.known_network.options.disable_autoconnect.name") => {
-                Some(KnownNetworkOptions::DisableAutoconnect)
-            }
-            s if s == t!("menus.known_network.options.enable_autoconnect.name") => {
-                Some(KnownNetworkOptions::EnableAutoconnect)
-            }
-            s if s == t!("menus.known_network.options.forget_network.name") => {
-                Some(KnownNetworkOptions::ForgetNetwork)
-            }
-            s if s == t!("menus.known_network.options.disconnect.name") => {
-                Some(KnownNetworkOptions::Disconnect)
-            }
-            s if s == t!("menus.known_network.options.connect.name") => {
-                Some(KnownNetworkOptions::Connect)
-            }
-            s if s == t!("menus.common.back") => Some(KnownNetworkOptions::Back),
-            _ => None,
-        }
-    }
-
     pub fn to_str(&self) -> Cow<'static, str> {
         match self {
             KnownNetworkOptions::DisableAutoconnect => {
@@ -82,13 +151,203 @@ impl KnownNetworkOptions {
             KnownNetworkOptions::Connect => {
                 t!("menus.known_network.options.connect.name")
             }
+            KnownNetworkOptions::ConnectionDetails => {
+                t!("menus.known_network.options.connection_details.name")
+            }
+            KnownNetworkOptions::Share => t!("menus.known_network.options.share.name"),
+            KnownNetworkOptions::UpdatePassphrase => {
+                t!("menus.known_network.options.update_passphrase.name")
+            }
             KnownNetworkOptions::Back => t!("menus.common.back"),
         }
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct ConnectionDetail {
+    pub label: Cow<'static, str>,
+    pub value: String,
+}
+
+impl ConnectionDetail {
+    pub fn from_diagnostic(diagnostic: &StationDiagnostic) -> Vec<Self> {
+        let bitrate = |rate_kbps: Option<u64>, mode: &Option<String>| {
+            rate_kbps.map(|rate| format_bitrate(rate, mode.clone()))
+        };
+
+        let fields = vec![
+            (
+                t!("menus.connection_details.fields.signal"),
+                diagnostic.rssi.map(|rssi| format!("{rssi} dBm")),
+            ),
+            (
+                t!("menus.connection_details.fields.tx_bitrate"),
+                bitrate(diagnostic.tx_rate_kbps, &diagnostic.tx_mode),
+            ),
+            (
+                t!("menus.connection_details.fields.rx_bitrate"),
+                bitrate(diagnostic.rx_rate_kbps, &diagnostic.rx_mode),
+            ),
+            (
+                t!("menus.connection_details.fields.frequency"),
+                Some(format!("{} MHz", diagnostic.frequency_mhz)),
+            ),
+            (
+                t!("menus.connection_details.fields.channel"),
+                diagnostic.channel.map(|channel| channel.to_string()),
+            ),
+            (
+                t!("menus.connection_details.fields.band"),
+                band_name(diagnostic.frequency_mhz).map(str::to_string),
+            ),
+            (
+                t!("menus.connection_details.fields.security"),
+                Some(match &diagnostic.pairwise_cipher {
+                    Some(cipher) => format!("{} ({cipher})", diagnostic.security),
+                    None => diagnostic.security.clone(),
+                }),
+            ),
+            (
+                t!("menus.connection_details.fields.bssid"),
+                Some(diagnostic.connected_bss.clone()),
+            ),
+        ];
+
+        Self::collect(fields)
+    }
+
+    pub fn from_client(client: &AccessPointClient) -> Vec<Self> {
+        let bitrate = |rate_kbps: Option<u64>, mode: &Option<String>| {
+            rate_kbps.map(|rate| format_bitrate(rate, mode.clone()))
+        };
+
+        let fields = vec![
+            (
+                t!("menus.connection_details.fields.mac_address"),
+                Some(client.address.clone()),
+            ),
+            (
+                t!("menus.connection_details.fields.signal"),
+                client.rssi.map(|rssi| format!("{rssi} dBm")),
+            ),
+            (
+                t!("menus.connection_details.fields.tx_bitrate"),
+                bitrate(client.tx_rate_kbps, &client.tx_mode),
+            ),
+            (
+                t!("menus.connection_details.fields.rx_bitrate"),
+                bitrate(client.rx_rate_kbps, &client.rx_mode),
+            ),
+            (
+                t!("menus.connection_details.fields.connected_time"),
+                client.connected_time.map(format_duration),
+            ),
+            (
+                t!("menus.connection_details.fields.inactive_time"),
+                client
+                    .inactive_time
+                    .map(|time| format!("{} ms", time.as_millis())),
+            ),
+        ];
+
+        Self::collect(fields)
+    }
+
+    fn collect(fields: Vec<(Cow<'static, str>, Option<String>)>) -> Vec<Self> {
+        fields
+            .into_iter()
+            .filter_map(|(label, value)| {
+                Some(Self {
+                    label,
+                    value: value?,
+                })
+            })
+            .collect()
+    }
+}
+
+fn format_bitrate(rate_kbps: u64, mode: Option<String>) -> String {
+    let rate = rate_kbps as f64 / 1000.0;
+
+    match mode {
+        Some(mode) => format!("{rate:.1} Mbit/s ({mode})"),
+        None => format!("{rate:.1} Mbit/s"),
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+
+    if hours > 0 {
+        format!("{hours}h {minutes:02}m {seconds:02}s")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds:02}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+fn band_name(frequency_mhz: u32) -> Option<&'static str> {
+    match frequency_mhz {
+        2400..=2500 => Some("2.4 GHz"),
+        5150..=5924 => Some("5 GHz"),
+        5925..=7125 => Some("6 GHz"),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ConnectionDetailsOptions {
+    Copy(ConnectionDetail),
+    Back,
+}
+
+#[derive(Debug, Clone)]
+pub enum ApClientsMenuOptions {
+    Client(AccessPointClient),
+    Back,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum EasyConnectMenuOptions {
+    Enrollee,
+    Configurator,
+    Back,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum WpsMenuOptions {
+    PushButton,
+    GeneratePin,
+    EnterPin,
+    Back,
+}
+
+#[derive(Debug, Clone)]
+pub enum ApProfilesMenuOptions {
+    Create,
+    Profile(String),
+    Back,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ApProfileOptions {
+    Start,
+    Edit(ApProfileField),
+    Delete,
+    Back,
+}
+
+#[derive(Debug, Clone)]
+pub enum ApProfileFieldEdit {
+    Set(String),
+    Clear,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum SettingsMenuOptions {
+    SavedNetworks,
     DisableAdapter,
     SwitchMode,
     Back,
@@ -97,6 +356,7 @@ pub enum SettingsMenuOptions {
 impl SettingsMenuOptions {
     pub fn from_id(id: &str) -> Option<Self> {
         match id {
+            "saved_networks" => Some(SettingsMenuOptions::SavedNetworks),
             "disable_adapter" => Some(SettingsMenuOptions::DisableAdapter),
             "switch_mode" => Some(SettingsMenuOptions::SwitchMode),
             "back" => Some(SettingsMenuOptions::Back),
@@ -106,6 +366,7 @@ impl SettingsMenuOptions {
 
     pub fn to_id(&self) -> &'static str {
         match self {
+            SettingsMenuOptions::SavedNetworks => "saved_networks",
             SettingsMenuOptions::DisableAdapter => "disable_adapter",
             SettingsMenuOptions::SwitchMode => "switch_mode",
             SettingsMenuOptions::Back => "back",
@@ -114,6 +375,7 @@ impl SettingsMenuOptions {
 
     pub fn to_str(&self) -> Cow<'static, str> {
         match self {
+            SettingsMenuOptions::SavedNetworks => t!("menus.settings.options.saved_networks.name"),
             SettingsMenuOptions::DisableAdapter => {
                 t!("menus.settings.options.disable_adapter.name")
             }
@@ -123,10 +385,19 @@ impl SettingsMenuOptions {
     }
 }
 
+#[derive(Debug, Clone)]
+pub enum SavedNetworksMenuOptions {
+    Network(KnownNetwork),
+    Back,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum ApMenuOptions {
     StartAp,
     StopAp,
+    ConnectedClients,
+    Share,
+    Profiles,
     SetSsid,
     SetPassword,
     Settings,
@@ -137,6 +408,9 @@ impl ApMenuOptions {
         match id {
             "start_ap" => Some(ApMenuOptions::StartAp),
             "stop_ap" => Some(ApMenuOptions::StopAp),
+            "connected_clients" => Some(ApMenuOptions::ConnectedClients),
+            "share_network" => Some(ApMenuOptions::Share),
+            "ap_profiles" => Some(ApMenuOptions::Profiles),
             "set_ssid" => Some(ApMenuOptions::SetSsid),
             "set_passphrase" => Some(ApMenuOptions::SetPassword),
             "settings" => Some(ApMenuOptions::Settings),
@@ -144,26 +418,13 @@ impl ApMenuOptions {
         }
     }
 
-    pub fn from_string(s: &str) -> Option<Self> {
-        if s == t!("menus.ap.options.start_ap.name") {
-            Some(ApMenuOptions::StartAp)
-        } else if s == t!("menus.ap.options.stop_ap.name") {
-            Some(ApMenuOptions::StopAp)
-        } else if s == t!("menus.ap.options.set_ssid.name") {
-            Some(ApMenuOptions::SetSsid)
-        } else if s == t!("menus.ap.options.set_passphrase.name") {
-            Some(ApMenuOptions::SetPassword)
-        } else if s == t!("menus.ap.options.settings.name") {
-            Some(ApMenuOptions::Settings)
-        } else {
-            None
-        }
-    }
-
     pub fn to_id(&self) -> &'static str {
         match self {
             ApMenuOptions::StartAp => "start_ap",
             ApMenuOptions::StopAp => "stop_ap",
+            ApMenuOptions::ConnectedClients => "connected_clients",
+            ApMenuOptions::Share => "share_network",
+            ApMenuOptions::Profiles => "ap_profiles",
             ApMenuOptions::SetSsid => "set_ssid",
             ApMenuOptions::SetPassword => "set_passphrase",
             ApMenuOptions::Settings => "settings",
@@ -174,6 +435,9 @@ impl ApMenuOptions {
         match self {
             ApMenuOptions::StartAp => t!("menus.ap.options.start_ap.name"),
             ApMenuOptions::StopAp => t!("menus.ap.options.stop_ap.name"),
+            ApMenuOptions::ConnectedClients => t!("menus.ap.options.connected_clients.name"),
+            ApMenuOptions::Share => t!("menus.ap.options.share.name"),
+            ApMenuOptions::Profiles => t!("menus.ap.options.profiles.name"),
             ApMenuOptions::SetSsid => t!("menus.ap.options.set_ssid.name"),
             ApMenuOptions::SetPassword => t!("menus.ap.options.set_passphrase.name"),
             ApMenuOptions::Settings => t!("menus.ap.options.settings.name"),
@@ -200,14 +464,6 @@ impl AdapterMenuOptions {
         }
     }
 
-    pub fn from_string(option: &str) -> Option<Self> {
-        if option == t!("menus.adapter.options.power_on_device.name") {
-            Some(AdapterMenuOptions::PowerOnDevice)
-        } else {
-            None
-        }
-    }
-
     pub fn to_str(&self) -> Cow<'static, str> {
         match self {
             AdapterMenuOptions::PowerOnDevice => t!("menus.adapter.options.power_on_device.name"),
@@ -215,15 +471,129 @@ impl AdapterMenuOptions {
     }
 }
 
+/// The lines shown by a menu and the values they stand for, so the selection is resolved by
+/// position instead of by matching the displayed text.
+pub struct MenuEntries<T> {
+    lines: Vec<String>,
+    values: Vec<T>,
+    fallback: Option<Fallback<T>>,
+}
+
+enum Fallback<T> {
+    Value(T),
+    Text(fn(String) -> T),
+}
+
+impl<T> MenuEntries<T> {
+    pub fn new() -> Self {
+        Self {
+            lines: Vec::new(),
+            values: Vec::new(),
+            fallback: None,
+        }
+    }
+
+    /// Value returned when the launcher prints something matching no entry, such as typed text.
+    pub fn with_fallback(mut self, value: T) -> Self {
+        self.fallback = Some(Fallback::Value(value));
+        self
+    }
+
+    /// Builds the value returned for text matching no entry from the text itself.
+    pub fn with_text_fallback(mut self, to_value: fn(String) -> T) -> Self {
+        self.fallback = Some(Fallback::Text(to_value));
+        self
+    }
+
+    pub fn push(&mut self, line: String, value: T) {
+        let line = self.unique_line(line);
+        self.lines.push(line);
+        self.values.push(value);
+    }
+
+    /// Numbers repeated lines, such as two networks sharing an SSID, so launchers printing the
+    /// selected text can still tell them apart.
+    fn unique_line(&self, line: String) -> String {
+        let (text, metadata) = line.split_at(line.find('\0').unwrap_or(line.len()));
+        let is_taken = |candidate: &str| {
+            self.lines
+                .iter()
+                .any(|line| line_text(line).trim() == candidate.trim())
+        };
+
+        if !is_taken(text) {
+            return line;
+        }
+
+        (2..)
+            .map(|number| format!("{} ({number})", text.trim_end()))
+            .find(|candidate| !is_taken(candidate))
+            .map(|candidate| format!("{candidate}{metadata}"))
+            .unwrap_or(line)
+    }
+
+    pub fn input(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    /// Launchers without an index mode print the selected line, minus the XDG icon metadata.
+    pub fn resolve(mut self, output: &str, is_index: bool) -> Option<T> {
+        let position = if is_index {
+            output.trim().parse::<usize>().ok()
+        } else {
+            self.lines
+                .iter()
+                .position(|line| line_text(line).trim() == output.trim())
+        };
+
+        match position.filter(|position| *position < self.values.len()) {
+            Some(position) => Some(self.values.swap_remove(position)),
+            None => match self.fallback {
+                Some(Fallback::Value(value)) => Some(value),
+                Some(Fallback::Text(to_value)) => Some(to_value(output.to_string())),
+                None => None,
+            },
+        }
+    }
+}
+
+impl<T> Default for MenuEntries<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The text of a menu line, without the metadata following it.
+fn line_text(line: &str) -> &str {
+    line.split('\0').next().unwrap_or("")
+}
+
 #[derive(Clone)]
 pub struct Menu {
     pub menu_type: LauncherType,
     pub icons: Arc<Icons>,
+    pub keybindings: Vec<(KeyAction, String)>,
 }
 
 impl Menu {
-    pub fn new(menu_type: LauncherType, icons: Arc<Icons>) -> Self {
-        Self { menu_type, icons }
+    pub fn new(
+        menu_type: LauncherType,
+        icons: Arc<Icons>,
+        keybindings: Vec<(KeyAction, String)>,
+    ) -> Self {
+        Self {
+            menu_type,
+            icons,
+            keybindings,
+        }
     }
 
     pub fn run_launcher(
@@ -234,36 +604,96 @@ impl Menu {
         hint: Option<&str>,
         obfuscate: bool,
     ) -> Result<Option<String>> {
-        let cmd =
-            Launcher::create_command(&self.menu_type, menu_command, icon_type, hint, obfuscate)?;
+        let cmd = Launcher::create_command(
+            &self.menu_type,
+            menu_command,
+            icon_type,
+            hint,
+            obfuscate,
+            false,
+        )?;
 
         Launcher::run(cmd, input)
     }
 
+    /// Shows `entries` and returns the value of the selected one, or `None` when dismissed.
+    pub fn select<T>(
+        &self,
+        menu_command: &Option<String>,
+        entries: MenuEntries<T>,
+        icon_type: &str,
+        hint: Option<&str>,
+    ) -> Result<Option<T>> {
+        let cmd =
+            Launcher::create_command(&self.menu_type, menu_command, icon_type, hint, false, true)?;
+        let is_index = cmd.returns_index();
+
+        Ok(Launcher::run(cmd, Some(&entries.input()))?
+            .and_then(|output| entries.resolve(&output, is_index)))
+    }
+
+    /// Like `select`, with the configured keybindings passed to the launcher. Also returns the
+    /// custom key the launcher was closed with, the entry being `None` when nothing matched.
+    pub fn select_with_keys<T>(
+        &self,
+        menu_command: &Option<String>,
+        entries: MenuEntries<T>,
+        icon_type: &str,
+        hint: Option<&str>,
+    ) -> Result<(Option<T>, Option<KeyAction>)> {
+        let mut cmd =
+            Launcher::create_command(&self.menu_type, menu_command, icon_type, hint, false, true)?;
+        let is_index = cmd.returns_index();
+
+        cmd.bind_keys(&self.custom_keys());
+
+        let output = Launcher::run_with_keys(cmd, Some(&entries.input()))?;
+        let selection = output
+            .text
+            .and_then(|text| entries.resolve(&text, is_index));
+
+        Ok((
+            selection,
+            output.custom_key.and_then(KeyAction::from_custom_key),
+        ))
+    }
+
+    fn custom_keys(&self) -> Vec<(usize, &str)> {
+        self.keybindings
+            .iter()
+            .map(|(action, key)| (action.custom_key(), key.as_str()))
+            .collect()
+    }
+
+    pub fn supports_live(&self, icon_type: &str) -> bool {
+        self.menu_type.live_command(icon_type).is_some()
+    }
+
+    /// Opens a menu whose rows can be updated while it is shown, with the configured
+    /// keybindings.
+    pub async fn open_live(&self, icon_type: &str) -> Result<LiveMenu> {
+        let (mut cmd, protocol) = self
+            .menu_type
+            .live_command(icon_type)
+            .ok_or_else(|| anyhow!("The launcher has no live menu support"))?;
+
+        cmd.bind_keys(&self.custom_keys());
+
+        LiveMenu::open(&cmd, protocol).await
+    }
+
+    fn format_option(&self, icon_key: &str, text: &str, icon_type: &str, spaces: usize) -> String {
+        self.icons
+            .get_icon_text(vec![(icon_key, text)], icon_type, spaces)
+    }
+
     pub fn get_signal_icon(
         &self,
         signal_strength: i16,
         is_secure_network: bool,
         icon_type: &str,
     ) -> String {
-        let icon_key = match signal_strength {
-            -10000..=-7500 => match is_secure_network {
-                true => "signal_weak_secure",
-                false => "signal_weak_open",
-            },
-            -7499..=-5000 => match is_secure_network {
-                true => "signal_ok_secure",
-                false => "signal_ok_open",
-            },
-            -4999..=-2500 => match is_secure_network {
-                true => "signal_good_secure",
-                false => "signal_good_open",
-            },
-            _ => match is_secure_network {
-                true => "signal_excellent_secure",
-                false => "signal_excellent_open",
-            },
-        };
+        let icon_key = SignalTier::from_strength(signal_strength).icon_key(is_secure_network);
 
         self.icons.get_icon(icon_key, icon_type)
     }
@@ -289,62 +719,6 @@ impl Menu {
             .format_display_with_icon(&display, &signal_icon, icon_type, spaces)
     }
 
-    pub fn clean_menu_output(&self, output: &str, icon_type: &str) -> String {
-        let output_trimmed = output.trim();
-
-        if icon_type == "font" {
-            output_trimmed
-                .chars()
-                .skip_while(|c| !c.is_ascii_alphanumeric())
-                .collect::<String>()
-                .trim()
-                .to_string()
-        } else if icon_type == "xdg" {
-            output_trimmed
-                .split('\0')
-                .next()
-                .unwrap_or("")
-                .trim()
-                .to_string()
-        } else {
-            output_trimmed.to_string()
-        }
-    }
-
-    pub fn select_network<'a, I>(
-        &self,
-        mut networks: I,
-        output: String,
-        icon_type: &str,
-        spaces: usize,
-    ) -> Option<(Network, i16)>
-    where
-        I: Iterator<Item = &'a (Network, i16)>,
-    {
-        let cleaned_output = self.clean_menu_output(&output, icon_type);
-
-        networks
-            .find(|(network, signal_strength)| {
-                let formatted_network =
-                    self.format_network_display(network, *signal_strength, icon_type, spaces);
-
-                let formatted_name = if icon_type == "font" {
-                    self.clean_menu_output(&formatted_network, icon_type)
-                } else if icon_type == "xdg" {
-                    formatted_network
-                        .split('\0')
-                        .next()
-                        .unwrap_or("")
-                        .to_string()
-                } else {
-                    formatted_network
-                };
-
-                formatted_name == cleaned_output
-            })
-            .cloned()
-    }
-
     pub async fn show_main_menu(
         &self,
         menu_command: &Option<String>,
@@ -352,43 +726,62 @@ impl Menu {
         icon_type: &str,
         spaces: usize,
     ) -> Result<Option<MainMenuOptions>> {
-        let scan_text = MainMenuOptions::Scan.to_str();
-        let options_before_networks = vec![("scan", scan_text.as_ref())];
-
-        let mut input = self
-            .icons
-            .get_icon_text(options_before_networks, icon_type, spaces);
-
-        for (network, signal_strength) in &station.known_networks {
-            let network_info =
-                self.format_network_display(network, *signal_strength, icon_type, spaces);
-            input.push_str(&format!("\n{network_info}"));
-        }
-
-        for (network, signal_strength) in &station.new_networks {
-            let network_info =
-                self.format_network_display(network, *signal_strength, icon_type, spaces);
-            input.push_str(&format!("\n{network_info}"));
+        loop {
+            let entries = self.main_menu_entries(station, icon_type, spaces);
+
+            return match self.select_with_keys(menu_command, entries, icon_type, None)? {
+                (_, Some(KeyAction::Scan)) => Ok(Some(MainMenuOptions::Scan)),
+                (Some(MainMenuOptions::Network(network)), Some(action)) => {
+                    Ok(Some(MainMenuOptions::NetworkAction(network, action)))
+                }
+                // Network actions do nothing on other entries, so the menu is shown again
+                (_, Some(action)) => {
+                    debug!("Ignoring {} outside of a network entry", action.name());
+                    continue;
+                }
+                (selection, None) => Ok(selection),
+            };
         }
+    }
 
-        let settings_text = MainMenuOptions::Settings.to_str();
-        let options_after_networks = vec![("settings", settings_text.as_ref())];
+    pub fn main_menu_entries(
+        &self,
+        station: &Station,
+        icon_type: &str,
+        spaces: usize,
+    ) -> MenuEntries<MainMenuOptions> {
+        let mut entries = MenuEntries::new();
 
-        let settings_input = self
-            .icons
-            .get_icon_text(options_after_networks, icon_type, spaces);
-        input.push_str(&format!("\n{settings_input}"));
+        entries.push(
+            self.format_option("scan", &MainMenuOptions::Scan.to_str(), icon_type, spaces),
+            MainMenuOptions::Scan,
+        );
 
-        let menu_output = self.run_launcher(menu_command, Some(&input), icon_type, None, false)?;
+        for (network, signal_strength) in station
+            .known_networks
+            .iter()
+            .chain(station.new_networks.iter())
+        {
+            entries.push(
+                self.format_network_display(network, *signal_strength, icon_type, spaces),
+                MainMenuOptions::Network(network.clone()),
+            );
+        }
 
-        if let Some(output) = menu_output {
-            let cleaned_output = self.clean_menu_output(&output, icon_type);
-            if let Some(option) = MainMenuOptions::from_string(&cleaned_output) {
-                return Ok(Some(option));
-            }
+        for (icon_key, option) in [
+            ("hidden_network", MainMenuOptions::HiddenNetwork),
+            ("qr_code", MainMenuOptions::QrCode),
+            ("easy_connect", MainMenuOptions::EasyConnect),
+            ("wps", MainMenuOptions::Wps),
+            ("settings", MainMenuOptions::Settings),
+        ] {
+            entries.push(
+                self.format_option(icon_key, &option.to_str(), icon_type, spaces),
+                option,
+            );
         }
 
-        Ok(None)
+        entries
     }
 
     pub async fn show_known_network_options(
@@ -400,76 +793,67 @@ impl Menu {
         network_ssid: &str,
         back_on_escape: bool,
     ) -> Result<Option<KnownNetworkOptions>> {
-        let mut input = String::new();
-
-        for option in &available_options {
-            let option_text = match option {
-                KnownNetworkOptions::Disconnect => self.icons.get_icon_text(
-                    vec![(
-                        "disconnect",
-                        t!("menus.known_network.options.disconnect.name"),
-                    )],
-                    icon_type,
-                    spaces,
-                ),
-                KnownNetworkOptions::Connect => self.icons.get_icon_text(
-                    vec![("connect", t!("menus.known_network.options.connect.name"))],
-                    icon_type,
-                    spaces,
-                ),
-                KnownNetworkOptions::DisableAutoconnect => self.icons.get_icon_text(
-                    vec![(
-                        "disable_autoconnect",
-                        t!("menus.known_network.options.disable_autoconnect.name"),
-                    )],
-                    icon_type,
-                    spaces,
-                ),
-                KnownNetworkOptions::EnableAutoconnect => self.icons.get_icon_text(
-                    vec![(
-                        "enable_autoconnect",
-                        t!("menus.known_network.options.enable_autoconnect.name"),
-                    )],
-                    icon_type,
-                    spaces,
-                ),
-                KnownNetworkOptions::ForgetNetwork => self.icons.get_icon_text(
-                    vec![(
-                        "forget_network",
-                        t!("menus.known_network.options.forget_network.name"),
-                    )],
-                    icon_type,
-                    spaces,
-                ),
-                KnownNetworkOptions::Back => self.icons.get_icon_text(
-                    vec![("back", t!("menus.common.back"))],
-                    icon_type,
-                    spaces,
-                ),
+        let mut entries = MenuEntries::new();
+
+        for option in available_options {
+            let icon_key = match option {
+                KnownNetworkOptions::Disconnect => "disconnect",
+                KnownNetworkOptions::Connect => "connect",
+                KnownNetworkOptions::DisableAutoconnect => "disable_autoconnect",
+                KnownNetworkOptions::EnableAutoconnect => "enable_autoconnect",
+                KnownNetworkOptions::ForgetNetwork => "forget_network",
+                KnownNetworkOptions::ConnectionDetails => "connection_details",
+                KnownNetworkOptions::Share => "share_network",
+                KnownNetworkOptions::UpdatePassphrase => "set_passphrase",
+                KnownNetworkOptions::Back => "back",
             };
-            input.push_str(&format!("{option_text}\n"));
+            entries.push(
+                self.format_option(icon_key, &option.to_str(), icon_type, spaces),
+                option,
+            );
         }
 
         if !back_on_escape {
-            let back_text = self.icons.get_icon_text(
-                vec![("back", t!("menus.common.back"))],
-                icon_type,
-                spaces,
+            entries.push(
+                self.format_option("back", &t!("menus.common.back"), icon_type, spaces),
+                KnownNetworkOptions::Back,
             );
-            input.push_str(&format!("{back_text}\n"));
         }
 
         let hint = t!("menus.known_network.hint", ssid = network_ssid);
 
-        let menu_output =
-            self.run_launcher(menu_command, Some(&input), icon_type, Some(&hint), false)?;
+        self.select(menu_command, entries, icon_type, Some(&hint))
+    }
+
+    fn format_connection_detail(&self, detail: &ConnectionDetail) -> String {
+        format!("{}: {}", detail.label, detail.value)
+    }
 
-        if let Some(output) = menu_output {
-            let cleaned_output = self.clean_menu_output(&output, icon_type);
-            return Ok(KnownNetworkOptions::from_string(&cleaned_output));
+    pub async fn show_connection_details(
+        &self,
+        menu_command: &Option<String>,
+        icon_type: &str,
+        spaces: usize,
+        details: &[ConnectionDetail],
+        title: &str,
+    ) -> Result<Option<ConnectionDetailsOptions>> {
+        let mut entries = MenuEntries::new().with_fallback(ConnectionDetailsOptions::Back);
+
+        for detail in details {
+            entries.push(
+                self.format_connection_detail(detail),
+                ConnectionDetailsOptions::Copy(detail.clone()),
+            );
         }
 
-        Ok(None)
+        entries.push(
+            self.format_option("back", &t!("menus.common.back"), icon_type, spaces),
+            ConnectionDetailsOptions::Back,
+        );
+
+        let hint = t!("menus.connection_details.hint", title = title);
+
+        self.select(menu_command, entries, icon_type, Some(&hint))
     }
 
     pub async fn show_settings_menu(
@@ -496,60 +880,150 @@ impl Menu {
             Mode::Ap => "access_point",
         };
 
-        let mut options = vec![
-            (
-                SettingsMenuOptions::DisableAdapter.to_id(),
-                self.icons.format_display_with_icon(
-                    &SettingsMenuOptions::DisableAdapter.to_str(),
-                    &self.icons.get_icon("disable_adapter", icon_type),
-                    icon_type,
-                    spaces,
-                ),
+        let mut entries = MenuEntries::new();
+
+        entries.push(
+            self.format_option(
+                "saved_networks",
+                &SettingsMenuOptions::SavedNetworks.to_str(),
+                icon_type,
+                spaces,
             ),
-            (
-                SettingsMenuOptions::SwitchMode.to_id(),
-                self.icons.format_display_with_icon(
-                    &switch_mode_text,
-                    &self.icons.get_icon(switch_mode_icon, icon_type),
-                    icon_type,
-                    spaces,
-                ),
+            SettingsMenuOptions::SavedNetworks,
+        );
+        entries.push(
+            self.format_option(
+                "disable_adapter",
+                &SettingsMenuOptions::DisableAdapter.to_str(),
+                icon_type,
+                spaces,
             ),
-        ];
+            SettingsMenuOptions::DisableAdapter,
+        );
+        entries.push(
+            self.format_option(switch_mode_icon, &switch_mode_text, icon_type, spaces),
+            SettingsMenuOptions::SwitchMode,
+        );
 
         if !back_on_escape {
-            options.push((
-                SettingsMenuOptions::Back.to_id(),
-                self.icons.format_display_with_icon(
-                    &t!("menus.common.back"),
-                    &self.icons.get_icon("back", icon_type),
-                    icon_type,
-                    spaces,
-                ),
-            ));
+            entries.push(
+                self.format_option("back", &t!("menus.common.back"), icon_type, spaces),
+                SettingsMenuOptions::Back,
+            );
         }
 
-        let input = options
-            .into_iter()
-            .map(|(_, formatted_text)| formatted_text)
-            .collect::<Vec<String>>()
-            .join("\n");
+        self.select(menu_command, entries, icon_type, None)
+    }
 
-        let menu_output = self.run_launcher(menu_command, Some(&input), icon_type, None, false)?;
+    pub fn format_saved_network_display(
+        &self,
+        known_network: &KnownNetwork,
+        icon_type: &str,
+        spaces: usize,
+    ) -> String {
+        let network_type = match known_network.network_type {
+            NetworkType::Open => "Open",
+            NetworkType::Wep => "WEP",
+            NetworkType::Psk => "PSK",
+            NetworkType::Eap => "802.1X",
+        };
 
-        if let Some(output) = menu_output {
-            let cleaned_output = self.clean_menu_output(&output, icon_type);
+        let mut details = vec![network_type.to_string()];
 
-            if cleaned_output == SettingsMenuOptions::DisableAdapter.to_str() {
-                return Ok(Some(SettingsMenuOptions::DisableAdapter));
-            } else if cleaned_output == switch_mode_text {
-                return Ok(Some(SettingsMenuOptions::SwitchMode));
-            } else if cleaned_output == t!("menus.common.back") {
-                return Ok(Some(SettingsMenuOptions::Back));
-            }
+        if known_network.is_hidden {
+            details.push(t!("menus.saved_networks.hidden").to_string());
+        }
+        if known_network.is_autoconnect {
+            details.push(t!("menus.saved_networks.autoconnect").to_string());
+        }
+        if let Some(last_connected) = known_network
+            .last_connected_time
+            .as_deref()
+            .and_then(|time| time.get(..16))
+        {
+            details.push(last_connected.replace('T', " "));
         }
 
-        Ok(None)
+        let display = format!("{} ({})", known_network.name, details.join(", "));
+
+        self.icons.format_display_with_icon(
+            &display,
+            &self.icons.get_icon("network_wireless", icon_type),
+            icon_type,
+            spaces,
+        )
+    }
+
+    pub async fn show_saved_networks_menu(
+        &self,
+        menu_command: &Option<String>,
+        saved_networks: &[KnownNetwork],
+        icon_type: &str,
+        spaces: usize,
+        back_on_escape: bool,
+    ) -> Result<Option<SavedNetworksMenuOptions>> {
+        let mut entries = MenuEntries::new();
+
+        for known_network in saved_networks {
+            entries.push(
+                self.format_saved_network_display(known_network, icon_type, spaces),
+                SavedNetworksMenuOptions::Network(known_network.clone()),
+            );
+        }
+
+        if !back_on_escape {
+            entries.push(
+                self.format_option("back", &t!("menus.common.back"), icon_type, spaces),
+                SavedNetworksMenuOptions::Back,
+            );
+        }
+
+        let hint = t!("menus.saved_networks.hint");
+
+        self.select(menu_command, entries, icon_type, Some(&hint))
+    }
+
+    pub fn format_device_display(
+        &self,
+        device: &DeviceEntry,
+        icon_type: &str,
+        spaces: usize,
+    ) -> String {
+        let mut details = vec![device.adapter_name.clone()];
+
+        if let Some(model) = &device.adapter_model {
+            details.push(model.clone());
+        }
+
+        let display = format!("{} ({})", device.name, details.join(", "));
+
+        self.icons.format_display_with_icon(
+            &display,
+            &self.icons.get_icon("network_wireless", icon_type),
+            icon_type,
+            spaces,
+        )
+    }
+
+    pub fn select_device(
+        &self,
+        menu_command: &Option<String>,
+        devices: &[DeviceEntry],
+        icon_type: &str,
+        spaces: usize,
+    ) -> Result<Option<DeviceEntry>> {
+        let mut entries = MenuEntries::new();
+
+        for device in devices {
+            entries.push(
+                self.format_device_display(device, icon_type, spaces),
+                device.clone(),
+            );
+        }
+
+        let hint = t!("menus.device.hint");
+
+        self.select(menu_command, entries, icon_type, Some(&hint))
     }
 
     pub fn get_mode_text(&self, mode: &Mode) -> String {
@@ -565,71 +1039,539 @@ impl Menu {
         icon_type: &str,
         spaces: usize,
     ) -> Option<AdapterMenuOptions> {
-        let options = vec![(
-            AdapterMenuOptions::PowerOnDevice.to_id(),
-            AdapterMenuOptions::PowerOnDevice.to_str(),
+        let mut entries = MenuEntries::new();
+
+        entries.push(
+            self.format_option(
+                AdapterMenuOptions::PowerOnDevice.to_id(),
+                &AdapterMenuOptions::PowerOnDevice.to_str(),
+                icon_type,
+                spaces,
+            ),
+            AdapterMenuOptions::PowerOnDevice,
+        );
+
+        self.select(menu_command, entries, icon_type, None)
+            .ok()
+            .flatten()
+    }
+
+    pub async fn show_ap_menu(
+        &self,
+        menu_command: &Option<String>,
+        access_point: &AccessPoint,
+        icon_type: &str,
+        spaces: usize,
+    ) -> Result<Option<ApMenuOptions>> {
+        let mut options = vec![if access_point.has_started {
+            ApMenuOptions::StopAp
+        } else {
+            ApMenuOptions::StartAp
+        }];
+
+        if access_point.has_started {
+            options.push(ApMenuOptions::ConnectedClients);
+        }
+
+        if access_point.can_share() {
+            options.push(ApMenuOptions::Share);
+        }
+
+        options.extend(vec![
+            ApMenuOptions::Profiles,
+            ApMenuOptions::SetSsid,
+            ApMenuOptions::SetPassword,
+            ApMenuOptions::Settings,
+        ]);
+
+        let mut entries = MenuEntries::new();
+
+        for option in options {
+            entries.push(
+                self.format_option(option.to_id(), &option.to_str(), icon_type, spaces),
+                option,
+            );
+        }
+
+        self.select(menu_command, entries, icon_type, None)
+    }
+
+    fn format_ap_client_display(
+        &self,
+        client: &AccessPointClient,
+        icon_type: &str,
+        spaces: usize,
+    ) -> String {
+        let display = match client.rssi {
+            Some(rssi) => format!("{} ({rssi} dBm)", client.address),
+            None => client.address.clone(),
+        };
+
+        self.icons.format_display_with_icon(
+            &display,
+            &self.icons.get_icon("ap_client", icon_type),
+            icon_type,
+            spaces,
+        )
+    }
+
+    pub async fn show_ap_clients(
+        &self,
+        menu_command: &Option<String>,
+        clients: &[AccessPointClient],
+        icon_type: &str,
+        spaces: usize,
+    ) -> Result<Option<ApClientsMenuOptions>> {
+        let mut entries = MenuEntries::new().with_fallback(ApClientsMenuOptions::Back);
+
+        for client in clients {
+            entries.push(
+                self.format_ap_client_display(client, icon_type, spaces),
+                ApClientsMenuOptions::Client(client.clone()),
+            );
+        }
+
+        entries.push(
+            self.format_option("back", &t!("menus.common.back"), icon_type, spaces),
+            ApClientsMenuOptions::Back,
+        );
+
+        let hint = t!("menus.ap.clients.hint", count = clients.len());
+
+        self.select(menu_command, entries, icon_type, Some(&hint))
+    }
+
+    pub async fn show_ap_profiles(
+        &self,
+        menu_command: &Option<String>,
+        profiles: &[String],
+        icon_type: &str,
+        spaces: usize,
+    ) -> Result<Option<ApProfilesMenuOptions>> {
+        let mut entries = MenuEntries::new().with_fallback(ApProfilesMenuOptions::Back);
+
+        entries.push(
+            self.format_option(
+                "create_ap_profile",
+                &t!("menus.ap.profiles.create"),
+                icon_type,
+                spaces,
+            ),
+            ApProfilesMenuOptions::Create,
+        );
+
+        for ssid in profiles {
+            entries.push(
+                self.format_option("access_point", ssid, icon_type, spaces),
+                ApProfilesMenuOptions::Profile(ssid.clone()),
+            );
+        }
+
+        entries.push(
+            self.format_option("back", &t!("menus.common.back"), icon_type, spaces),
+            ApProfilesMenuOptions::Back,
+        );
+
+        let hint = t!("menus.ap.profiles.hint");
+
+        self.select(menu_command, entries, icon_type, Some(&hint))
+    }
+
+    pub fn get_ap_profile_field_text(&self, field: ApProfileField) -> Cow<'static, str> {
+        match field {
+            ApProfileField::Passphrase => t!("menus.ap.profile.fields.passphrase"),
+            ApProfileField::Channel => t!("menus.ap.profile.fields.channel"),
+            ApProfileField::Address => t!("menus.ap.profile.fields.address"),
+            ApProfileField::Gateway => t!("menus.ap.profile.fields.gateway"),
+            ApProfileField::Netmask => t!("menus.ap.profile.fields.netmask"),
+            ApProfileField::DnsList => t!("menus.ap.profile.fields.dns_list"),
+            ApProfileField::LeaseTime => t!("menus.ap.profile.fields.lease_time"),
+        }
+    }
+
+    fn format_ap_profile_field(&self, profile: &ApProfile, field: ApProfileField) -> String {
+        let value = match profile.get(field) {
+            Some(_) if field.is_secret() => "********".into(),
+            Some(value) => Cow::Owned(value.to_string()),
+            None => t!("menus.ap.profile.unset"),
+        };
+
+        format!("{}: {value}", self.get_ap_profile_field_text(field))
+    }
+
+    pub async fn show_ap_profile_options(
+        &self,
+        menu_command: &Option<String>,
+        profile: &ApProfile,
+        icon_type: &str,
+        spaces: usize,
+    ) -> Result<Option<ApProfileOptions>> {
+        let mut entries = MenuEntries::new().with_fallback(ApProfileOptions::Back);
+
+        entries.push(
+            self.format_option("start_ap", &t!("menus.ap.profile.start"), icon_type, spaces),
+            ApProfileOptions::Start,
+        );
+
+        for field in ApProfileField::ALL {
+            entries.push(
+                self.format_ap_profile_field(profile, field),
+                ApProfileOptions::Edit(field),
+            );
+        }
+
+        entries.push(
+            self.format_option(
+                "forget_network",
+                &t!("menus.ap.profile.delete"),
+                icon_type,
+                spaces,
+            ),
+            ApProfileOptions::Delete,
+        );
+        entries.push(
+            self.format_option("back", &t!("menus.common.back"), icon_type, spaces),
+            ApProfileOptions::Back,
+        );
+
+        let hint = t!("menus.ap.profile.hint", ssid = profile.ssid);
+
+        self.select(menu_command, entries, icon_type, Some(&hint))
+    }
+
+    pub fn prompt_ap_profile_field(
+        &self,
+        menu_command: &Option<String>,
+        field: ApProfileField,
+        current_value: Option<&str>,
+        icon_type: &str,
+    ) -> Result<Option<ApProfileFieldEdit>> {
+        let hint = t!(
+            "menus.ap.profile.field_hint",
+            field = self.get_ap_profile_field_text(field)
+        );
+
+        if field.is_secret() {
+            return Ok(self
+                .run_launcher(menu_command, None, icon_type, Some(&hint), true)?
+                .map(ApProfileFieldEdit::Set));
+        }
+
+        // Typing a value works as usual; listing the current one keeps it a click away
+        let mut entries = MenuEntries::new().with_text_fallback(ApProfileFieldEdit::Set);
+        if let Some(value) = current_value {
+            entries.push(
+                value.to_string(),
+                ApProfileFieldEdit::Set(value.to_string()),
+            );
+            entries.push(
+                t!("menus.ap.profile.clear").to_string(),
+                ApProfileFieldEdit::Clear,
+            );
+        }
+
+        // Typed text has no index, so the selection is matched by text
+        Ok(self
+            .run_launcher(
+                menu_command,
+                Some(&entries.input()),
+                icon_type,
+                Some(&hint),
+                false,
+            )?
+            .and_then(|output| entries.resolve(&output, false)))
+    }
+
+    pub fn prompt_station_passphrase(
+        &self,
+        menu_command: &Option<String>,
+        ssid: &str,
+        error_message: Option<&str>,
+        icon_type: &str,
+    ) -> Option<String> {
+        let hint_text = match error_message {
+            Some(error_message) => t!(
+                "menus.auth.passphrase.retry_hint",
+                error_message = error_message
+            ),
+            None => t!("menus.main.options.network.hint", ssid = ssid),
+        };
+        self.run_launcher(menu_command, None, icon_type, Some(&hint_text), true)
+            .ok()
+            .flatten()
+    }
+
+    pub fn prompt_new_passphrase(
+        &self,
+        menu_command: &Option<String>,
+        ssid: &str,
+        error_message: Option<&str>,
+        icon_type: &str,
+    ) -> Option<String> {
+        let hint_text = match error_message {
+            Some(error_message) => t!(
+                "menus.auth.passphrase.retry_hint",
+                error_message = error_message
+            ),
+            None => t!(
+                "menus.known_network.options.update_passphrase.hint",
+                ssid = ssid
+            ),
+        };
+        self.run_launcher(menu_command, None, icon_type, Some(&hint_text), true)
+            .ok()
+            .flatten()
+    }
+
+    pub fn prompt_hidden_network_ssid(
+        &self,
+        menu_command: &Option<String>,
+        icon_type: &str,
+    ) -> Option<String> {
+        let hint_text = t!("menus.main.options.hidden_network.hint");
+        self.run_launcher(menu_command, None, icon_type, Some(&hint_text), false)
+            .ok()
+            .flatten()
+    }
+
+    pub async fn show_easy_connect_menu(
+        &self,
+        menu_command: &Option<String>,
+        can_configure: bool,
+        icon_type: &str,
+        spaces: usize,
+    ) -> Result<Option<EasyConnectMenuOptions>> {
+        let mut options = vec![(
+            "dpp_enrollee",
+            t!("menus.easy_connect.options.enrollee.name"),
+            EasyConnectMenuOptions::Enrollee,
         )];
 
-        let input = self.icons.get_icon_text(options, icon_type, spaces);
+        if can_configure {
+            options.push((
+                "dpp_configurator",
+                t!("menus.easy_connect.options.configurator.name"),
+                EasyConnectMenuOptions::Configurator,
+            ));
+        }
 
-        if let Ok(Some(output)) =
-            self.run_launcher(menu_command, Some(&input), icon_type, None, false)
-        {
-            let cleaned_output = self.clean_menu_output(&output, icon_type);
+        options.push((
+            "back",
+            t!("menus.common.back"),
+            EasyConnectMenuOptions::Back,
+        ));
 
-            if let Some(option) = AdapterMenuOptions::from_string(&cleaned_output) {
-                return Some(option);
-            }
+        let mut entries = MenuEntries::new();
+
+        for (icon_key, text, option) in options {
+            entries.push(
+                self.format_option(icon_key, &text, icon_type, spaces),
+                option,
+            );
         }
 
-        None
+        let hint = t!("menus.easy_connect.hint");
+
+        self.select(menu_command, entries, icon_type, Some(&hint))
     }
 
-    pub async fn show_ap_menu(
+    /// Shows a single stop entry while a long operation runs. Returns once it is selected or
+    /// dismissed, or when the operation ends and closes the launcher.
+    pub fn show_stop_menu(
         &self,
         menu_command: &Option<String>,
-        access_point: &AccessPoint,
+        hint: &str,
         icon_type: &str,
         spaces: usize,
-    ) -> Result<Option<ApMenuOptions>> {
+    ) -> Result<()> {
+        let input =
+            self.icons
+                .get_icon_text(vec![("stop", t!("menus.common.stop"))], icon_type, spaces);
+
+        self.run_launcher(menu_command, Some(&input), icon_type, Some(hint), false)?;
+
+        Ok(())
+    }
+
+    pub async fn show_wps_menu(
+        &self,
+        menu_command: &Option<String>,
+        icon_type: &str,
+        spaces: usize,
+    ) -> Result<Option<WpsMenuOptions>> {
         let options = vec![
-            if access_point.has_started {
-                ("stop_ap", t!("menus.ap.options.stop_ap.name"))
-            } else {
-                ("start_ap", t!("menus.ap.options.start_ap.name"))
-            },
-            ("set_ssid", t!("menus.ap.options.set_ssid.name")),
-            ("set_passphrase", t!("menus.ap.options.set_passphrase.name")),
-            ("settings", t!("menus.ap.options.settings.name")),
+            (
+                "wps_push_button",
+                t!("menus.wps.options.push_button.name"),
+                WpsMenuOptions::PushButton,
+            ),
+            (
+                "wps_generate_pin",
+                t!("menus.wps.options.generate_pin.name"),
+                WpsMenuOptions::GeneratePin,
+            ),
+            (
+                "wps_enter_pin",
+                t!("menus.wps.options.enter_pin.name"),
+                WpsMenuOptions::EnterPin,
+            ),
+            ("back", t!("menus.common.back"), WpsMenuOptions::Back),
         ];
 
-        let input = self.icons.get_icon_text(options, icon_type, spaces);
+        let mut entries = MenuEntries::new();
 
-        let menu_output = self.run_launcher(menu_command, Some(&input), icon_type, None, false)?;
+        for (icon_key, text, option) in options {
+            entries.push(
+                self.format_option(icon_key, &text, icon_type, spaces),
+                option,
+            );
+        }
 
-        if let Some(output) = menu_output {
-            let cleaned_output = self.clean_menu_output(&output, icon_type);
+        let hint = t!("menus.wps.hint");
 
-            if let Some(option) = ApMenuOptions::from_string(&cleaned_output) {
-                return Ok(Some(option));
-            }
-        }
+        self.select(menu_command, entries, icon_type, Some(&hint))
+    }
 
-        Ok(None)
+    pub fn prompt_wps_pin(&self, menu_command: &Option<String>, icon_type: &str) -> Option<String> {
+        let hint_text = t!("menus.wps.pin_hint");
+        self.run_launcher(menu_command, None, icon_type, Some(&hint_text), false)
+            .ok()
+            .flatten()
     }
 
-    pub fn prompt_station_passphrase(
+    pub fn prompt_dpp_uri(&self, menu_command: &Option<String>, icon_type: &str) -> Option<String> {
+        let hint_text = t!("menus.easy_connect.uri_hint");
+        self.run_launcher(menu_command, None, icon_type, Some(&hint_text), false)
+            .ok()
+            .flatten()
+    }
+
+    pub fn prompt_qr_image_path(
+        &self,
+        menu_command: &Option<String>,
+        icon_type: &str,
+    ) -> Option<String> {
+        let hint_text = t!("menus.main.options.qr_code.hint");
+        self.run_launcher(menu_command, None, icon_type, Some(&hint_text), false)
+            .ok()
+            .flatten()
+    }
+
+    pub fn prompt_private_key_passphrase(
         &self,
         menu_command: &Option<String>,
         ssid: &str,
         icon_type: &str,
     ) -> Option<String> {
-        let hint_text = t!("menus.main.options.network.hint", ssid = ssid);
+        let hint_text = t!("menus.auth.private_key_passphrase.hint", ssid = ssid);
         self.run_launcher(menu_command, None, icon_type, Some(&hint_text), true)
             .ok()
             .flatten()
     }
 
+    pub fn prompt_eap_username(
+        &self,
+        menu_command: &Option<String>,
+        ssid: &str,
+        icon_type: &str,
+    ) -> Option<String> {
+        let hint_text = t!("menus.auth.username.hint", ssid = ssid);
+        self.run_launcher(menu_command, None, icon_type, Some(&hint_text), false)
+            .ok()
+            .flatten()
+    }
+
+    pub fn prompt_eap_password(
+        &self,
+        menu_command: &Option<String>,
+        ssid: &str,
+        user_name: Option<&str>,
+        icon_type: &str,
+    ) -> Option<String> {
+        let hint_text = match user_name {
+            Some(user_name) => t!(
+                "menus.auth.password.hint_with_user",
+                ssid = ssid,
+                user_name = user_name
+            ),
+            None => t!("menus.auth.password.hint", ssid = ssid),
+        };
+        self.run_launcher(menu_command, None, icon_type, Some(&hint_text), true)
+            .ok()
+            .flatten()
+    }
+
+    pub fn prompt_eap_method(
+        &self,
+        menu_command: &Option<String>,
+        ssid: &str,
+        icon_type: &str,
+    ) -> Option<EapMethod> {
+        let mut entries = MenuEntries::new();
+
+        for method in EapMethod::ALL {
+            entries.push(method.as_str().to_string(), method);
+        }
+
+        let hint_text = t!("menus.eap.method.hint", ssid = ssid);
+
+        self.select(menu_command, entries, icon_type, Some(&hint_text))
+            .ok()
+            .flatten()
+    }
+
+    pub fn prompt_phase2_method(
+        &self,
+        menu_command: &Option<String>,
+        method: EapMethod,
+        icon_type: &str,
+    ) -> Option<Phase2Method> {
+        let mut entries = MenuEntries::new();
+
+        for phase2_method in method.phase2_methods() {
+            entries.push(phase2_method.as_str().to_string(), *phase2_method);
+        }
+
+        let hint_text = t!("menus.eap.phase2_method.hint", method = method.as_str());
+
+        self.select(menu_command, entries, icon_type, Some(&hint_text))
+            .ok()
+            .flatten()
+    }
+
+    pub fn prompt_eap_identity(
+        &self,
+        menu_command: &Option<String>,
+        icon_type: &str,
+    ) -> Option<String> {
+        let hint_text = t!("menus.eap.identity.hint");
+        self.run_launcher(menu_command, None, icon_type, Some(&hint_text), false)
+            .ok()
+            .flatten()
+    }
+
+    pub fn prompt_eap_ca_cert(
+        &self,
+        menu_command: &Option<String>,
+        icon_type: &str,
+    ) -> Option<String> {
+        let hint_text = t!("menus.eap.ca_cert.hint");
+        self.run_launcher(menu_command, None, icon_type, Some(&hint_text), false)
+            .ok()
+            .flatten()
+    }
+
+    pub fn prompt_eap_domain_mask(
+        &self,
+        menu_command: &Option<String>,
+        icon_type: &str,
+    ) -> Option<String> {
+        let hint_text = t!("menus.eap.domain_mask.hint");
+        self.run_launcher(menu_command, None, icon_type, Some(&hint_text), false)
+            .ok()
+            .flatten()
+    }
+
     pub fn prompt_ap_ssid(&self, menu_command: &Option<String>, icon_type: &str) -> Option<String> {
         let hint_text = t!("menus.ap.options.set_ssid.hint");
         self.run_launcher(menu_command, None, icon_type, Some(&hint_text), false)
@@ -648,3 +1590,81 @@ impl Menu {
             .flatten()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries() -> MenuEntries<&'static str> {
+        let mut entries = MenuEntries::new().with_fallback("fallback");
+        entries.push("Scan".to_string(), "scan");
+        entries.push("Home\0icon\x1fnetwork-wireless".to_string(), "home");
+        entries.push("Settings".to_string(), "settings");
+        entries
+    }
+
+    #[test]
+    fn resolve_by_index() {
+        assert_eq!(entries().resolve("0", true), Some("scan"));
+        assert_eq!(entries().resolve("2\n", true), Some("settings"));
+    }
+
+    #[test]
+    fn resolve_out_of_range_index_uses_fallback() {
+        assert_eq!(entries().resolve("3", true), Some("fallback"));
+        assert_eq!(MenuEntries::<&str>::new().resolve("3", true), None);
+    }
+
+    #[test]
+    fn resolve_negative_index_uses_fallback() {
+        assert_eq!(entries().resolve("-1", true), Some("fallback"));
+    }
+
+    #[test]
+    fn resolve_by_text_ignores_icon_metadata() {
+        assert_eq!(entries().resolve("Home", false), Some("home"));
+        assert_eq!(entries().resolve(" Settings\n", false), Some("settings"));
+        assert_eq!(entries().resolve("typed text", false), Some("fallback"));
+    }
+
+    #[test]
+    fn repeated_lines_are_numbered() {
+        let mut entries = MenuEntries::new();
+        entries.push("Home\0icon\x1fnetwork-wireless".to_string(), "psk");
+        entries.push("Home\0icon\x1fnetwork-wireless".to_string(), "open");
+        entries.push("Home ".to_string(), "wep");
+
+        assert_eq!(
+            entries.lines(),
+            [
+                "Home\0icon\x1fnetwork-wireless",
+                "Home (2)\0icon\x1fnetwork-wireless",
+                "Home (3)",
+            ]
+        );
+        assert_eq!(entries.resolve("Home (2)", false), Some("open"));
+    }
+
+    #[test]
+    fn text_fallback_receives_the_typed_text() {
+        let entries = || {
+            let mut entries = MenuEntries::new().with_text_fallback(ApProfileFieldEdit::Set);
+            entries.push("6".to_string(), ApProfileFieldEdit::Set("6".to_string()));
+            entries.push("Clear".to_string(), ApProfileFieldEdit::Clear);
+            entries
+        };
+
+        assert!(matches!(
+            entries().resolve("Clear", false),
+            Some(ApProfileFieldEdit::Clear)
+        ));
+        assert!(matches!(
+            entries().resolve("6", false),
+            Some(ApProfileFieldEdit::Set(value)) if value == "6"
+        ));
+        assert!(matches!(
+            entries().resolve("11", false),
+            Some(ApProfileFieldEdit::Set(value)) if value == "11"
+        ));
+    }
+}
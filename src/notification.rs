@@ -1,6 +1,7 @@
 use anyhow::{anyhow, Result};
 use notify_rust::{Notification, NotificationHandle, Timeout};
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 
 use crate::icons::Icons;
@@ -28,6 +29,27 @@ impl NotificationManager {
         body: Option<String>,
         icon: Option<&str>,
         timeout: Option<Timeout>,
+    ) -> Result<u32> {
+        self.show(summary, body, icon, None, timeout)
+    }
+
+    pub fn send_image_notification(
+        &self,
+        summary: Option<String>,
+        body: Option<String>,
+        image: &Path,
+        timeout: Option<Timeout>,
+    ) -> Result<u32> {
+        self.show(summary, body, None, Some(image), timeout)
+    }
+
+    fn show(
+        &self,
+        summary: Option<String>,
+        body: Option<String>,
+        icon: Option<&str>,
+        image: Option<&Path>,
+        timeout: Option<Timeout>,
     ) -> Result<u32> {
         let icon_name = self.icons.get_xdg_icon(icon.unwrap_or("network_wireless"));
 
@@ -38,6 +60,10 @@ impl NotificationManager {
             .icon(&icon_name)
             .timeout(timeout.unwrap_or(Timeout::Milliseconds(3000)));
 
+        if let Some(image) = image {
+            notification.image_path(&image.to_string_lossy());
+        }
+
         let handle = notification.show()?;
         let id = handle.id();
 
@@ -50,6 +76,21 @@ impl NotificationManager {
         Ok(id)
     }
 
+    pub fn update_notification(&self, id: u32, body: &str) -> Result<()> {
+        let mut handles = self
+            .handles
+            .lock()
+            .map_err(|e| anyhow!("Failed to acquire lock on notification handles: {e}"))?;
+
+        let handle = handles
+            .get_mut(&id)
+            .ok_or_else(|| anyhow!("Notification ID {id} not found"))?;
+        handle.body(body);
+        handle.update();
+
+        Ok(())
+    }
+
     pub fn close_notification(&self, id: u32) -> Result<()> {
         let mut handles = self
             .handles
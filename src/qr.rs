@@ -0,0 +1,336 @@
+use crate::launcher::Launcher;
+use anyhow::{anyhow, Context, Result};
+use iwdrs::network::NetworkType;
+use qrcode::{render::unicode::Dense1x2, Color, QrCode};
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::BufWriter,
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const MODULE_SIZE: usize = 8;
+const QUIET_ZONE: usize = 4;
+
+/// Builds the `WIFI:` payload understood by phone cameras and most QR scanners.
+pub fn wifi_payload(
+    ssid: &str,
+    network_type: NetworkType,
+    passphrase: Option<&str>,
+    hidden: bool,
+) -> Result<String> {
+    let security = match network_type {
+        NetworkType::Open => None,
+        NetworkType::Wep => Some("WEP"),
+        NetworkType::Psk => Some("WPA"),
+        NetworkType::Eap => {
+            return Err(anyhow!("Enterprise networks cannot be shared as a QR code"));
+        }
+    };
+
+    let mut payload = format!(
+        "WIFI:T:{};S:{};",
+        security.unwrap_or("nopass"),
+        escape(ssid)
+    );
+
+    if security.is_some() {
+        let passphrase = passphrase.ok_or_else(|| anyhow!("No passphrase available for {ssid}"))?;
+        payload.push_str(&format!("P:{};", escape(passphrase)));
+    }
+
+    if hidden {
+        payload.push_str("H:true;");
+    }
+
+    payload.push(';');
+
+    Ok(payload)
+}
+
+/// Network credentials read from a `WIFI:` QR payload.
+#[derive(Debug, Clone)]
+pub struct WifiCredentials {
+    pub ssid: String,
+    pub passphrase: Option<String>,
+    pub hidden: bool,
+}
+
+impl WifiCredentials {
+    pub fn parse(payload: &str) -> Result<Self> {
+        let fields = payload
+            .trim()
+            .strip_prefix("WIFI:")
+            .ok_or_else(|| anyhow!("Not a Wi-Fi QR code"))?;
+
+        let mut ssid = None;
+        let mut passphrase = None;
+        let mut hidden = false;
+
+        for field in split_fields(fields) {
+            let Some((key, value)) = field.split_once(':') else {
+                continue;
+            };
+
+            match key {
+                "S" => ssid = Some(value.to_string()),
+                "P" if !value.is_empty() => passphrase = Some(value.to_string()),
+                "H" => hidden = value.eq_ignore_ascii_case("true"),
+                _ => {}
+            }
+        }
+
+        let ssid = ssid
+            .filter(|ssid| !ssid.is_empty())
+            .ok_or_else(|| anyhow!("The Wi-Fi QR code has no network name"))?;
+
+        Ok(Self {
+            ssid,
+            passphrase,
+            hidden,
+        })
+    }
+
+    /// Decodes the first Wi-Fi QR code found in the PNG or JPEG image at `path`.
+    pub fn from_image(path: &Path) -> Result<Self> {
+        Self::parse(&decode_image(path, "WIFI:")?)
+    }
+}
+
+/// Returns the content of the first QR code in the image at `path` starting with `prefix`.
+pub fn decode_image(path: &Path, prefix: &str) -> Result<String> {
+    let image = image::open(path)
+        .with_context(|| format!("Failed to open image {}", path.display()))?
+        .to_luma8();
+
+    let mut prepared = rqrr::PreparedImage::prepare(image);
+
+    prepared
+        .detect_grids()
+        .iter()
+        .filter_map(|grid| grid.decode().ok())
+        .map(|(_, content)| content)
+        .find(|content| content.starts_with(prefix))
+        .ok_or_else(|| anyhow!("No {prefix} QR code found in {}", path.display()))
+}
+
+/// Runs `command`, split like a launcher command rather than given to a shell, and returns the
+/// image path from the first line it prints.
+pub fn image_path_from_command(command: &str) -> Result<PathBuf> {
+    let (program, args) = Launcher::parse_command(command)?;
+
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run `{command}`"))?;
+
+    if !output.status.success() {
+        return Err(anyhow!("`{command}` exited with {}", output.status));
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(expand_home)
+        .ok_or_else(|| anyhow!("`{command}` did not print an image path"))
+}
+
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Splits on unescaped `;` and removes the escaping backslashes.
+fn split_fields(fields: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut current = String::new();
+    let mut chars = fields.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => current.extend(chars.next()),
+            ';' => result.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+
+    if !current.is_empty() {
+        result.push(current);
+    }
+
+    result
+}
+
+fn escape(value: &str) -> String {
+    value.chars().fold(String::new(), |mut acc, c| {
+        if matches!(c, '\\' | ';' | ',' | ':' | '"') {
+            acc.push('\\');
+        }
+        acc.push(c);
+        acc
+    })
+}
+
+pub struct WifiQr {
+    code: QrCode,
+}
+
+impl WifiQr {
+    pub fn new(payload: &str) -> Result<Self> {
+        let code = QrCode::new(payload.as_bytes()).context("Failed to encode the QR code")?;
+
+        Ok(Self { code })
+    }
+
+    /// Renders the code with half-block characters, light on dark so it scans from a terminal.
+    pub fn to_terminal(&self) -> String {
+        self.code
+            .render::<Dense1x2>()
+            .dark_color(Dense1x2::Light)
+            .light_color(Dense1x2::Dark)
+            .build()
+    }
+
+    /// Writes the code as a grayscale PNG. The file holds the passphrase, so it is only
+    /// readable by the current user.
+    pub fn save_png(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+        }
+
+        let modules = self.code.width();
+        let side = (modules + 2 * QUIET_ZONE) * MODULE_SIZE;
+        let colors = self.code.to_colors();
+
+        let mut pixels = vec![u8::MAX; side * side];
+        for (index, color) in colors.iter().enumerate() {
+            if *color != Color::Dark {
+                continue;
+            }
+
+            let x = (index % modules + QUIET_ZONE) * MODULE_SIZE;
+            let y = (index / modules + QUIET_ZONE) * MODULE_SIZE;
+            for row in y..y + MODULE_SIZE {
+                pixels[row * side + x..row * side + x + MODULE_SIZE].fill(0);
+            }
+        }
+
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+
+        let mut encoder = png::Encoder::new(BufWriter::new(file), side as u32, side as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        encoder
+            .write_header()
+            .and_then(|mut writer| {
+                writer.write_image_data(&pixels)?;
+                writer.finish()
+            })
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(ssid: &str, passphrase: &str, hidden: bool) -> WifiCredentials {
+        let payload = wifi_payload(ssid, NetworkType::Psk, Some(passphrase), hidden).unwrap();
+        WifiCredentials::parse(&payload).unwrap()
+    }
+
+    #[test]
+    fn escape_prefixes_special_characters() {
+        assert_eq!(escape(r#"a;b,c:d\e"f"#), r#"a\;b\,c\:d\\e\"f"#);
+        assert_eq!(escape("plain"), "plain");
+    }
+
+    #[test]
+    fn payload_round_trips_special_characters() {
+        for value in [
+            "semi;colon",
+            "com,ma",
+            "co:lon",
+            r"back\slash",
+            r#"quo"te"#,
+            r#";,:\""#,
+        ] {
+            let credentials = round_trip(value, &format!("pass{value}word"), false);
+
+            assert_eq!(credentials.ssid, value);
+            assert_eq!(
+                credentials.passphrase.as_deref(),
+                Some(format!("pass{value}word").as_str())
+            );
+            assert!(!credentials.hidden);
+        }
+    }
+
+    #[test]
+    fn payload_round_trips_hidden_flag() {
+        assert!(round_trip("Hidden", "password", true).hidden);
+
+        let payload = wifi_payload("Open", NetworkType::Open, None, true).unwrap();
+        let credentials = WifiCredentials::parse(&payload).unwrap();
+
+        assert_eq!(payload, "WIFI:T:nopass;S:Open;H:true;;");
+        assert_eq!(credentials.ssid, "Open");
+        assert_eq!(credentials.passphrase, None);
+        assert!(credentials.hidden);
+    }
+
+    #[test]
+    fn decode_reads_saved_png() {
+        let dir = env::temp_dir().join(format!("iwmenu-qr-{}", std::process::id()));
+        let path = dir.join("share.png");
+        let payload = wifi_payload("Café;Home", NetworkType::Psk, Some("pass:word"), true).unwrap();
+
+        WifiQr::new(&payload).unwrap().save_png(&path).unwrap();
+
+        assert_eq!(decode_image(&path, "WIFI:").unwrap(), payload);
+        assert!(decode_image(&path, "DPP:").is_err());
+
+        let credentials = WifiCredentials::from_image(&path).unwrap();
+        assert_eq!(credentials.ssid, "Café;Home");
+        assert_eq!(credentials.passphrase.as_deref(), Some("pass:word"));
+        assert!(credentials.hidden);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn image_command_runs_without_shell() {
+        assert_eq!(
+            image_path_from_command("printf '\\n%s\\n' '/tmp/a b.png'").unwrap(),
+            PathBuf::from("/tmp/a b.png")
+        );
+        assert_eq!(
+            image_path_from_command("echo '$HOME;' | true").unwrap(),
+            PathBuf::from("$HOME; | true")
+        );
+        assert!(image_path_from_command("false").is_err());
+        assert!(image_path_from_command("").is_err());
+    }
+
+    #[test]
+    fn parse_rejects_payloads_without_network_name() {
+        assert!(WifiCredentials::parse("WIFI:T:WPA;P:password;;").is_err());
+        assert!(WifiCredentials::parse("MECARD:N:Someone;;").is_err());
+    }
+}
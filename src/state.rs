@@ -0,0 +1,68 @@
+use crate::iw::profile::write_profile;
+use anyhow::{Context, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{env, fs, io, path::PathBuf};
+
+pub fn state_dir() -> Option<PathBuf> {
+    env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))
+        .map(|dir| dir.join("iwmenu"))
+}
+
+/// Where the QR code shared for `ssid` is saved.
+pub fn qr_code_path(ssid: &str) -> Option<PathBuf> {
+    let file_name = format!("{}.png", ssid.replace('/', "_"));
+
+    state_dir().map(|dir| dir.join("qr").join(file_name))
+}
+
+/// Where the QR code of the Easy Connect URI is saved.
+pub fn easy_connect_qr_code_path() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join("easy_connect.png"))
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct AccessPointSettings {
+    pub ssid: String,
+    pub passphrase: String,
+}
+
+impl AccessPointSettings {
+    const FILE_NAME: &'static str = "access_point.toml";
+
+    fn path() -> Option<PathBuf> {
+        state_dir().map(|dir| dir.join(Self::FILE_NAME))
+    }
+
+    /// Returns the settings the access point was last started with, if any.
+    pub fn load() -> Option<Self> {
+        let path = Self::path()?;
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                warn!("Failed to read {}: {e}", path.display());
+                return None;
+            }
+        };
+
+        toml::from_str(&contents)
+            .map_err(|e| warn!("Ignoring invalid {}: {e}", path.display()))
+            .ok()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().context("Unable to determine the iwmenu state directory")?;
+
+        let contents =
+            toml::to_string(self).context("Failed to serialize access point settings")?;
+
+        // The passphrase is stored in clear text, keep it private to the user
+        write_profile(&path, &contents)
+    }
+}
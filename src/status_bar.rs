@@ -0,0 +1,254 @@
+use crate::icons::{Icons, SignalTier};
+use crate::iw::adapter::Adapter;
+use clap::ValueEnum;
+use iwdrs::station::State;
+use rust_i18n::t;
+use serde_json::json;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum StatusFormat {
+    Waybar,
+    I3blocks,
+    Plain,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarStatus {
+    Off,
+    AccessPoint,
+    Disconnected,
+    Connecting,
+    Connected,
+}
+
+impl BarStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BarStatus::Off => "off",
+            BarStatus::AccessPoint => "ap",
+            BarStatus::Disconnected => "disconnected",
+            BarStatus::Connecting => "connecting",
+            BarStatus::Connected => "connected",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BarState {
+    pub status: BarStatus,
+    pub name: Option<String>,
+    pub tier: Option<SignalTier>,
+    pub is_secure: bool,
+}
+
+impl BarState {
+    pub fn from_adapter(adapter: &Adapter) -> Self {
+        let device = &adapter.device;
+
+        if !adapter.is_powered || !device.is_powered {
+            return Self::with_status(BarStatus::Off);
+        }
+
+        if let Some(access_point) = &device.access_point {
+            return Self {
+                name: access_point.name.clone(),
+                ..Self::with_status(BarStatus::AccessPoint)
+            };
+        }
+
+        let Some(station) = &device.station else {
+            return Self::with_status(BarStatus::Off);
+        };
+
+        let status = match station.state {
+            State::Connected => BarStatus::Connected,
+            State::Connecting | State::Roaming => BarStatus::Connecting,
+            State::Disconnected | State::Disconnecting => BarStatus::Disconnected,
+        };
+
+        let Some(network) = &station.connected_network else {
+            return Self::with_status(status);
+        };
+
+        let tier = station
+            .known_networks
+            .iter()
+            .find(|(net, _)| net.name == network.name)
+            .map(|(_, signal_strength)| SignalTier::from_strength(*signal_strength));
+
+        Self {
+            status,
+            name: Some(network.name.clone()),
+            tier,
+            is_secure: network.is_secure(),
+        }
+    }
+
+    fn with_status(status: BarStatus) -> Self {
+        Self {
+            status,
+            name: None,
+            tier: None,
+            is_secure: false,
+        }
+    }
+
+    /// XDG icons are given by their single name, as bars look names up themselves.
+    fn icon(&self, icons: &Icons, icon_type: &str) -> String {
+        let icon_key = match (self.status, self.tier) {
+            (BarStatus::Off, _) => "disable_adapter",
+            (BarStatus::AccessPoint, _) => "access_point",
+            (BarStatus::Disconnected, _) => "disconnected",
+            (BarStatus::Connecting, _) => "connect",
+            (BarStatus::Connected, Some(tier)) => tier.icon_key(self.is_secure),
+            (BarStatus::Connected, None) => "connected",
+        };
+
+        match icon_type {
+            "xdg" => icons.get_xdg_icon(icon_key),
+            _ => icons.get_icon(icon_key, icon_type),
+        }
+    }
+
+    fn label(&self) -> String {
+        match (self.status, &self.name) {
+            (BarStatus::Off, _) => t!("status.off").to_string(),
+            (BarStatus::Disconnected, _) => t!("status.disconnected").to_string(),
+            (BarStatus::AccessPoint, None) => t!("modes.access_point").to_string(),
+            (BarStatus::Connecting, None) => t!("status.connecting").to_string(),
+            (_, Some(name)) => name.clone(),
+            (BarStatus::Connected, None) => t!("status.connected").to_string(),
+        }
+    }
+
+    fn tooltip(&self) -> String {
+        match (self.status, &self.name) {
+            (BarStatus::Connected, Some(name)) => {
+                t!("notifications.network.connected", network_name = name).to_string()
+            }
+            _ => self.label(),
+        }
+    }
+
+    fn classes(&self) -> Vec<&'static str> {
+        let mut classes = vec![self.status.as_str()];
+        if let Some(tier) = self.tier {
+            classes.push(tier.as_str());
+        }
+        classes
+    }
+
+    pub fn render(
+        &self,
+        format: StatusFormat,
+        icons: &Icons,
+        icon_type: &str,
+        spaces: usize,
+    ) -> String {
+        let icon = self.icon(icons, icon_type);
+        let text = if icon.is_empty() {
+            self.label()
+        } else {
+            format!("{}{}{}", icon, " ".repeat(spaces), self.label())
+        };
+
+        match format {
+            StatusFormat::Waybar => json!({
+                "text": text,
+                "alt": self.status.as_str(),
+                "tooltip": self.tooltip(),
+                "class": self.classes(),
+            })
+            .to_string(),
+            StatusFormat::I3blocks => json!({
+                "full_text": text,
+                "short_text": icon,
+            })
+            .to_string(),
+            StatusFormat::Plain => text,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn connected() -> BarState {
+        BarState {
+            status: BarStatus::Connected,
+            name: Some("Home".to_string()),
+            tier: Some(SignalTier::Good),
+            is_secure: true,
+        }
+    }
+
+    fn font_icon(key: &str) -> String {
+        Icons::new().get_icon(key, "font")
+    }
+
+    #[test]
+    fn waybar_renders_json_with_classes() {
+        let output = connected().render(StatusFormat::Waybar, &Icons::new(), "font", 1);
+        let output: Value = serde_json::from_str(&output).unwrap();
+
+        assert_eq!(
+            output["text"],
+            format!("{} Home", font_icon("signal_good_secure"))
+        );
+        assert_eq!(output["alt"], "connected");
+        assert_eq!(
+            output["tooltip"],
+            t!("notifications.network.connected", network_name = "Home").to_string()
+        );
+        assert_eq!(output["class"], serde_json::json!(["connected", "good"]));
+    }
+
+    #[test]
+    fn i3blocks_renders_full_and_short_text() {
+        let output = connected().render(StatusFormat::I3blocks, &Icons::new(), "font", 2);
+        let output: Value = serde_json::from_str(&output).unwrap();
+        let icon = font_icon("signal_good_secure");
+
+        assert_eq!(output["full_text"], format!("{icon}  Home"));
+        assert_eq!(output["short_text"], icon);
+        assert_eq!(output.as_object().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn plain_renders_icon_and_label_for_polybar() {
+        let state = BarState::with_status(BarStatus::Disconnected);
+
+        assert_eq!(
+            state.render(StatusFormat::Plain, &Icons::new(), "font", 1),
+            format!(
+                "{} {}",
+                font_icon("disconnected"),
+                t!("status.disconnected")
+            )
+        );
+    }
+
+    #[test]
+    fn render_uses_the_configured_icon_type() {
+        let icons = Icons::new();
+
+        assert_eq!(
+            connected().render(StatusFormat::Plain, &icons, "xdg", 1),
+            format!("{} Home", icons.get_xdg_icon("signal_good_secure"))
+        );
+        assert_ne!(
+            icons.get_xdg_icon("signal_good_secure"),
+            font_icon("signal_good_secure")
+        );
+    }
+
+    #[test]
+    fn render_without_icon_has_no_leading_spaces() {
+        assert_eq!(
+            connected().render(StatusFormat::Plain, &Icons::new(), "none", 1),
+            "Home"
+        );
+    }
+}